@@ -0,0 +1,90 @@
+use gamelib::spatial::SpatialHash;
+use gamelib::utils::Rectangle;
+
+use std::time::{Duration, Instant};
+
+/// Deterministic pseudo-random rectangles, so that runs are comparable.
+fn rectangles(count: usize) -> Vec<Rectangle> {
+    let mut state: u32 = 0x1234_5678;
+
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state as f32 / u32::MAX as f32
+    };
+
+    (0..count)
+        .map(|_| Rectangle::new(next() * 1000.0, next() * 1000.0, 4.0, 4.0))
+        .collect()
+}
+
+fn naive(rectangles: &[Rectangle]) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+
+    for (i, a) in rectangles.iter().enumerate() {
+        for (j, b) in rectangles.iter().enumerate().skip(i + 1) {
+            if a.collides_with(b) {
+                pairs.push((i, j));
+            }
+        }
+    }
+
+    pairs
+}
+
+fn spatial_hash(rectangles: &[Rectangle]) -> Vec<(usize, usize)> {
+    let mut hash = SpatialHash::new(8.0);
+
+    for (i, &rect) in rectangles.iter().enumerate() {
+        hash.insert(i, rect);
+    }
+
+    hash.pairs()
+}
+
+/// Pairs in a canonical order, to compare the results of both methods.
+fn sorted(pairs: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+    let mut pairs: Vec<_> = pairs
+        .into_iter()
+        .map(|(a, b)| (a.min(b), a.max(b)))
+        .collect();
+
+    pairs.sort_unstable();
+    pairs
+}
+
+fn bench(name: &str, count: usize, f: impl Fn() -> Vec<(usize, usize)>) {
+    let iterations = 10;
+    let start = Instant::now();
+    // Summed up and printed so that the work can't be optimized away.
+    let mut pairs = 0;
+
+    for _ in 0..iterations {
+        pairs += f().len();
+    }
+
+    let elapsed: Duration = start.elapsed() / iterations;
+    println!(
+        "{:>14} {:>6} rectangles: {:>10.3?} ({} pairs)",
+        name,
+        count,
+        elapsed,
+        pairs / iterations as usize
+    );
+}
+
+fn main() {
+    for &count in &[100, 1000, 5000] {
+        let rectangles = rectangles(count);
+
+        assert_eq!(
+            sorted(naive(&rectangles)),
+            sorted(spatial_hash(&rectangles)),
+            "the spatial hash disagrees with the naive method"
+        );
+
+        bench("naive", count, || naive(&rectangles));
+        bench("spatial hash", count, || spatial_hash(&rectangles));
+    }
+}
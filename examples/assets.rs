@@ -0,0 +1,94 @@
+use gamelib::assets::{Handle, LoadState};
+use gamelib::audio::Sound;
+use gamelib::render::Texture;
+use gamelib::*;
+
+/// Loads textures and a sound in the background, showing a progress bar until they're ready.
+/// Space plays the sound, U unloads the character and L loads it again.
+struct AssetsDemo {
+    character: Option<Handle<Texture>>,
+    panel: Option<Handle<Texture>>,
+    blip: Option<Handle<Sound>>,
+    missing: Option<Handle<Texture>>,
+}
+
+impl Game for AssetsDemo {
+    fn configure(&self, config: &mut GameConfig) {
+        config.title = "Assets".into();
+    }
+
+    fn init(&mut self, context: &mut Context) {
+        let assets = context.assets();
+
+        self.character = Some(assets.load("examples/textures/ch.png"));
+        self.panel = Some(assets.load("examples/textures/panel.png"));
+        self.blip = Some(assets.load("examples/sounds/blip.wav"));
+        self.missing = Some(assets.load("examples/textures/missing.png"));
+    }
+
+    fn update(&mut self, context: &mut Context) {
+        if let Some(missing) = self.missing.take() {
+            match context.assets().state(&missing) {
+                LoadState::Failed => println!("{}", context.assets().error(&missing).unwrap()),
+                _ => self.missing = Some(missing),
+            }
+        }
+
+        if context.was_pressed(KeyCode::Space) {
+            let blip = self.blip.as_ref().unwrap();
+
+            if let Some(sound) = context.assets().get(blip).cloned() {
+                context.audio().play_sound(&sound).commit();
+            }
+        }
+
+        if context.was_pressed(KeyCode::U) {
+            if let Some(character) = self.character.take() {
+                context.assets().unload(&character);
+            }
+        }
+
+        if context.was_pressed(KeyCode::L) && self.character.is_none() {
+            self.character = Some(context.assets().load("examples/textures/ch.png"));
+        }
+    }
+
+    fn render(&mut self, canvas: &mut Canvas, context: &mut Context) {
+        canvas.clear(0.1, 0.1, 0.1);
+        canvas.size(2.0, 2.0);
+        canvas.fit();
+
+        if context.assets().is_loading() {
+            let progress = context.assets().progress();
+
+            context
+                .render_blank()
+                .translate(-0.5 + progress / 2.0, 0.0)
+                .scale(progress, 0.1)
+                .commit(canvas);
+
+            return;
+        }
+
+        let textures = [(&self.panel, -0.4), (&self.character, 0.4)];
+
+        for (texture, x) in textures.iter() {
+            let builder = texture
+                .as_ref()
+                .and_then(|texture| context.render_texture(texture));
+
+            if let Some(builder) = builder {
+                builder.translate(*x, 0.0).scale(0.5, 0.5).commit(canvas);
+            }
+        }
+    }
+}
+
+fn main() -> Result<(), Error> {
+    run_game(AssetsDemo {
+        character: None,
+        panel: None,
+        blip: None,
+        missing: None,
+    })
+}
@@ -0,0 +1,94 @@
+use gamelib::audio::{Group, Rolloff};
+use gamelib::*;
+
+const BLIP: &str = "examples/sounds/blip.wav";
+const MUSIC: &str = "examples/sounds/music.wav";
+
+/// Space plays a blip where the square is, which Left and Right move: it gets quieter as the square
+/// leaves the screen, and is silent past 8 units from the camera. M toggles the music, Up and Down
+/// change the volume of the sound effects.
+struct AudioDemo {
+    x: f32,
+    pitch: f32,
+    music: bool,
+}
+
+impl Game for AudioDemo {
+    fn configure(&self, config: &mut GameConfig) {
+        config.title = "Audio".into();
+    }
+
+    fn init(&mut self, context: &mut Context) {
+        context.audio().set_rolloff(Rolloff::Linear, 1.0, 8.0);
+        context.audio().play_music(MUSIC, 1.0).unwrap();
+    }
+
+    fn update(&mut self, context: &mut Context) {
+        if context.is_held(KeyCode::Left) {
+            self.x = (self.x - context.delta * 4.0).max(-10.0);
+        }
+
+        if context.is_held(KeyCode::Right) {
+            self.x = (self.x + context.delta * 4.0).min(10.0);
+        }
+
+        if context.was_pressed(KeyCode::Space) {
+            // Go up a semitone on every blip, then back down after an octave.
+            self.pitch *= 2f32.powf(1.0 / 12.0);
+
+            if self.pitch > 2.0 {
+                self.pitch = 1.0;
+            }
+
+            let (x, pitch) = (self.x, self.pitch);
+            context.play(BLIP).unwrap().at(x, 0.0).pitch(pitch).commit();
+        }
+
+        if context.was_pressed(KeyCode::M) {
+            self.music = !self.music;
+
+            if self.music {
+                context.audio().play_music(MUSIC, 1.0).unwrap();
+            } else {
+                context.audio().stop_music(1.0);
+            }
+        }
+
+        let volume = context.audio().volume(Group::Sfx);
+
+        if context.was_pressed(KeyCode::Up) {
+            context
+                .audio()
+                .set_volume(Group::Sfx, (volume + 0.1).min(1.0));
+        }
+
+        if context.was_pressed(KeyCode::Down) {
+            context
+                .audio()
+                .set_volume(Group::Sfx, (volume - 0.1).max(0.0));
+        }
+    }
+
+    fn render(&mut self, canvas: &mut Canvas, context: &mut Context) {
+        canvas.clear(0.1, 0.1, 0.1);
+        canvas.size(4.0, 4.0);
+        canvas.fit();
+
+        let volume = context.audio().volume(Group::Sfx);
+
+        context
+            .render_blank()
+            .scale(0.4, 0.4)
+            .translate(self.x, 0.0)
+            .shade(volume, volume, 1.0)
+            .commit(canvas);
+    }
+}
+
+fn main() -> Result<(), Error> {
+    run_game(AudioDemo {
+        x: 0.0,
+        pitch: 1.0,
+        music: true,
+    })
+}
@@ -1,39 +1,37 @@
+use gamelib::scheduler::TaskId;
 use gamelib::*;
 
 struct Camera {
-    seconds: f32,
+    timer: Option<TaskId>,
     camera_index: usize,
 }
 
 impl Camera {
     fn new() -> Self {
         Self {
-            seconds: 0.0,
+            timer: None,
             camera_index: 0,
         }
     }
 }
 
 impl Game for Camera {
-    fn update(&mut self, context: &mut Context) {
-        self.seconds += context.delta;
-
-        let interval = 1.5;
-
-        while self.seconds >= interval {
-            self.seconds -= interval;
-            self.camera_index += 1;
+    fn init(&mut self, context: &mut Context) {
+        self.timer = Some(context.every(1.5));
+    }
 
-            if self.camera_index == 4 {
-                self.camera_index = 0;
+    fn update(&mut self, context: &mut Context) {
+        if let Some(timer) = self.timer {
+            for _ in 0..context.fired(timer) {
+                self.camera_index = (self.camera_index + 1) % 4;
             }
         }
     }
 
     fn render(&mut self, canvas: &mut Canvas, context: &mut Context) {
-        let positions = vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+        let positions = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
 
-        let colors = vec![
+        let colors = [
             (1.0, 0.0, 0.0),
             (0.0, 1.0, 0.0),
             (0.0, 0.0, 1.0),
@@ -49,7 +47,7 @@ impl Game for Camera {
 
         for i in 0..4 {
             context
-                .render("rect")
+                .render_blank()
                 .translate_tup(positions[i])
                 .shade_tup(colors[i])
                 .commit(canvas);
@@ -57,6 +55,6 @@ impl Game for Camera {
     }
 }
 
-fn main() {
-    run_game(Camera::new());
+fn main() -> Result<(), Error> {
+    run_game(Camera::new())
 }
@@ -0,0 +1,42 @@
+use gamelib::tween::Tween;
+use gamelib::*;
+
+/// F12 saves a screenshot and F10 starts or stops recording a GIF, both into the working
+/// directory. R records a PNG sequence into `frames/` instead.
+struct Capture(Tween<f32>);
+
+impl Game for Capture {
+    fn configure(&self, config: &mut GameConfig) {
+        config.title = "Capture".into();
+        config.screenshot_key = Some(KeyCode::F12);
+        config.record_key = Some(KeyCode::F10);
+    }
+
+    fn update(&mut self, context: &mut Context) {
+        self.0.update(context.delta);
+
+        if context.was_pressed(KeyCode::R) {
+            if context.is_recording() {
+                context.stop_recording();
+            } else {
+                context.start_recording("frames");
+            }
+        }
+    }
+
+    fn render(&mut self, canvas: &mut Canvas, context: &mut Context) {
+        canvas.clear(0.0, 0.0, 0.0);
+        canvas.fit();
+        context
+            .render_blank()
+            .rotate(self.0.value())
+            .scale(2.0, 0.5)
+            .shade(0.0, 1.0, 0.5)
+            .commit(canvas);
+    }
+}
+
+fn main() -> Result<(), Error> {
+    let turn = Tween::new(0.0, 2.0 * std::f32::consts::PI, 2.0).repeat_forever();
+    run_game(Capture(turn))
+}
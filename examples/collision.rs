@@ -0,0 +1,103 @@
+use gamelib::collision::{raycast_first, Obb, Shape};
+use gamelib::utils::{Rectangle, Vec2};
+use gamelib::*;
+
+struct Collision {
+    player: Vec2,
+    obstacles: Vec<Obb>,
+}
+
+impl Collision {
+    fn new() -> Self {
+        Self {
+            player: Vec2::new(-0.6, 0.0),
+            obstacles: vec![
+                Obb::new(Vec2::new(0.3, 0.3), Vec2::new(0.2, 0.1), 0.0),
+                Obb::new(Vec2::new(0.2, -0.4), Vec2::new(0.3, 0.08), 0.6),
+            ],
+        }
+    }
+
+    fn player_shape(&self) -> Shape {
+        Shape::Rectangle(Rectangle::new(
+            self.player.x - 0.05,
+            self.player.y - 0.05,
+            0.1,
+            0.1,
+        ))
+    }
+}
+
+impl Game for Collision {
+    fn update(&mut self, context: &mut Context) {
+        let mut direction = Vec2::ZERO;
+
+        if context.is_held(KeyCode::Left) {
+            direction.x -= 1.0;
+        }
+
+        if context.is_held(KeyCode::Right) {
+            direction.x += 1.0;
+        }
+
+        if context.is_held(KeyCode::Down) {
+            direction.y -= 1.0;
+        }
+
+        if context.is_held(KeyCode::Up) {
+            direction.y += 1.0;
+        }
+
+        self.player += direction.normalize() * 0.5 * context.delta;
+
+        // Push the player out of every obstacle it overlaps.
+        for obstacle in &self.obstacles {
+            if let Some(contact) = Shape::Obb(*obstacle).contact(&self.player_shape()) {
+                self.player += contact.normal * contact.depth;
+            }
+        }
+    }
+
+    fn render(&mut self, canvas: &mut Canvas, context: &mut Context) {
+        canvas.clear(0.1, 0.1, 0.1);
+        canvas.fit();
+
+        for obstacle in &self.obstacles {
+            context
+                .render_blank()
+                .translate(obstacle.center.x, obstacle.center.y)
+                .rotate(obstacle.rotation)
+                .scale(obstacle.half_extents.x * 2.0, obstacle.half_extents.y * 2.0)
+                .shade(0.5, 0.5, 0.5)
+                .commit(canvas);
+        }
+
+        // Laser pointing to the right, stopped by the first obstacle.
+        let obstacles: Vec<_> = self.obstacles.iter().map(|&obb| Shape::Obb(obb)).collect();
+        let direction = Vec2::new(1.0, 0.0);
+
+        let length = match raycast_first(&obstacles, self.player, direction, 2.0) {
+            Some((_, hit)) => hit.fraction,
+            None => 2.0,
+        };
+
+        context
+            .render_blank()
+            .translate(self.player.x, self.player.y)
+            .scale(length, 0.01)
+            .pivot(0.0, 0.5)
+            .shade(1.0, 0.0, 0.0)
+            .commit(canvas);
+
+        context
+            .render_blank()
+            .translate(self.player.x, self.player.y)
+            .scale(0.1, 0.1)
+            .shade(0.0, 1.0, 0.0)
+            .commit(canvas);
+    }
+}
+
+fn main() -> Result<(), Error> {
+    run_game(Collision::new())
+}
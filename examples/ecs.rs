@@ -0,0 +1,92 @@
+use gamelib::collision::Circle;
+use gamelib::ecs::{
+    Collider, CollisionDetector, Schedule, Sprite, SpriteRenderer, System, Transform, World,
+};
+use gamelib::utils::Vec2;
+use gamelib::*;
+
+struct Velocity(Vec2);
+
+fn movement(world: &mut World, context: &mut Context) {
+    let delta = context.delta;
+
+    world.each2::<Transform, Velocity, _>(|_, transform, velocity| {
+        transform.position += velocity.0 * delta;
+
+        // Bounce off the edges of the screen.
+        if transform.position.x.abs() > 0.9 {
+            velocity.0.x = -velocity.0.x;
+        }
+
+        if transform.position.y.abs() > 0.9 {
+            velocity.0.y = -velocity.0.y;
+        }
+    });
+}
+
+/// Turns the balls red while they overlap others.
+struct Highlight;
+
+impl System for Highlight {
+    fn update(&mut self, world: &mut World, _context: &mut Context) {
+        world.each2::<Collider, Sprite, _>(|_, collider, sprite| {
+            sprite.shade = if collider.contacts.is_empty() {
+                (1.0, 1.0, 1.0)
+            } else {
+                (1.0, 0.0, 0.0)
+            };
+        });
+    }
+}
+
+struct Ecs {
+    world: World,
+    schedule: Schedule,
+}
+
+impl Ecs {
+    fn new() -> Self {
+        let mut world = World::new();
+
+        for i in 0..12 {
+            let angle = i as f32 * 0.7;
+            let mut transform = Transform::new();
+            transform.position = Vec2::new(angle.cos() * 0.5, angle.sin() * 0.5);
+
+            world
+                .spawn()
+                .with(transform)
+                .with(Velocity(Vec2::from_angle(angle * 2.0) * 0.4))
+                .with(Sprite::blank(0.1, 0.1))
+                .with(Collider::new(Circle::new(Vec2::ZERO, 0.05)))
+                .build();
+        }
+
+        let mut schedule = Schedule::new();
+
+        schedule
+            .add("movement", movement)
+            .add("highlight", Highlight)
+            .add_before("highlight", "collisions", CollisionDetector::new(0.1))
+            .add("sprites", SpriteRenderer);
+
+        Self { world, schedule }
+    }
+}
+
+impl Game for Ecs {
+    fn update(&mut self, context: &mut Context) {
+        self.schedule.update(&mut self.world, context);
+    }
+
+    fn render(&mut self, canvas: &mut Canvas, context: &mut Context) {
+        canvas.clear(0.1, 0.1, 0.1);
+        canvas.fit();
+
+        self.schedule.render(&mut self.world, canvas, context);
+    }
+}
+
+fn main() -> Result<(), Error> {
+    run_game(Ecs::new())
+}
@@ -0,0 +1,85 @@
+use gamelib::assets::Handle;
+use gamelib::*;
+
+const CONFIG: &str = "examples/data/hot_reload.txt";
+
+/// Run with `--features hot-reload`, then edit the config file, the character texture or the
+/// shaders: the changes show up without restarting.
+struct HotReload {
+    config: Option<Handle<String>>,
+    version: u32,
+    background: (f32, f32, f32),
+    scale: f32,
+}
+
+impl HotReload {
+    /// Parse the config again if it changed since last time.
+    fn apply_config(&mut self, context: &mut Context) {
+        let config = self.config.as_ref().unwrap();
+        let version = context.assets().version(config);
+
+        if version == self.version {
+            return;
+        }
+
+        self.version = version;
+
+        let text = match context.assets().get(config) {
+            Some(text) => text,
+            None => return,
+        };
+
+        let numbers: Vec<f32> = text
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .flat_map(|line| line.split_whitespace())
+            .filter_map(|number| number.parse().ok())
+            .collect();
+
+        if let [red, green, blue, scale] = numbers[..] {
+            self.background = (red, green, blue);
+            self.scale = scale;
+        } else {
+            eprintln!("Expected a color and a scale in {}", CONFIG);
+        }
+    }
+}
+
+impl Game for HotReload {
+    fn configure(&self, config: &mut GameConfig) {
+        config.title = "Hot reload".into();
+        config.shaders = Some((
+            "examples/shaders/sprite.vert".into(),
+            "examples/shaders/sprite.frag".into(),
+        ));
+    }
+
+    fn init(&mut self, context: &mut Context) {
+        self.config = Some(context.assets().load_now(CONFIG).unwrap());
+    }
+
+    fn update(&mut self, context: &mut Context) {
+        self.apply_config(context);
+    }
+
+    fn render(&mut self, canvas: &mut Canvas, context: &mut Context) {
+        let (red, green, blue) = self.background;
+
+        canvas.clear(red, green, blue);
+        canvas.fit();
+
+        context
+            .render("examples/textures/ch.png")
+            .scale(self.scale, self.scale)
+            .commit(canvas);
+    }
+}
+
+fn main() -> Result<(), Error> {
+    run_game(HotReload {
+        config: None,
+        version: 0,
+        background: (0.0, 0.0, 0.0),
+        scale: 1.0,
+    })
+}
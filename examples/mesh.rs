@@ -0,0 +1,80 @@
+use gamelib::*;
+
+struct Mesh {
+    angle: f32,
+    triangle: Option<Model>,
+    trail: Option<Model>,
+}
+
+impl Mesh {
+    fn new() -> Self {
+        Self {
+            angle: 0.0,
+            triangle: None,
+            trail: None,
+        }
+    }
+
+    fn trail_builder(&self) -> MeshBuilder<'static> {
+        let mut builder = MeshBuilder::new(PrimitiveType::LineStrip).dynamic();
+
+        for i in 0..32 {
+            let angle = self.angle - i as f32 * 0.05;
+            let fade = 1.0 - i as f32 / 32.0;
+
+            builder = builder
+                .vertex(angle.cos() * 0.75, angle.sin() * 0.75)
+                .color(fade, fade, fade);
+        }
+
+        builder
+    }
+}
+
+impl Game for Mesh {
+    fn init(&mut self, context: &mut Context) {
+        let triangle = MeshBuilder::new(PrimitiveType::TrianglesList)
+            .vertex(-0.5, -0.5)
+            .color(1.0, 0.0, 0.0)
+            .vertex(0.5, -0.5)
+            .color(0.0, 1.0, 0.0)
+            .vertex(0.0, 0.5)
+            .color(0.0, 0.0, 1.0)
+            .indices(&[0, 1, 2]);
+
+        self.triangle = Some(context.build_mesh(&triangle).unwrap());
+        self.trail = Some(context.build_mesh(&self.trail_builder()).unwrap());
+    }
+
+    fn update(&mut self, context: &mut Context) {
+        self.angle += context.delta * std::f32::consts::PI;
+
+        let builder = self.trail_builder();
+
+        if let Some(trail) = &mut self.trail {
+            context.update_mesh(trail, &builder).unwrap();
+        }
+    }
+
+    fn render(&mut self, canvas: &mut Canvas, _context: &mut Context) {
+        canvas.clear(0.1, 0.1, 0.1);
+        canvas.size(2.0, 2.0);
+        canvas.fit();
+
+        if let Some(triangle) = &self.triangle {
+            ModelRenderBuilder::new(triangle)
+                .shade(1.0, 1.0, 1.0)
+                .commit(canvas);
+        }
+
+        if let Some(trail) = &self.trail {
+            ModelRenderBuilder::new(trail)
+                .shade(1.0, 1.0, 0.0)
+                .commit(canvas);
+        }
+    }
+}
+
+fn main() -> Result<(), Error> {
+    run_game(Mesh::new())
+}
@@ -0,0 +1,38 @@
+use gamelib::*;
+
+struct NineSlice(f32);
+
+impl Game for NineSlice {
+    fn update(&mut self, context: &mut Context) {
+        self.0 += context.delta;
+    }
+
+    fn render(&mut self, canvas: &mut Canvas, context: &mut Context) {
+        canvas.clear(0.1, 0.1, 0.1);
+        canvas.size(640.0, 420.0);
+        canvas.fit();
+
+        let width = 200.0 + self.0.sin() * 100.0;
+
+        context
+            .render_nine_slice("examples/textures/panel.png")
+            .translate(-150.0, 0.0)
+            .size(width, 150.0)
+            .insets(8.0, 8.0, 8.0, 8.0)
+            .texel_size(2.0)
+            .commit(canvas);
+
+        context
+            .render_nine_slice("examples/textures/panel.png")
+            .translate(150.0, 0.0)
+            .size(width, 150.0)
+            .insets(8.0, 8.0, 8.0, 8.0)
+            .texel_size(2.0)
+            .tile()
+            .commit(canvas);
+    }
+}
+
+fn main() -> Result<(), Error> {
+    run_game(NineSlice(0.0))
+}
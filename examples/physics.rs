@@ -0,0 +1,94 @@
+use gamelib::collision::Shape;
+use gamelib::physics::{Body, BodyHandle, BodyType, CollisionEventKind, World};
+use gamelib::utils::{Rectangle, Vec2};
+use gamelib::*;
+
+struct Physics {
+    world: World,
+    boxes: Vec<BodyHandle>,
+    ground: BodyHandle,
+    hits: usize,
+}
+
+impl Physics {
+    fn new() -> Self {
+        let mut world = World::new(Vec2::new(0.0, -2.0));
+
+        let ground = world.add(
+            Body::new(
+                BodyType::Static,
+                Shape::Rectangle(Rectangle::new(-0.8, -0.05, 1.6, 0.1)),
+            )
+            .with_position(0.0, -0.7),
+        );
+
+        Self {
+            world,
+            boxes: Vec::new(),
+            ground,
+            hits: 0,
+        }
+    }
+
+    fn spawn(&mut self) {
+        let x = (self.boxes.len() as f32 * 0.37).sin() * 0.5;
+
+        let handle = self.world.add(
+            Body::new(
+                BodyType::Dynamic,
+                Shape::Rectangle(Rectangle::new(-0.05, -0.05, 0.1, 0.1)),
+            )
+            .with_position(x, 0.8)
+            .with_restitution(0.3),
+        );
+
+        self.boxes.push(handle);
+    }
+}
+
+impl Game for Physics {
+    fn configure(&self, config: &mut GameConfig) {
+        config.update_fps = 60.0;
+    }
+
+    fn update(&mut self, context: &mut Context) {
+        if context.was_pressed(KeyCode::Space) {
+            self.spawn();
+        }
+
+        self.world.step(context.delta);
+
+        for event in self.world.drain_events() {
+            if event.kind == CollisionEventKind::Started && event.a == self.ground {
+                self.hits += 1;
+            }
+        }
+    }
+
+    fn render(&mut self, canvas: &mut Canvas, context: &mut Context) {
+        canvas.clear(0.1, 0.1, 0.1);
+        canvas.fit();
+
+        for (handle, body) in self.world.bodies() {
+            let bounds = body.world_shape().bounds();
+            let center = bounds.center();
+
+            let color = if handle == self.ground {
+                (0.5, 0.5, 0.5)
+            } else {
+                (1.0, 0.5, 0.0)
+            };
+
+            context
+                .render_blank()
+                .translate(center.x, center.y)
+                .scale(bounds.w, bounds.h)
+                .shade_tup(color)
+                .commit(canvas);
+        }
+    }
+}
+
+fn main() -> Result<(), Error> {
+    run_game(Physics::new())
+}
@@ -0,0 +1,129 @@
+use gamelib::assets::Handle;
+use gamelib::render::Texture;
+use gamelib::*;
+
+const SIZE: u32 = 64;
+const SIGHT: i32 = 4;
+
+/// A map generated at startup, covered by fog that the arrow keys reveal as the character walks
+/// around. The character texture is built into the executable and loaded from memory.
+struct Pixels {
+    map: Option<Handle<Texture>>,
+    fog: Option<Handle<Texture>>,
+    character: Option<Handle<Texture>>,
+    position: (i32, i32),
+}
+
+impl Pixels {
+    /// Clear the fog in a square around the character.
+    fn reveal(&self, context: &mut Context) {
+        let (x, y) = self.position;
+        let left = (x - SIGHT).max(0) as u32;
+        let top = (y - SIGHT).max(0) as u32;
+        let right = (x + SIGHT + 1).min(SIZE as i32) as u32;
+        let bottom = (y + SIGHT + 1).min(SIZE as i32) as u32;
+
+        let clear = vec![0; ((right - left) * (bottom - top) * 4) as usize];
+        let fog = context
+            .assets()
+            .get_mut(self.fog.as_ref().unwrap())
+            .unwrap();
+        fog.write_region(left, top, right - left, bottom - top, &clear)
+            .unwrap();
+    }
+}
+
+/// Water, sand and grass, from a cheap interference pattern.
+fn generate_map() -> Vec<u8> {
+    let mut pixels = Vec::new();
+
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let (x, y) = (x as f32 / 6.0, y as f32 / 6.0);
+            let height = (x.sin() + y.cos() + (x * 0.6 + y * 0.8).sin()) / 3.0;
+
+            let color = if height < -0.1 {
+                [40, 80, 160]
+            } else if height < 0.05 {
+                [200, 190, 120]
+            } else {
+                [60, 140, 60]
+            };
+
+            pixels.extend_from_slice(&color);
+            pixels.push(255);
+        }
+    }
+
+    pixels
+}
+
+impl Game for Pixels {
+    fn configure(&self, config: &mut GameConfig) {
+        config.title = "Pixels".into();
+    }
+
+    fn init(&mut self, context: &mut Context) {
+        let assets = context.assets();
+        let fog = [0, 0, 0, 255].repeat((SIZE * SIZE) as usize);
+
+        self.map = Some(assets.create_texture(SIZE, SIZE, &generate_map()).unwrap());
+        self.fog = Some(assets.create_texture(SIZE, SIZE, &fog).unwrap());
+
+        let bytes = include_bytes!("textures/ch.png").to_vec();
+        self.character = Some(assets.load_bytes("ch.png", bytes).unwrap());
+
+        self.reveal(context);
+    }
+
+    fn update(&mut self, context: &mut Context) {
+        let moves = [
+            (KeyCode::Left, (-1, 0)),
+            (KeyCode::Right, (1, 0)),
+            (KeyCode::Up, (0, -1)),
+            (KeyCode::Down, (0, 1)),
+        ];
+
+        for (key, (dx, dy)) in moves.iter() {
+            if context.was_pressed(*key) {
+                let (x, y) = self.position;
+                self.position = (
+                    (x + dx).max(0).min(SIZE as i32 - 1),
+                    (y + dy).max(0).min(SIZE as i32 - 1),
+                );
+                self.reveal(context);
+            }
+        }
+    }
+
+    fn render(&mut self, canvas: &mut Canvas, context: &mut Context) {
+        canvas.clear(0.0, 0.0, 0.0);
+        canvas.size(SIZE as f32, SIZE as f32);
+        canvas.fit();
+
+        for texture in [&self.map, &self.fog].iter() {
+            if let Some(builder) = context.render_texture(texture.as_ref().unwrap()) {
+                builder.scale(SIZE as f32, SIZE as f32).commit(canvas);
+            }
+        }
+
+        let (x, y) = self.position;
+        let half = SIZE as f32 / 2.0;
+
+        if let Some(builder) = context.render_texture(self.character.as_ref().unwrap()) {
+            builder
+                .translate(x as f32 + 0.5 - half, half - y as f32 - 0.5)
+                .scale(2.0, 2.0)
+                .commit(canvas);
+        }
+    }
+}
+
+fn main() -> Result<(), Error> {
+    run_game(Pixels {
+        map: None,
+        fog: None,
+        character: None,
+        position: (SIZE as i32 / 2, SIZE as i32 / 2),
+    })
+}
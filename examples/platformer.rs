@@ -0,0 +1,145 @@
+use gamelib::controller::{CharacterController, Tile, TileMap};
+use gamelib::utils::Rectangle;
+use gamelib::*;
+
+struct Platformer {
+    map: TileMap,
+    player: CharacterController,
+    jump_held: bool,
+    slope_up: Option<Model>,
+    slope_down: Option<Model>,
+}
+
+impl Platformer {
+    fn new() -> Self {
+        let map = TileMap::from_rows(
+            &[
+                "################",
+                "#..............#",
+                "#..............#",
+                "#.......---....#",
+                "#..............#",
+                "#...---......###",
+                "#...........####",
+                "#......#/..#####",
+                "#.....##########",
+                "################",
+            ],
+            1.0,
+        );
+
+        Self {
+            map,
+            player: CharacterController::new(Rectangle::new(2.0, 1.0, 0.8, 0.9)),
+            jump_held: false,
+            slope_up: None,
+            slope_down: None,
+        }
+    }
+}
+
+fn triangle(a: (f32, f32), b: (f32, f32), c: (f32, f32)) -> MeshBuilder<'static> {
+    MeshBuilder::new(PrimitiveType::TrianglesList)
+        .vertex_tup(a)
+        .vertex_tup(b)
+        .vertex_tup(c)
+        .indices(&[0, 1, 2])
+}
+
+impl Game for Platformer {
+    fn init(&mut self, context: &mut Context) {
+        let up = triangle((-0.5, -0.5), (0.5, -0.5), (0.5, 0.5));
+        let down = triangle((-0.5, -0.5), (0.5, -0.5), (-0.5, 0.5));
+
+        self.slope_up = Some(context.build_mesh(&up).unwrap());
+        self.slope_down = Some(context.build_mesh(&down).unwrap());
+    }
+
+    fn update(&mut self, context: &mut Context) {
+        let mut direction = 0.0;
+
+        if context.is_held(KeyCode::Left) {
+            direction -= 1.0;
+        }
+
+        if context.is_held(KeyCode::Right) {
+            direction += 1.0;
+        }
+
+        self.player.velocity.x = direction * 6.0;
+
+        // Releasing the key early makes for a shorter jump.
+        let jump_held = context.is_held(KeyCode::Space);
+
+        if jump_held && !self.jump_held {
+            self.player.jump();
+        } else if !jump_held && self.jump_held {
+            self.player.release_jump();
+        }
+
+        self.jump_held = jump_held;
+
+        if context.was_pressed(KeyCode::Down) {
+            self.player.drop_through();
+        }
+
+        self.player.update(context.delta, &self.map);
+    }
+
+    fn render(&mut self, canvas: &mut Canvas, context: &mut Context) {
+        canvas.clear(0.1, 0.1, 0.1);
+        canvas.size(16.0, 10.0);
+        canvas.bottom_left();
+        canvas.fit();
+
+        for row in 0..self.map.rows() {
+            for column in 0..self.map.columns() {
+                let bounds = self.map.tile_bounds(column, row);
+                let center = bounds.center();
+
+                let slope = match self.map.get(column, row) {
+                    Tile::Empty => continue,
+                    Tile::Solid => None,
+                    Tile::OneWay => {
+                        context
+                            .render_blank()
+                            .translate(center.x, bounds.y + bounds.h * 0.9)
+                            .scale(bounds.w, bounds.h * 0.2)
+                            .shade(0.4, 0.3, 0.2)
+                            .commit(canvas);
+                        continue;
+                    }
+                    Tile::SlopeUp => self.slope_up.as_ref(),
+                    Tile::SlopeDown => self.slope_down.as_ref(),
+                };
+
+                match slope {
+                    Some(model) => ModelRenderBuilder::new(model),
+                    None => context.render_blank(),
+                }
+                .translate(center.x, center.y)
+                .scale(bounds.w, bounds.h)
+                .shade(0.5, 0.5, 0.5)
+                .commit(canvas);
+            }
+        }
+
+        let bounds = self.player.bounds;
+        let shade = if self.player.is_grounded() {
+            (0.0, 1.0, 0.0)
+        } else {
+            (1.0, 1.0, 0.0)
+        };
+
+        context
+            .render_blank()
+            .translate_tup(bounds.center().into())
+            .scale(bounds.w, bounds.h)
+            .shade_tup(shade)
+            .commit(canvas);
+    }
+}
+
+fn main() -> Result<(), Error> {
+    run_game(Platformer::new())
+}
@@ -0,0 +1,56 @@
+use gamelib::node::Node;
+use gamelib::*;
+
+struct SceneGraph {
+    root: Node,
+}
+
+impl SceneGraph {
+    fn new() -> Self {
+        let moon = Node::named("moon")
+            .with_position(0.3, 0.0)
+            .with_blank_sprite(0.05, 0.05)
+            .with_shade(0.8, 0.8, 0.8);
+
+        let planet = Node::named("planet")
+            .with_position(0.6, 0.0)
+            .with_blank_sprite(0.15, 0.15)
+            .with_shade(0.0, 0.5, 1.0)
+            .with_child(moon);
+
+        let root = Node::named("sun")
+            .with_blank_sprite(0.3, 0.3)
+            .with_shade(1.0, 0.8, 0.0)
+            .with_child(planet);
+
+        Self { root }
+    }
+}
+
+impl Game for SceneGraph {
+    fn update(&mut self, context: &mut Context) {
+        let delta = context.delta;
+
+        self.root.transform.rotation += delta * 0.5;
+
+        if let Some(planet) = self.root.find_mut("planet") {
+            planet.transform.rotation += delta * 2.0;
+        }
+
+        if context.was_pressed(KeyCode::Space) {
+            if let Some(moon) = self.root.find_mut("moon") {
+                moon.visible = !moon.visible;
+            }
+        }
+    }
+
+    fn render(&mut self, canvas: &mut Canvas, context: &mut Context) {
+        canvas.clear(0.0, 0.0, 0.1);
+        canvas.fit();
+        self.root.render(canvas, context);
+    }
+}
+
+fn main() -> Result<(), Error> {
+    run_game(SceneGraph::new())
+}
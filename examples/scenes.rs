@@ -0,0 +1,114 @@
+use gamelib::scene::{Scene, SceneStack, Transition};
+use gamelib::transition::{Effect, WipeDirection};
+use gamelib::*;
+
+/// Press Space to start.
+struct Title {
+    seconds: f32,
+}
+
+impl Scene for Title {
+    fn update(&mut self, context: &mut Context) -> Transition {
+        self.seconds += context.delta;
+
+        if context.was_pressed(KeyCode::Space) {
+            Transition::replace(Gameplay { x: 0.0 }).with_effect(Effect::Fade((0.0, 0.0, 0.0)), 1.0)
+        } else {
+            Transition::None
+        }
+    }
+
+    fn render(&mut self, canvas: &mut Canvas, context: &mut Context) {
+        canvas.clear(0.1, 0.1, 0.3);
+        canvas.fit();
+
+        let pulse = 1.0 + (self.seconds * 4.0).sin() * 0.1;
+
+        context
+            .render_blank()
+            .scale(0.6 * pulse, 0.2 * pulse)
+            .shade(1.0, 1.0, 1.0)
+            .commit(canvas);
+    }
+}
+
+/// Move with the arrow keys, press Escape to pause.
+struct Gameplay {
+    x: f32,
+}
+
+impl Scene for Gameplay {
+    fn on_enter(&mut self, _context: &mut Context) {
+        println!("Playing");
+    }
+
+    fn on_exit(&mut self, _context: &mut Context) {
+        println!("Paused");
+    }
+
+    fn update(&mut self, context: &mut Context) -> Transition {
+        if context.is_held(KeyCode::Left) {
+            self.x -= context.delta;
+        }
+
+        if context.is_held(KeyCode::Right) {
+            self.x += context.delta;
+        }
+
+        if context.was_pressed(KeyCode::Escape) {
+            Transition::push(Pause).with_effect(Effect::Wipe(WipeDirection::Down), 0.3)
+        } else {
+            Transition::None
+        }
+    }
+
+    fn render(&mut self, canvas: &mut Canvas, context: &mut Context) {
+        canvas.clear(0.1, 0.1, 0.1);
+        canvas.fit();
+
+        context
+            .render_blank()
+            .translate(self.x, 0.0)
+            .scale(0.2, 0.2)
+            .shade(0.0, 1.0, 0.0)
+            .commit(canvas);
+    }
+}
+
+/// Drawn over the paused gameplay. Escape resumes, Q goes back to the title screen.
+struct Pause;
+
+impl Scene for Pause {
+    fn update(&mut self, context: &mut Context) -> Transition {
+        if context.was_pressed(KeyCode::Escape) {
+            Transition::Pop.with_effect(Effect::Crossfade, 0.3)
+        } else if context.was_pressed(KeyCode::Q) {
+            Transition::reset(Title { seconds: 0.0 }).with_effect(Effect::Iris, 0.8)
+        } else {
+            Transition::None
+        }
+    }
+
+    fn render(&mut self, canvas: &mut Canvas, context: &mut Context) {
+        for x in &[-0.1, 0.1] {
+            context
+                .render_blank()
+                .translate(*x, 0.0)
+                .scale(0.1, 0.4)
+                .shade(1.0, 1.0, 1.0)
+                .commit(canvas);
+        }
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
+
+fn main() -> Result<(), Error> {
+    let scenes = SceneStack::new(Title { seconds: 0.0 }).with_config(|config| {
+        config.title = "Scenes".into();
+    });
+
+    run_game(scenes)
+}
@@ -0,0 +1,22 @@
+#version 140
+
+uniform sampler2D tex;
+
+in vec3 v_shade;
+in vec3 v_color;
+in vec2 v_tex_coords;
+
+out vec4 f_color;
+
+void main() {
+    // Use solid color instead of dummy 1x1 texture.
+    if (textureSize(tex, 0) == vec2(1, 1)) {
+        f_color = vec4(v_shade, 1.0);
+    } else {
+        // Try another mix, or a grayscale version of the texture.
+        vec4 t_color = texture(tex, v_tex_coords);
+        f_color = mix(t_color, vec4(v_shade, 1.0), 0.5);
+    }
+
+    f_color *= vec4(v_color, 1.0);
+}
@@ -0,0 +1,22 @@
+#version 140
+
+uniform mat4 mvp;
+uniform vec3 shade;
+uniform vec3 tint;
+uniform vec2 uv_offset;
+uniform vec2 uv_size;
+
+in vec2 position;
+in vec2 tex_coords;
+in vec3 color;
+
+out vec3 v_shade;
+out vec3 v_color;
+out vec2 v_tex_coords;
+
+void main() {
+    gl_Position = vec4(position, 0.0, 1.0) * mvp;
+    v_shade = shade;
+    v_color = color * tint;
+    v_tex_coords = uv_offset + tex_coords * uv_size;
+}
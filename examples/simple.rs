@@ -38,8 +38,8 @@ impl Game for Simple {
         canvas.size(1.0, 1.0);
         canvas.fit();
         context
-            .render("rect")
-            .translate(x - size / 2.0, y - size / 2.0)
+            .render_blank()
+            .translate(x, y)
             .scale(size, size)
             .rotate(self.angle)
             .shade(1.0, 0.0, 0.0)
@@ -47,6 +47,6 @@ impl Game for Simple {
     }
 }
 
-fn main() {
-    run_game(Simple::new());
+fn main() -> Result<(), Error> {
+    run_game(Simple::new())
 }
@@ -1,24 +1,26 @@
+use gamelib::tween::Tween;
 use gamelib::*;
 
-struct Spin(f32);
+struct Spin(Tween<f32>);
 
 impl Game for Spin {
     fn update(&mut self, context: &mut Context) {
-        self.0 += context.delta * std::f32::consts::PI;
+        self.0.update(context.delta);
     }
 
     fn render(&mut self, canvas: &mut Canvas, context: &mut Context) {
         canvas.clear(0.0, 0.0, 0.0);
         canvas.fit();
         context
-            .render("rect")
-            .rotate(self.0)
+            .render_blank()
+            .rotate(self.0.value())
             .scale(2.0, 0.5)
             .shade(0.0, 1.0, 0.5)
             .commit(canvas);
     }
 }
 
-fn main() {
-    run_game(Spin(0.0));
+fn main() -> Result<(), Error> {
+    let turn = Tween::new(0.0, 2.0 * std::f32::consts::PI, 2.0).repeat_forever();
+    run_game(Spin(turn))
 }
@@ -0,0 +1,89 @@
+use gamelib::synth::Synth;
+use gamelib::*;
+
+const SAMPLE_RATE: u32 = 44100;
+
+/// Keys 1 to 7 play the synth presets; V plays a different variation of the explosion every time,
+/// and S saves the last one as a WAV file.
+struct SynthDemo {
+    presets: Vec<(KeyCode, &'static str, Synth)>,
+    variation: u64,
+    last: Synth,
+    flash: f32,
+}
+
+impl SynthDemo {
+    fn new() -> Self {
+        Self {
+            presets: vec![
+                (KeyCode::Key1, "pickup", Synth::pickup()),
+                (KeyCode::Key2, "laser", Synth::laser()),
+                (KeyCode::Key3, "explosion", Synth::explosion()),
+                (KeyCode::Key4, "powerup", Synth::powerup()),
+                (KeyCode::Key5, "hit", Synth::hit()),
+                (KeyCode::Key6, "jump", Synth::jump()),
+                (KeyCode::Key7, "blip", Synth::blip()),
+            ],
+            variation: 0,
+            last: Synth::blip(),
+            flash: 0.0,
+        }
+    }
+}
+
+impl Game for SynthDemo {
+    fn configure(&self, config: &mut GameConfig) {
+        config.title = "Synth".into();
+    }
+
+    fn init(&mut self, context: &mut Context) {
+        for (_, name, synth) in &self.presets {
+            context.add_sound(name, synth.to_sound(SAMPLE_RATE));
+        }
+    }
+
+    fn update(&mut self, context: &mut Context) {
+        self.flash = (self.flash - context.delta * 4.0).max(0.0);
+
+        for (key, name, synth) in &self.presets {
+            if context.was_pressed(*key) {
+                context.play(name).unwrap().commit();
+                self.last = *synth;
+                self.flash = 1.0;
+            }
+        }
+
+        if context.was_pressed(KeyCode::V) {
+            self.variation += 1;
+            self.last = Synth::explosion().mutate(0.3, self.variation);
+            self.flash = 1.0;
+
+            let sound = self.last.to_sound(SAMPLE_RATE);
+            context.audio().play_sound(&sound).commit();
+        }
+
+        if context.was_pressed(KeyCode::S) {
+            match self.last.save_wav("synth.wav", SAMPLE_RATE) {
+                Ok(()) => println!("Saved synth.wav"),
+                Err(error) => eprintln!("{}", error),
+            }
+        }
+    }
+
+    fn render(&mut self, canvas: &mut Canvas, context: &mut Context) {
+        canvas.clear(0.1, 0.1, 0.1);
+        canvas.fit();
+
+        let size = 0.3 + self.flash * 0.2;
+
+        context
+            .render_blank()
+            .scale(size, size)
+            .shade(1.0, 0.8, 0.3)
+            .commit(canvas);
+    }
+}
+
+fn main() -> Result<(), Error> {
+    run_game(SynthDemo::new())
+}
@@ -11,6 +11,6 @@ impl Game for Texture {
     }
 }
 
-fn main() {
-    run_game(Texture);
+fn main() -> Result<(), Error> {
+    run_game(Texture)
 }
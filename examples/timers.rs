@@ -0,0 +1,66 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use gamelib::*;
+
+/// A square blinking three times, then sliding to the right, scripted with a coroutine.
+struct Timers {
+    visible: Rc<Cell<bool>>,
+    x: Rc<Cell<f32>>,
+}
+
+impl Game for Timers {
+    fn init(&mut self, context: &mut Context) {
+        let visible = self.visible.clone();
+        let x = self.x.clone();
+
+        context.spawn(|co| async move {
+            loop {
+                for _ in 0..3 {
+                    visible.set(false);
+                    co.wait(0.2).await;
+                    visible.set(true);
+                    co.wait(0.2).await;
+                }
+
+                while x.get() < 0.5 {
+                    x.set(x.get() + 0.02);
+                    co.next_update().await;
+                }
+
+                co.wait(1.0).await;
+                x.set(-0.5);
+            }
+        });
+
+        context.run_after(5.0, |_| println!("Five seconds in"));
+
+        let mut seconds = 0;
+
+        context.run_every(1.0, move |_| {
+            seconds += 1;
+            println!("{} s", seconds);
+        });
+    }
+
+    fn render(&mut self, canvas: &mut Canvas, context: &mut Context) {
+        canvas.clear(0.1, 0.1, 0.1);
+        canvas.fit();
+
+        if self.visible.get() {
+            context
+                .render_blank()
+                .translate(self.x.get(), 0.0)
+                .scale(0.2, 0.2)
+                .shade(1.0, 0.5, 0.0)
+                .commit(canvas);
+        }
+    }
+}
+
+fn main() -> Result<(), Error> {
+    run_game(Timers {
+        visible: Rc::new(Cell::new(true)),
+        x: Rc::new(Cell::new(-0.5)),
+    })
+}
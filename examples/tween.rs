@@ -0,0 +1,99 @@
+use gamelib::tween::{Easing, Parallel, Sequence, Tween};
+use gamelib::utils::{Color, Vec2};
+use gamelib::*;
+
+struct Square {
+    position: Vec2,
+    size: f32,
+    color: Color,
+}
+
+struct Tweening {
+    square: Square,
+    animation: Sequence<Square>,
+    /// Not part of the sequence, just bouncing up and down on its own.
+    ball: Tween<f32>,
+}
+
+impl Tweening {
+    fn new() -> Self {
+        let animation = Sequence::new()
+            .then(
+                Tween::new(Vec2::new(-0.6, 0.0), Vec2::new(0.6, 0.0), 1.0)
+                    .with_easing(Easing::BackInOut)
+                    .on(|square: &mut Square, position| square.position = position),
+            )
+            .then_wait(0.3)
+            .then(
+                Parallel::new()
+                    .with(
+                        Tween::new(0.2, 0.4, 0.4)
+                            .with_easing(Easing::ElasticOut)
+                            .with_repeat(1)
+                            .with_yoyo()
+                            .on(|square: &mut Square, size| square.size = size),
+                    )
+                    .with(
+                        Tween::new((0.2, 0.6, 1.0), (1.0, 0.4, 0.2), 0.8)
+                            .on(|square: &mut Square, color| square.color = color),
+                    ),
+            )
+            .then(
+                Tween::new(Vec2::new(0.6, 0.0), Vec2::new(-0.6, 0.0), 1.0)
+                    .with_easing(Easing::CubicInOut)
+                    .with_delay(0.3)
+                    .on(|square: &mut Square, position| square.position = position),
+            )
+            .then(
+                Tween::new((1.0, 0.4, 0.2), (0.2, 0.6, 1.0), 0.3)
+                    .on(|square: &mut Square, color| square.color = color),
+            )
+            .repeat_forever();
+
+        Self {
+            square: Square {
+                position: Vec2::new(-0.6, 0.0),
+                size: 0.2,
+                color: (0.2, 0.6, 1.0),
+            },
+            animation,
+            ball: Tween::new(0.8, 0.4, 1.0)
+                .with_easing(Easing::BounceOut)
+                .repeat_forever(),
+        }
+    }
+}
+
+impl Game for Tweening {
+    fn configure(&self, config: &mut GameConfig) {
+        config.update_fps = 60.0;
+    }
+
+    fn update(&mut self, context: &mut Context) {
+        self.animation.update(context.delta, &mut self.square);
+        self.ball.update(context.delta);
+    }
+
+    fn render(&mut self, canvas: &mut Canvas, context: &mut Context) {
+        canvas.clear(0.1, 0.1, 0.1);
+        canvas.fit();
+
+        context
+            .render_blank()
+            .translate_tup(self.square.position.into())
+            .scale(self.square.size, self.square.size)
+            .shade_tup(self.square.color)
+            .commit(canvas);
+
+        context
+            .render_blank()
+            .translate(0.0, self.ball.value())
+            .scale(0.1, 0.1)
+            .shade(1.0, 1.0, 1.0)
+            .commit(canvas);
+    }
+}
+
+fn main() -> Result<(), Error> {
+    run_game(Tweening::new())
+}
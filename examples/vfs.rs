@@ -0,0 +1,49 @@
+use gamelib::vfs::{self, Archive};
+use gamelib::*;
+
+/// The character texture is built into the executable, so it shows up whatever the working
+/// directory. Pack the textures with `cargo run --bin gamelib-pack -- assets.pak examples/textures`
+/// and the panel is read from `assets.pak`, if there's one in the working directory.
+struct Vfs {
+    panel: bool,
+}
+
+impl Game for Vfs {
+    fn configure(&self, config: &mut GameConfig) {
+        config.title = "Virtual filesystem".into();
+    }
+
+    fn init(&mut self, _context: &mut Context) {
+        self.panel = vfs::read("examples/textures/panel.png").is_ok();
+    }
+
+    fn render(&mut self, canvas: &mut Canvas, context: &mut Context) {
+        canvas.clear(0.1, 0.1, 0.1);
+        canvas.size(2.0, 2.0);
+        canvas.fit();
+
+        context
+            .render("examples/textures/ch.png")
+            .translate(-0.4, 0.0)
+            .scale(0.5, 0.5)
+            .commit(canvas);
+
+        if self.panel {
+            context
+                .render("examples/textures/panel.png")
+                .translate(0.4, 0.0)
+                .scale(0.5, 0.5)
+                .commit(canvas);
+        }
+    }
+}
+
+fn main() -> Result<(), Error> {
+    if let Ok(archive) = Archive::open("assets.pak") {
+        vfs::mount(archive);
+    }
+
+    vfs::mount(gamelib::embed_assets!("examples/textures/ch.png"));
+
+    run_game(Vfs { panel: false })
+}
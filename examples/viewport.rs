@@ -19,7 +19,7 @@ impl Game for Viewport {
 
         let mut rect = |x: f32, y: f32| {
             context
-                .render("rect")
+                .render_blank()
                 .translate(x, y)
                 .shade_tup(colors.pop().unwrap())
                 .commit(canvas);
@@ -32,6 +32,6 @@ impl Game for Viewport {
     }
 }
 
-fn main() {
-    run_game(Viewport);
+fn main() -> Result<(), Error> {
+    run_game(Viewport)
 }
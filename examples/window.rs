@@ -0,0 +1,95 @@
+use gamelib::tween::Tween;
+use gamelib::window::{CursorIcon, WindowMode};
+use gamelib::*;
+
+/// F toggles borderless fullscreen and E exclusive fullscreen. C hides the cursor, G keeps it in
+/// the window and H turns it into a hand. The square stops spinning while the window is in the
+/// background.
+struct Window {
+    turn: Tween<f32>,
+    cursor_visible: bool,
+    cursor_grab: bool,
+    hand: bool,
+}
+
+impl Window {
+    fn toggle_mode(context: &mut Context, mode: WindowMode) {
+        if context.window_mode() == mode {
+            context.set_window_mode(WindowMode::Windowed);
+        } else {
+            context.set_window_mode(mode);
+        }
+    }
+}
+
+impl Game for Window {
+    fn configure(&self, config: &mut GameConfig) {
+        config.title = "Window".into();
+        config.min_window_size = Some((320, 240));
+        config.icon = Some("examples/textures/ch.png".into());
+        config.vsync = true;
+        config.multisampling = 4;
+        config.pause_on_focus_lost = true;
+    }
+
+    fn update(&mut self, context: &mut Context) {
+        self.turn.update(context.delta);
+
+        if context.was_pressed(KeyCode::F) {
+            Self::toggle_mode(context, WindowMode::Borderless);
+        }
+
+        if context.was_pressed(KeyCode::E) {
+            Self::toggle_mode(context, WindowMode::Exclusive);
+        }
+
+        if context.was_pressed(KeyCode::C) {
+            self.cursor_visible = !self.cursor_visible;
+            context.set_cursor_visible(self.cursor_visible);
+        }
+
+        if context.was_pressed(KeyCode::G) {
+            self.cursor_grab = !self.cursor_grab;
+            context.set_cursor_grab(self.cursor_grab);
+        }
+
+        if context.was_pressed(KeyCode::H) {
+            self.hand = !self.hand;
+            context.set_cursor_icon(if self.hand {
+                CursorIcon::Hand
+            } else {
+                CursorIcon::Default
+            });
+        }
+    }
+
+    fn resized(&mut self, context: &mut Context, width: u32, height: u32) {
+        let (logical_width, logical_height) = context.window_size();
+        context.set_title(&format!("Window {}x{}", logical_width, logical_height));
+        println!("Resized to {}x{} pixels", width, height);
+    }
+
+    fn focus_changed(&mut self, _context: &mut Context, focused: bool) {
+        println!("{}", if focused { "Resumed" } else { "Paused" });
+    }
+
+    fn render(&mut self, canvas: &mut Canvas, context: &mut Context) {
+        canvas.clear(0.0, 0.0, 0.0);
+        canvas.fit();
+        context
+            .render_blank()
+            .rotate(self.turn.value())
+            .scale(2.0, 0.5)
+            .shade(0.2, 0.6, 1.0)
+            .commit(canvas);
+    }
+}
+
+fn main() -> Result<(), Error> {
+    run_game(Window {
+        turn: Tween::new(0.0, 2.0 * std::f32::consts::PI, 2.0).repeat_forever(),
+        cursor_visible: true,
+        cursor_grab: false,
+        hand: false,
+    })
+}
@@ -0,0 +1,1025 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::marker::PhantomData;
+#[cfg(feature = "hot-reload")]
+use std::path::PathBuf;
+use std::rc::{Rc, Weak};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+#[cfg(feature = "hot-reload")]
+use std::time::Instant;
+
+use glium::Display;
+use image::RgbaImage;
+
+use crate::audio::Sound;
+use crate::error::Error;
+use crate::font::Font;
+#[cfg(feature = "hot-reload")]
+use crate::hot_reload::{FileWatcher, POLL_INTERVAL};
+use crate::render::{self, Texture};
+use crate::vfs;
+
+#[derive(Debug)]
+pub enum AssetErrorKind {
+    /// The file couldn't be read.
+    Io(io::Error),
+    /// The file was read, but isn't a valid asset.
+    Decode(String),
+    /// The asset was decoded, but couldn't be created, e.g. a texture too big for the GPU.
+    Create(String),
+}
+
+#[derive(Debug)]
+pub struct AssetError {
+    pub path: String,
+    pub kind: AssetErrorKind,
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            AssetErrorKind::Io(error) => write!(f, "Couldn't read {}: {}", self.path, error),
+            AssetErrorKind::Decode(error) => write!(f, "Couldn't decode {}: {}", self.path, error),
+            AssetErrorKind::Create(error) => write!(f, "Couldn't create {}: {}", self.path, error),
+        }
+    }
+}
+
+impl error::Error for AssetError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match &self.kind {
+            AssetErrorKind::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Something `Assets` can load. Decoding happens on a background thread, while creating the
+/// asset out of the decoded data happens on the main one, where the display can be used.
+pub trait Asset: Sized + 'static {
+    type Data: Send + 'static;
+
+    fn decode(bytes: Vec<u8>) -> Result<Self::Data, String>;
+    /// `display` is `None` for assets kept without a window, which only some assets need.
+    fn create(data: Self::Data, display: Option<&Display>) -> Result<Self, String>;
+}
+
+impl Asset for Texture {
+    type Data = RgbaImage;
+
+    fn decode(bytes: Vec<u8>) -> Result<RgbaImage, String> {
+        image::load_from_memory(&bytes)
+            .map(|image| image.to_rgba8())
+            .map_err(|error| error.to_string())
+    }
+
+    fn create(image: RgbaImage, display: Option<&Display>) -> Result<Self, String> {
+        let display = display.ok_or("Textures need a window")?;
+        Texture::from_image(display, image).map_err(|error| error.to_string())
+    }
+}
+
+/// Text files, e.g. configs to parse.
+impl Asset for String {
+    type Data = String;
+
+    fn decode(bytes: Vec<u8>) -> Result<String, String> {
+        String::from_utf8(bytes).map_err(|error| error.to_string())
+    }
+
+    fn create(text: String, _display: Option<&Display>) -> Result<Self, String> {
+        Ok(text)
+    }
+}
+
+/// Any file, as is.
+impl Asset for Vec<u8> {
+    type Data = Vec<u8>;
+
+    fn decode(bytes: Vec<u8>) -> Result<Vec<u8>, String> {
+        Ok(bytes)
+    }
+
+    fn create(bytes: Vec<u8>, _display: Option<&Display>) -> Result<Self, String> {
+        Ok(bytes)
+    }
+}
+
+impl Asset for Sound {
+    type Data = Sound;
+
+    fn decode(bytes: Vec<u8>) -> Result<Sound, String> {
+        Sound::decode(bytes)
+    }
+
+    fn create(sound: Sound, _display: Option<&Display>) -> Result<Self, String> {
+        Ok(sound)
+    }
+}
+
+impl Asset for Font {
+    type Data = Font;
+
+    fn decode(bytes: Vec<u8>) -> Result<Font, String> {
+        Font::decode(bytes).map_err(|error| error.to_string())
+    }
+
+    fn create(font: Font, _display: Option<&Display>) -> Result<Self, String> {
+        Ok(font)
+    }
+}
+
+/// Refers to an asset in `Assets`. The asset is unloaded once every handle to it is dropped.
+pub struct Handle<T> {
+    id: u64,
+    refs: Rc<()>,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            refs: self.refs.clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Handle({})", self.id)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LoadState {
+    Loading,
+    Loaded,
+    Failed,
+    /// Unloaded with `Assets::unload`.
+    Unloaded,
+}
+
+enum Slot<T> {
+    Loading,
+    Loaded(T),
+    Failed(AssetError),
+    Unloaded,
+}
+
+struct Entry<T> {
+    path: Option<String>,
+    slot: Slot<T>,
+    refs: Weak<()>,
+    version: u32,
+}
+
+struct Storage<T> {
+    entries: HashMap<u64, Entry<T>>,
+    paths: HashMap<String, u64>,
+}
+
+/// Lets `Assets` keep the storages of all asset types together.
+trait AnyStorage {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    /// Remove the assets without handles left.
+    fn collect(&mut self);
+}
+
+impl<T: 'static> AnyStorage for Storage<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn collect(&mut self) {
+        let paths = &mut self.paths;
+
+        self.entries.retain(|_, entry| {
+            let alive = entry.refs.strong_count() > 0;
+
+            if !alive {
+                if let Some(path) = &entry.path {
+                    paths.remove(path);
+                }
+            }
+
+            alive
+        });
+    }
+}
+
+type Job = Box<dyn FnOnce() -> Finished + Send>;
+
+/// Decoded data, turned into an asset on the main thread. Returns why a reload failed, if it did.
+type Finished = Box<dyn FnOnce(&mut Assets) -> Option<AssetError> + Send>;
+
+/// Threads decoding assets in the background.
+struct Workers {
+    jobs: Sender<Job>,
+    finished: Receiver<Finished>,
+}
+
+impl Workers {
+    fn new() -> Self {
+        let (jobs, job_receiver) = channel::<Job>();
+        let (finished_sender, finished) = channel();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+
+        let count = thread::available_parallelism()
+            .map(|count| count.get())
+            .unwrap_or(1)
+            .clamp(1, 4);
+
+        for _ in 0..count {
+            let jobs = job_receiver.clone();
+            let finished = finished_sender.clone();
+
+            thread::spawn(move || loop {
+                let job = match jobs.lock().unwrap().recv() {
+                    Ok(job) => job,
+                    Err(_) => return,
+                };
+
+                if finished.send(job()).is_err() {
+                    return;
+                }
+            });
+        }
+
+        Self { jobs, finished }
+    }
+}
+
+/// Reloads the asset of some type at a path, returning false if there's none.
+#[cfg(feature = "hot-reload")]
+type Reload = fn(&mut Assets, &str) -> bool;
+
+/// Loads and keeps textures, sounds, fonts and any other `Asset`, handing out handles to them.
+/// Get it with `Context::assets`.
+///
+/// With the `hot-reload` feature, assets loaded from files are reloaded in the background when
+/// the files change, keeping their handles.
+pub struct Assets {
+    display: Option<Display>,
+    storages: HashMap<TypeId, Box<dyn AnyStorage>>,
+    next_id: u64,
+    workers: Option<Workers>,
+    /// Background loads since the last time none were pending, for `progress`.
+    queued: usize,
+    done: usize,
+    #[cfg(feature = "hot-reload")]
+    watcher: FileWatcher,
+    /// For every watched file, the paths and types of the assets loaded from it.
+    #[cfg(feature = "hot-reload")]
+    reloads: HashMap<PathBuf, HashMap<(String, TypeId), Reload>>,
+    #[cfg(feature = "hot-reload")]
+    last_poll: Instant,
+}
+
+impl Assets {
+    pub(crate) fn new(display: Option<Display>) -> Self {
+        Self {
+            display,
+            storages: HashMap::new(),
+            next_id: 0,
+            workers: None,
+            queued: 0,
+            done: 0,
+            #[cfg(feature = "hot-reload")]
+            watcher: FileWatcher::new(),
+            #[cfg(feature = "hot-reload")]
+            reloads: HashMap::new(),
+            #[cfg(feature = "hot-reload")]
+            last_poll: Instant::now(),
+        }
+    }
+
+    /// Start loading an asset on a background thread, unless it's already loaded or loading.
+    /// Check how it went with `state`, or wait until `is_loading` is false. Assets that failed to
+    /// load are tried again.
+    pub fn load<T: Asset>(&mut self, path: &str) -> Handle<T> {
+        let handle = match self.find::<T>(path) {
+            Some(handle) if self.state(&handle) != LoadState::Failed => return handle,
+            Some(handle) => {
+                self.entry_mut(&handle).unwrap().slot = Slot::Loading;
+                handle
+            }
+            None => self.add(Some(path), Slot::<T>::Loading),
+        };
+
+        self.queue::<T>(handle.id, path, false);
+        self.queued += 1;
+        handle
+    }
+
+    /// Load an asset right away, unless it's already loaded. This takes the place of a pending
+    /// background load of the same path.
+    pub fn load_now<T: Asset>(&mut self, path: &str) -> Result<Handle<T>, AssetError> {
+        let handle = self.find::<T>(path);
+
+        if let Some(handle) = &handle {
+            if self.state(handle) == LoadState::Loaded {
+                return Ok(handle.clone());
+            }
+        }
+
+        let asset = read(path)
+            .and_then(|bytes| decode::<T>(path, bytes))
+            .and_then(|data| self.create::<T>(path, data))?;
+
+        Ok(match handle {
+            Some(handle) => {
+                let entry = self.entry_mut(&handle).unwrap();
+                entry.slot = Slot::Loaded(asset);
+                entry.version += 1;
+                handle
+            }
+            None => self.add(Some(path), Slot::Loaded(asset)),
+        })
+    }
+
+    /// Decode an asset from a file already in memory, e.g. one built in with `include_bytes!` or
+    /// downloaded. `name` only shows up in errors; the asset isn't shared with loads by path.
+    pub fn load_bytes<T: Asset>(
+        &mut self,
+        name: &str,
+        bytes: Vec<u8>,
+    ) -> Result<Handle<T>, AssetError> {
+        let asset = decode::<T>(name, bytes).and_then(|data| self.create::<T>(name, data))?;
+        Ok(self.insert(asset))
+    }
+
+    /// A texture from raw RGBA pixels, as in `Texture::from_rgba`, to update later with
+    /// `Texture::write` through `get_mut`.
+    pub fn create_texture(
+        &mut self,
+        width: u32,
+        height: u32,
+        pixels: &[u8],
+    ) -> Result<Handle<Texture>, AssetError> {
+        let path = "generated texture";
+
+        let image = render::rgba_image(width, height, pixels).map_err(|error| AssetError {
+            path: path.to_string(),
+            kind: AssetErrorKind::Create(error.to_string()),
+        })?;
+
+        let texture = self.create::<Texture>(path, image)?;
+        Ok(self.insert(texture))
+    }
+
+    /// Add an asset that wasn't loaded from a file.
+    pub fn insert<T: Asset>(&mut self, asset: T) -> Handle<T> {
+        self.add(None, Slot::Loaded(asset))
+    }
+
+    /// `None` unless the asset is loaded.
+    pub fn get<T: Asset>(&self, handle: &Handle<T>) -> Option<&T> {
+        match &self.entry(handle)?.slot {
+            Slot::Loaded(asset) => Some(asset),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut<T: Asset>(&mut self, handle: &Handle<T>) -> Option<&mut T> {
+        match &mut self.entry_mut(handle)?.slot {
+            Slot::Loaded(asset) => Some(asset),
+            _ => None,
+        }
+    }
+
+    pub fn state<T: Asset>(&self, handle: &Handle<T>) -> LoadState {
+        match self.entry(handle).map(|entry| &entry.slot) {
+            Some(Slot::Loading) => LoadState::Loading,
+            Some(Slot::Loaded(_)) => LoadState::Loaded,
+            Some(Slot::Failed(_)) => LoadState::Failed,
+            Some(Slot::Unloaded) | None => LoadState::Unloaded,
+        }
+    }
+
+    /// Why the asset failed to load, if it did.
+    pub fn error<T: Asset>(&self, handle: &Handle<T>) -> Option<&AssetError> {
+        match &self.entry(handle)?.slot {
+            Slot::Failed(error) => Some(error),
+            _ => None,
+        }
+    }
+
+    /// Goes up every time the asset is loaded, e.g. when hot reloaded: compare it to a previous
+    /// one to know when to update what depends on the asset.
+    pub fn version<T: Asset>(&self, handle: &Handle<T>) -> u32 {
+        self.entry(handle).map_or(0, |entry| entry.version)
+    }
+
+    pub fn path<T: Asset>(&self, handle: &Handle<T>) -> Option<&str> {
+        self.entry(handle)?.path.as_deref()
+    }
+
+    /// Free an asset before its handles are dropped. Loading its path again loads it anew.
+    pub fn unload<T: Asset>(&mut self, handle: &Handle<T>) {
+        let storage = self.storage_mut::<T>();
+
+        if let Some(entry) = storage.entries.get_mut(&handle.id) {
+            entry.slot = Slot::Unloaded;
+
+            if let Some(path) = entry.path.take() {
+                storage.paths.remove(&path);
+            }
+        }
+    }
+
+    /// Whether background loads are pending.
+    pub fn is_loading(&self) -> bool {
+        self.done < self.queued
+    }
+
+    /// From 0.0 to 1.0, how many of the background loads started since the last time none were
+    /// pending are over, failed ones included.
+    pub fn progress(&self) -> f32 {
+        if self.queued == 0 {
+            1.0
+        } else {
+            self.done as f32 / self.queued as f32
+        }
+    }
+
+    /// Called by the game loop every frame: creates the assets decoded in the background and
+    /// frees the ones without handles left. Returns why hot reloads failed, since those keep the
+    /// previous asset instead of failing it.
+    pub(crate) fn update(&mut self) -> Vec<Error> {
+        let finished: Vec<Finished> = match &self.workers {
+            Some(workers) => workers.finished.try_iter().collect(),
+            None => Vec::new(),
+        };
+
+        let errors = finished
+            .into_iter()
+            .filter_map(|finish| finish(self))
+            .map(Error::from)
+            .collect();
+
+        #[cfg(feature = "hot-reload")]
+        self.reload_changed();
+
+        for storage in self.storages.values_mut() {
+            storage.collect();
+        }
+
+        if !self.is_loading() {
+            self.queued = 0;
+            self.done = 0;
+        }
+
+        errors
+    }
+
+    /// Decode an asset on a background thread.
+    fn queue<T: Asset>(&mut self, id: u64, path: &str, reload: bool) {
+        let path = path.to_string();
+
+        let job: Job = Box::new(move || {
+            let data = read(&path).and_then(|bytes| decode::<T>(&path, bytes));
+            Box::new(move |assets: &mut Assets| assets.finish::<T>(id, &path, data, reload))
+        });
+
+        self.workers
+            .get_or_insert_with(Workers::new)
+            .jobs
+            .send(job)
+            .unwrap();
+    }
+
+    /// Create an asset decoded in the background. Reloads replace loaded and failed assets
+    /// instead of loading ones, and keep the previous asset if they fail, returning the error.
+    fn finish<T: Asset>(
+        &mut self,
+        id: u64,
+        path: &str,
+        data: Result<T::Data, AssetError>,
+        reload: bool,
+    ) -> Option<AssetError> {
+        if !reload {
+            self.done += 1;
+        }
+
+        // Dropped, unloaded, or already loaded with `load_now`.
+        match self
+            .storage::<T>()
+            .and_then(|storage| storage.entries.get(&id))
+            .map(|entry| &entry.slot)
+        {
+            Some(Slot::Loading) if !reload => {}
+            Some(Slot::Loaded(_)) | Some(Slot::Failed(_)) if reload => {}
+            _ => return None,
+        }
+
+        let result = data.and_then(|data| self.create::<T>(path, data));
+        let entry = self.storage_mut::<T>().entries.get_mut(&id).unwrap();
+
+        match result {
+            Ok(asset) => {
+                entry.slot = Slot::Loaded(asset);
+                entry.version += 1;
+            }
+            Err(error) => match entry.slot {
+                Slot::Loaded(_) => return Some(error),
+                _ => entry.slot = Slot::Failed(error),
+            },
+        }
+
+        None
+    }
+
+    #[cfg(feature = "hot-reload")]
+    fn reload<T: Asset>(&mut self, path: &str) -> bool {
+        let id = match self
+            .storage::<T>()
+            .and_then(|storage| storage.paths.get(path))
+        {
+            Some(&id) => id,
+            None => return false,
+        };
+
+        self.queue::<T>(id, path, true);
+        true
+    }
+
+    /// Load every loaded asset of the type from its file again, right away, e.g. textures after
+    /// the OpenGL context was lost. Assets that weren't loaded from files are left as they are.
+    pub(crate) fn reload_all<T: Asset>(&mut self) {
+        let loaded: Vec<(u64, String)> = match self.storage::<T>() {
+            Some(storage) => storage
+                .paths
+                .iter()
+                .filter(|(_, id)| {
+                    matches!(
+                        storage.entries.get(id),
+                        Some(Entry {
+                            slot: Slot::Loaded(_),
+                            ..
+                        })
+                    )
+                })
+                .map(|(path, &id)| (id, path.clone()))
+                .collect(),
+            None => return,
+        };
+
+        for (id, path) in loaded {
+            let slot = match read(&path)
+                .and_then(|bytes| decode::<T>(&path, bytes))
+                .and_then(|data| self.create::<T>(&path, data))
+            {
+                Ok(asset) => Slot::Loaded(asset),
+                Err(error) => Slot::Failed(error),
+            };
+
+            let entry = self.storage_mut::<T>().entries.get_mut(&id).unwrap();
+            entry.slot = slot;
+            entry.version += 1;
+        }
+    }
+
+    #[cfg(feature = "hot-reload")]
+    fn watch<T: Asset>(&mut self, path: &str) {
+        // Packed and embedded files don't change.
+        let file = match vfs::local_path(path) {
+            Some(file) => file,
+            None => return,
+        };
+
+        self.watcher.watch(&file);
+
+        self.reloads
+            .entry(file)
+            .or_default()
+            .insert((path.to_string(), TypeId::of::<T>()), Self::reload::<T>);
+    }
+
+    /// Reload the assets whose files changed.
+    #[cfg(feature = "hot-reload")]
+    fn reload_changed(&mut self) {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return;
+        }
+
+        self.last_poll = Instant::now();
+
+        for file in self.watcher.poll() {
+            let reloads: Vec<(String, Reload)> = match self.reloads.get(&file) {
+                Some(reloads) => reloads
+                    .iter()
+                    .map(|((path, _), reload)| (path.clone(), *reload))
+                    .collect(),
+                None => continue,
+            };
+
+            let mut found = false;
+
+            for (path, reload) in reloads {
+                found |= reload(self, &path);
+            }
+
+            // Every asset loaded from the file is gone.
+            if !found {
+                self.watcher.unwatch(&file);
+                self.reloads.remove(&file);
+            }
+        }
+    }
+
+    fn create<T: Asset>(&self, path: &str, data: T::Data) -> Result<T, AssetError> {
+        T::create(data, self.display.as_ref()).map_err(|error| AssetError {
+            path: path.to_string(),
+            kind: AssetErrorKind::Create(error),
+        })
+    }
+
+    /// A handle to the asset at `path`, if it's already there.
+    fn find<T: Asset>(&mut self, path: &str) -> Option<Handle<T>> {
+        let storage = self.storage_mut::<T>();
+        let id = *storage.paths.get(path)?;
+        let entry = storage.entries.get_mut(&id)?;
+
+        // Handles might all be gone without the asset being collected yet.
+        let refs = entry.refs.upgrade().unwrap_or_else(|| {
+            let refs = Rc::new(());
+            entry.refs = Rc::downgrade(&refs);
+            refs
+        });
+
+        Some(Handle {
+            id,
+            refs,
+            marker: PhantomData,
+        })
+    }
+
+    fn add<T: Asset>(&mut self, path: Option<&str>, slot: Slot<T>) -> Handle<T> {
+        self.next_id += 1;
+        let id = self.next_id;
+        let refs = Rc::new(());
+
+        let storage = self.storage_mut::<T>();
+
+        if let Some(path) = path {
+            storage.paths.insert(path.to_string(), id);
+        }
+
+        let version = match slot {
+            Slot::Loaded(_) => 1,
+            _ => 0,
+        };
+
+        storage.entries.insert(
+            id,
+            Entry {
+                path: path.map(str::to_string),
+                slot,
+                refs: Rc::downgrade(&refs),
+                version,
+            },
+        );
+
+        #[cfg(feature = "hot-reload")]
+        {
+            if let Some(path) = path {
+                self.watch::<T>(path);
+            }
+        }
+
+        Handle {
+            id,
+            refs,
+            marker: PhantomData,
+        }
+    }
+
+    fn entry<T: Asset>(&self, handle: &Handle<T>) -> Option<&Entry<T>> {
+        self.storage::<T>()?.entries.get(&handle.id)
+    }
+
+    fn entry_mut<T: Asset>(&mut self, handle: &Handle<T>) -> Option<&mut Entry<T>> {
+        self.storage_mut::<T>().entries.get_mut(&handle.id)
+    }
+
+    fn storage<T: Asset>(&self) -> Option<&Storage<T>> {
+        self.storages
+            .get(&TypeId::of::<T>())
+            .and_then(|storage| storage.as_any().downcast_ref())
+    }
+
+    fn storage_mut<T: Asset>(&mut self) -> &mut Storage<T> {
+        self.storages
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| {
+                Box::new(Storage::<T> {
+                    entries: HashMap::new(),
+                    paths: HashMap::new(),
+                })
+            })
+            .as_any_mut()
+            .downcast_mut()
+            .unwrap()
+    }
+}
+
+fn decode<T: Asset>(path: &str, bytes: Vec<u8>) -> Result<T::Data, AssetError> {
+    T::decode(bytes).map_err(|error| AssetError {
+        path: path.to_string(),
+        kind: AssetErrorKind::Decode(error),
+    })
+}
+
+fn read(path: &str) -> Result<Vec<u8>, AssetError> {
+    vfs::read(path).map_err(|error| AssetError {
+        path: path.to_string(),
+        kind: AssetErrorKind::Io(error),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+    use std::time::Duration;
+
+    /// A text file only this test uses.
+    fn text_file(name: &str, text: &str) -> String {
+        let path = std::env::temp_dir().join(format!(
+            "gamelib-assets-{}-{}.txt",
+            std::process::id(),
+            name
+        ));
+        fs::write(&path, text).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    /// Update until the background loads are over.
+    fn wait(assets: &mut Assets) {
+        for _ in 0..5000 {
+            assets.update();
+
+            if !assets.is_loading() {
+                return;
+            }
+
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        panic!("The assets never finished loading");
+    }
+
+    #[test]
+    fn assets_are_freed_with_their_last_handle() {
+        let mut assets = Assets::new(None);
+        let handle = assets.insert("text".to_string());
+        let clone = handle.clone();
+
+        drop(handle);
+        assets.update();
+        assert_eq!(assets.get(&clone).unwrap(), "text");
+
+        let id = clone.id;
+        drop(clone);
+        assets.update();
+        assert!(!assets
+            .storage::<String>()
+            .unwrap()
+            .entries
+            .contains_key(&id));
+    }
+
+    #[test]
+    fn paths_are_shared_until_freed() {
+        let path = text_file("shared", "shared");
+        let mut assets = Assets::new(None);
+
+        let first = assets.load_now::<String>(&path).unwrap();
+        let second = assets.load_now::<String>(&path).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(assets.version(&first), 1);
+
+        // Found again while dropped but not freed yet.
+        let id = first.id;
+        drop((first, second));
+        let again = assets.load_now::<String>(&path).unwrap();
+        assert_eq!(again.id, id);
+
+        drop(again);
+        assets.update();
+        assert_ne!(assets.load_now::<String>(&path).unwrap().id, id);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn unloaded_assets_load_anew() {
+        let path = text_file("unload", "before");
+        let mut assets = Assets::new(None);
+        let handle = assets.load_now::<String>(&path).unwrap();
+
+        assets.unload(&handle);
+        assert_eq!(assets.state(&handle), LoadState::Unloaded);
+        assert!(assets.get(&handle).is_none());
+        assert!(assets.path(&handle).is_none());
+
+        fs::write(&path, "after").unwrap();
+        let reloaded = assets.load_now::<String>(&path).unwrap();
+        assert_ne!(reloaded, handle);
+        assert_eq!(assets.get(&reloaded).unwrap(), "after");
+        assert_eq!(assets.state(&handle), LoadState::Unloaded);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn background_loads() {
+        let path = text_file("background", "loaded");
+        let missing = format!("{}.missing", path);
+        let mut assets = Assets::new(None);
+
+        let handle = assets.load::<String>(&path);
+        let failed = assets.load::<String>(&missing);
+        assert_eq!(assets.load::<String>(&path), handle);
+        assert_eq!(assets.state(&handle), LoadState::Loading);
+        assert!(assets.is_loading());
+
+        wait(&mut assets);
+        assert_eq!(assets.progress(), 1.0);
+        assert_eq!(assets.get(&handle).unwrap(), "loaded");
+        assert_eq!(assets.version(&handle), 1);
+        assert_eq!(assets.state(&failed), LoadState::Failed);
+        assert!(matches!(
+            assets.error(&failed),
+            Some(AssetError {
+                kind: AssetErrorKind::Io(_),
+                ..
+            })
+        ));
+
+        // Failed loads are tried again.
+        fs::rename(&path, &missing).unwrap();
+        assert_eq!(assets.load::<String>(&missing), failed);
+        assert_eq!(assets.state(&failed), LoadState::Loading);
+        wait(&mut assets);
+        assert_eq!(assets.get(&failed).unwrap(), "loaded");
+
+        fs::remove_file(missing).unwrap();
+    }
+
+    #[cfg(feature = "hot-reload")]
+    #[test]
+    fn reloads_keep_the_handle() {
+        let path = text_file("reload-handle", "before");
+        let mut assets = Assets::new(None);
+        let handle = assets.load_now::<String>(&path).unwrap();
+
+        fs::write(&path, "after").unwrap();
+        assert!(assets.reload::<String>(&path));
+
+        for _ in 0..5000 {
+            assets.update();
+
+            if assets.version(&handle) > 1 {
+                break;
+            }
+
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        assert_eq!(assets.get(&handle).unwrap(), "after");
+        assert_eq!(assets.version(&handle), 2);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(feature = "hot-reload")]
+    #[test]
+    fn failed_reloads_keep_the_asset_and_return_the_error() {
+        let path = text_file("reload", "before");
+        let mut assets = Assets::new(None);
+        let handle = assets.load_now::<String>(&path).unwrap();
+
+        fs::write(&path, [0xff]).unwrap();
+        assert!(assets.reload::<String>(&path));
+
+        let mut errors = Vec::new();
+
+        for _ in 0..5000 {
+            errors = assets.update();
+
+            if !errors.is_empty() {
+                break;
+            }
+
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        assert!(matches!(
+            errors.as_slice(),
+            [Error::Asset(AssetError {
+                kind: AssetErrorKind::Decode(_),
+                ..
+            })]
+        ));
+        assert_eq!(assets.get(&handle).unwrap(), "before");
+        assert_eq!(assets.version(&handle), 1);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn reload_all_reads_the_files_again() {
+        let path = text_file("reload-all", "before");
+        let mut assets = Assets::new(None);
+        let handle = assets.load_now::<String>(&path).unwrap();
+        let inserted = assets.insert("inserted".to_string());
+
+        fs::write(&path, "after").unwrap();
+        assets.reload_all::<String>();
+        assert_eq!(assets.get(&handle).unwrap(), "after");
+        assert_eq!(assets.version(&handle), 2);
+        assert_eq!(assets.get(&inserted).unwrap(), "inserted");
+
+        fs::remove_file(&path).unwrap();
+        assets.reload_all::<String>();
+        assert_eq!(assets.state(&handle), LoadState::Failed);
+    }
+
+    #[test]
+    fn dropped_background_loads_are_discarded() {
+        let path = text_file("dropped", "dropped");
+        let mut assets = Assets::new(None);
+
+        let id = assets.load::<String>(&path).id;
+        wait(&mut assets);
+        assert!(!assets
+            .storage::<String>()
+            .unwrap()
+            .entries
+            .contains_key(&id));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn every_kind_of_asset() {
+        let mut assets = Assets::new(None);
+
+        let sound = assets
+            .load_now::<Sound>("tests/fixtures/silence.ogg")
+            .unwrap();
+        assert_eq!(assets.get(&sound).unwrap().sample_rate(), 8000);
+
+        let font = assets
+            .load_now::<Font>("tests/fixtures/SourceSansPro-Regular-Tiny.ttf")
+            .unwrap();
+        assert!(assets.get(&font).unwrap().measure("A", 10.0).0 > 0);
+
+        let bytes = assets.load_bytes::<Vec<u8>>("bytes", vec![1, 2]).unwrap();
+        assert_eq!(assets.get(&bytes).unwrap(), &[1, 2]);
+
+        let error = assets.load_bytes::<Font>("font", vec![1, 2]).unwrap_err();
+        assert!(matches!(error.kind, AssetErrorKind::Decode(_)));
+        assert_eq!(error.path, "font");
+    }
+
+    #[test]
+    fn textures_need_a_window() {
+        let mut assets = Assets::new(None);
+
+        let error = assets.create_texture(1, 1, &[0; 4]).unwrap_err();
+        assert!(matches!(error.kind, AssetErrorKind::Create(_)));
+
+        let error = assets.create_texture(2, 1, &[0; 4]).unwrap_err();
+        assert!(error.to_string().contains("Expected 2x1 RGBA pixels"));
+    }
+}
@@ -0,0 +1,1351 @@
+use std::collections::HashMap;
+use std::io::{BufReader, Cursor, SeekFrom};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use hound::{SampleFormat, WavIntoSamples, WavReader};
+use lewton::inside_ogg::OggStreamReader;
+
+use crate::assets::{AssetError, AssetErrorKind};
+use crate::error::Error;
+use crate::utils::Vec2;
+use crate::vfs::{self, ReadSeek};
+
+/// Frames of every loaded sound are stereo, left then right.
+type Frame = [f32; 2];
+
+/// Sample rate of the null and recording backends unless told otherwise.
+pub const DEFAULT_SAMPLE_RATE: u32 = 44100;
+
+type Reader = Box<dyn ReadSeek>;
+
+/// Reads WAV, OGG Vorbis or FLAC data bit by bit, picked by the first bytes of the data.
+enum Decoder {
+    WavInt(WavIntoSamples<BufReader<Reader>, i32>, f32),
+    WavFloat(WavIntoSamples<BufReader<Reader>, f32>),
+    Ogg(Box<OggStreamReader<Reader>>),
+    Flac(claxon::FlacReader<Reader>, f32),
+}
+
+/// How many samples a decoder reads at once, for formats that don't come in packets.
+const CHUNK_SIZE: usize = 4096;
+
+impl Decoder {
+    /// Returns the decoder, the number of channels and the sample rate.
+    fn new(mut reader: Reader) -> Result<(Self, usize, u32), String> {
+        let mut magic = [0; 4];
+        reader
+            .read_exact(&mut magic)
+            .map_err(|error| error.to_string())?;
+        reader
+            .seek(SeekFrom::Start(0))
+            .map_err(|error| error.to_string())?;
+
+        match &magic {
+            b"RIFF" => {
+                let reader = WavReader::new(BufReader::new(reader)).map_err(|e| e.to_string())?;
+                let spec = reader.spec();
+
+                let decoder = match spec.sample_format {
+                    SampleFormat::Int => {
+                        let scale = 1.0 / (1u64 << (spec.bits_per_sample - 1)) as f32;
+                        Decoder::WavInt(reader.into_samples(), scale)
+                    }
+                    SampleFormat::Float => Decoder::WavFloat(reader.into_samples()),
+                };
+
+                Ok((decoder, spec.channels as usize, spec.sample_rate))
+            }
+            b"OggS" => {
+                let reader = OggStreamReader::new(reader).map_err(|e| e.to_string())?;
+                let channels = reader.ident_hdr.audio_channels as usize;
+                let sample_rate = reader.ident_hdr.audio_sample_rate;
+
+                Ok((Decoder::Ogg(Box::new(reader)), channels, sample_rate))
+            }
+            b"fLaC" => {
+                let reader = claxon::FlacReader::new(reader).map_err(|e| e.to_string())?;
+                let info = reader.streaminfo();
+                let scale = 1.0 / (1u64 << (info.bits_per_sample - 1)) as f32;
+
+                Ok((
+                    Decoder::Flac(reader, scale),
+                    info.channels as usize,
+                    info.sample_rate,
+                ))
+            }
+            _ => Err("unsupported audio format, expected WAV, OGG Vorbis or FLAC".to_string()),
+        }
+    }
+
+    fn open(filename: &str) -> Result<(Self, usize, u32), AssetError> {
+        let error = |kind| AssetError {
+            path: filename.to_string(),
+            kind,
+        };
+
+        let reader = vfs::open(filename).map_err(|e| error(AssetErrorKind::Io(e)))?;
+        Self::new(reader).map_err(|e| error(AssetErrorKind::Decode(e)))
+    }
+
+    /// The next interleaved samples, or `None` at the end of the data.
+    fn next_chunk(&mut self) -> Result<Option<Vec<f32>>, String> {
+        let chunk: Vec<f32> = match self {
+            Decoder::WavInt(samples, scale) => samples
+                .take(CHUNK_SIZE)
+                .map(|sample| sample.map(|sample| sample as f32 * *scale))
+                .collect::<Result<_, _>>()
+                .map_err(|e| e.to_string())?,
+            Decoder::WavFloat(samples) => samples
+                .take(CHUNK_SIZE)
+                .collect::<Result<_, _>>()
+                .map_err(|e| e.to_string())?,
+            Decoder::Ogg(reader) => loop {
+                match reader.read_dec_packet_itl().map_err(|e| e.to_string())? {
+                    // The first packet only primes the decoder, and others can be empty too.
+                    Some(packet) if packet.is_empty() => continue,
+                    Some(packet) => {
+                        break packet
+                            .iter()
+                            .map(|&sample| sample as f32 / 32768.0)
+                            .collect()
+                    }
+                    None => return Ok(None),
+                }
+            },
+            Decoder::Flac(reader, scale) => {
+                let block = match reader
+                    .blocks()
+                    .read_next_or_eof(Vec::new())
+                    .map_err(|e| e.to_string())?
+                {
+                    Some(block) => block,
+                    None => return Ok(None),
+                };
+
+                let mut chunk = Vec::with_capacity(block.len() as usize);
+
+                for frame in 0..block.duration() {
+                    for channel in 0..block.channels() {
+                        chunk.push(block.sample(channel, frame) as f32 * *scale);
+                    }
+                }
+
+                chunk
+            }
+        };
+
+        if chunk.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(chunk))
+        }
+    }
+
+    /// Decode everything that's left.
+    fn read_all(&mut self, channels: usize) -> Result<Vec<Frame>, String> {
+        let mut frames = Vec::new();
+
+        while let Some(chunk) = self.next_chunk()? {
+            frames.extend(to_frames(&chunk, channels));
+        }
+
+        Ok(frames)
+    }
+}
+
+/// Turn interleaved samples into stereo frames: mono is played on both sides, and channels
+/// past the first two are dropped.
+fn to_frames(samples: &[f32], channels: usize) -> Vec<Frame> {
+    samples
+        .chunks_exact(channels.max(1))
+        .map(|frame| [frame[0], frame[frame.len().min(2) - 1]])
+        .collect()
+}
+
+/// A sound decoded into memory, cheap to clone.
+#[derive(Clone)]
+pub struct Sound {
+    frames: Arc<Vec<Frame>>,
+    sample_rate: u32,
+}
+
+impl Sound {
+    /// Decode a whole WAV, OGG Vorbis or FLAC file.
+    pub fn load(filename: &str) -> Result<Self, Error> {
+        let (mut decoder, channels, sample_rate) = Decoder::open(filename)?;
+        let frames = decoder.read_all(channels).map_err(|error| AssetError {
+            path: filename.to_string(),
+            kind: AssetErrorKind::Decode(error),
+        })?;
+
+        Ok(Self::from_frames(frames, sample_rate))
+    }
+
+    /// Decode a whole WAV, OGG Vorbis or FLAC file already read into memory.
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, Error> {
+        Self::decode(bytes).map_err(Error::Sound)
+    }
+
+    /// `from_bytes`, with the error `Assets` wraps in an `AssetError`.
+    pub(crate) fn decode(bytes: Vec<u8>) -> Result<Self, String> {
+        let (mut decoder, channels, sample_rate) = Decoder::new(Box::new(Cursor::new(bytes)))?;
+        let frames = decoder.read_all(channels)?;
+
+        Ok(Self::from_frames(frames, sample_rate))
+    }
+
+    /// A sound from interleaved stereo samples, e.g. generated ones.
+    pub fn from_samples(samples: &[f32], sample_rate: u32) -> Self {
+        Self::from_frames(to_frames(samples, 2), sample_rate)
+    }
+
+    pub fn from_mono(samples: &[f32], sample_rate: u32) -> Self {
+        Self::from_frames(to_frames(samples, 1), sample_rate)
+    }
+
+    fn from_frames(frames: Vec<Frame>, sample_rate: u32) -> Self {
+        Self {
+            frames: Arc::new(frames),
+            sample_rate,
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// In seconds.
+    pub fn duration(&self) -> f32 {
+        self.frames.len() as f32 / self.sample_rate as f32
+    }
+}
+
+/// Where the frames of a voice come from.
+trait Source: Send {
+    fn next_frame(&mut self) -> Option<Frame>;
+    fn rewind(&mut self) -> Result<(), AssetError>;
+    fn sample_rate(&self) -> u32;
+}
+
+struct Buffered {
+    sound: Sound,
+    position: usize,
+}
+
+impl Source for Buffered {
+    fn next_frame(&mut self) -> Option<Frame> {
+        let frame = self.sound.frames.get(self.position).copied();
+        self.position += 1;
+        frame
+    }
+
+    fn rewind(&mut self) -> Result<(), AssetError> {
+        self.position = 0;
+        Ok(())
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sound.sample_rate
+    }
+}
+
+/// Decodes the file while playing it instead of loading all of it at once, for music.
+struct Streamed {
+    filename: String,
+    decoder: Decoder,
+    channels: usize,
+    sample_rate: u32,
+    frames: Vec<Frame>,
+    position: usize,
+}
+
+impl Streamed {
+    fn open(filename: &str) -> Result<Self, AssetError> {
+        let (decoder, channels, sample_rate) = Decoder::open(filename)?;
+
+        Ok(Self {
+            filename: filename.to_string(),
+            decoder,
+            channels,
+            sample_rate,
+            frames: Vec::new(),
+            position: 0,
+        })
+    }
+}
+
+impl Source for Streamed {
+    fn next_frame(&mut self) -> Option<Frame> {
+        while self.position >= self.frames.len() {
+            // Decoding errors in the middle of a stream just end it.
+            let chunk = self.decoder.next_chunk().ok().flatten()?;
+            self.frames = to_frames(&chunk, self.channels);
+            self.position = 0;
+        }
+
+        self.position += 1;
+        Some(self.frames[self.position - 1])
+    }
+
+    fn rewind(&mut self) -> Result<(), AssetError> {
+        // The file can be gone or changed since it started playing.
+        *self = Self::open(&self.filename)?;
+        Ok(())
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+}
+
+/// Volume groups; the volume of a voice is multiplied by the one of its group and the master one.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Group {
+    Master,
+    Music,
+    Sfx,
+}
+
+/// Identifies a playing sound.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct VoiceId(u64);
+
+/// A linear volume ramp, for fading in and out.
+#[derive(Copy, Clone)]
+struct Fade {
+    gain: f32,
+    target: f32,
+    /// Gain change per output frame.
+    step: f32,
+    /// Stop the voice once the target is reached.
+    stop: bool,
+}
+
+impl Fade {
+    fn to(&mut self, target: f32, seconds: f32, sample_rate: u32, stop: bool) {
+        let frames = (seconds * sample_rate as f32).max(1.0);
+
+        self.target = target;
+        self.step = (target - self.gain).abs() / frames;
+        self.stop = stop;
+    }
+
+    fn advance(&mut self) {
+        if self.gain < self.target {
+            self.gain = (self.gain + self.step).min(self.target);
+        } else {
+            self.gain = (self.gain - self.step).max(self.target);
+        }
+    }
+
+    fn is_over(&self) -> bool {
+        self.stop && self.gain == self.target
+    }
+}
+
+/// How the volume of a positioned sound drops with its distance to the listener, between the
+/// minimum distance, where it plays at full volume, and the maximum one, past which it's silent.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Rolloff {
+    /// Down to nothing at the maximum distance.
+    Linear,
+    /// Halved every time the distance doubles, like in the real world.
+    Inverse,
+    /// Like `Inverse` with a custom steepness: 2.0 quarters the volume every time the distance
+    /// doubles.
+    Exponential(f32),
+}
+
+/// Where a voice plays from, and how it fades with distance.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Spatial {
+    position: Vec2,
+    rolloff: Rolloff,
+    min_distance: f32,
+    max_distance: f32,
+}
+
+impl Spatial {
+    /// Gain and pan heard by a listener at `listener`.
+    fn apply(&self, listener: Vec2) -> (f32, f32) {
+        let offset = self.position - listener;
+        let distance = offset.length();
+        let min = self.min_distance.max(f32::EPSILON);
+        let max = self.max_distance.max(min);
+
+        let gain = if distance >= max {
+            0.0
+        } else if distance <= min {
+            1.0
+        } else {
+            match self.rolloff {
+                Rolloff::Linear => 1.0 - (distance - min) / (max - min),
+                Rolloff::Inverse => min / distance,
+                Rolloff::Exponential(factor) => (min / distance).powf(factor),
+            }
+        };
+
+        // Sounds within the minimum distance drift towards the center.
+        let pan = offset.x / distance.max(min);
+
+        (gain, pan)
+    }
+}
+
+struct Voice {
+    id: VoiceId,
+    source: Box<dyn Source>,
+    group: Group,
+    volume: f32,
+    /// -1.0 is fully left, 1.0 fully right.
+    pan: f32,
+    spatial: Option<Spatial>,
+    pitch: f32,
+    looping: bool,
+    fade: Fade,
+    /// The frames the output is interpolated between, when resampling.
+    current: Frame,
+    next: Option<Frame>,
+    fraction: f64,
+    started: bool,
+    finished: bool,
+    /// Why a looping voice couldn't go on, handed to the game by `Audio::update`.
+    error: Option<AssetError>,
+}
+
+impl Voice {
+    /// The next frame, faded but without the volume and panning applied, or `None` once finished.
+    fn next_frame(&mut self, output_rate: u32) -> Option<Frame> {
+        if !self.started {
+            self.started = true;
+            self.current = self.read_frame()?;
+            self.next = self.read_frame();
+        }
+
+        let next = self.next?;
+        let t = self.fraction as f32;
+        let frame = [
+            self.current[0] + (next[0] - self.current[0]) * t,
+            self.current[1] + (next[1] - self.current[1]) * t,
+        ];
+
+        self.fraction +=
+            self.source.sample_rate() as f64 / output_rate as f64 * self.pitch.max(0.0) as f64;
+
+        // Skips frames when playing faster than the output rate.
+        while self.fraction >= 1.0 {
+            let next = match self.next {
+                Some(next) => next,
+                None => break,
+            };
+
+            self.fraction -= 1.0;
+            self.current = next;
+            self.next = self.read_frame();
+        }
+
+        self.fade.advance();
+
+        Some([frame[0] * self.fade.gain, frame[1] * self.fade.gain])
+    }
+
+    fn read_frame(&mut self) -> Option<Frame> {
+        match self.source.next_frame() {
+            Some(frame) => Some(frame),
+            None if self.looping => match self.source.rewind() {
+                Ok(()) => self.source.next_frame(),
+                Err(error) => {
+                    self.error = Some(error);
+                    None
+                }
+            },
+            None => None,
+        }
+    }
+}
+
+/// Gains of the left and right channels: panning to one side silences the other one, while the
+/// center keeps both at full volume.
+fn balance(pan: f32) -> (f32, f32) {
+    let pan = pan.clamp(-1.0, 1.0);
+    ((1.0 - pan).min(1.0), (1.0 + pan).min(1.0))
+}
+
+/// Mixes all playing voices together. Shared between the game and the audio backend.
+pub struct Mixer {
+    sample_rate: u32,
+    listener: Vec2,
+    voices: Vec<Voice>,
+    volumes: HashMap<Group, f32>,
+    /// Of voices that stopped early, since the last `Audio::update`.
+    errors: Vec<AssetError>,
+}
+
+impl Mixer {
+    fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            listener: Vec2::ZERO,
+            voices: Vec::new(),
+            volumes: HashMap::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Fill `output` with interleaved stereo samples, advancing every voice.
+    pub fn mix(&mut self, output: &mut [f32]) {
+        for sample in output.iter_mut() {
+            *sample = 0.0;
+        }
+
+        let master = self.volume(Group::Master);
+        let sample_rate = self.sample_rate;
+
+        for voice in &mut self.voices {
+            let (distance_gain, pan) = match &voice.spatial {
+                Some(spatial) => spatial.apply(self.listener),
+                None => (1.0, voice.pan),
+            };
+
+            let gain = master
+                * self.volumes.get(&voice.group).copied().unwrap_or(1.0)
+                * voice.volume
+                * distance_gain;
+            let (left, right) = balance(pan);
+
+            for frame in output.chunks_exact_mut(2) {
+                match voice.next_frame(sample_rate) {
+                    Some(sample) => {
+                        frame[0] += sample[0] * gain * left;
+                        frame[1] += sample[1] * gain * right;
+                    }
+                    None => {
+                        voice.finished = true;
+                        break;
+                    }
+                }
+
+                if voice.fade.is_over() {
+                    break;
+                }
+            }
+        }
+
+        let errors = &mut self.errors;
+
+        self.voices.retain_mut(|voice| {
+            errors.extend(voice.error.take());
+            !voice.finished && !voice.fade.is_over()
+        });
+    }
+
+    fn volume(&self, group: Group) -> f32 {
+        self.volumes.get(&group).copied().unwrap_or(1.0)
+    }
+
+    fn voice_mut(&mut self, id: VoiceId) -> Option<&mut Voice> {
+        self.voices.iter_mut().find(|voice| voice.id == id)
+    }
+}
+
+/// Where the mixed audio goes.
+pub trait AudioBackend {
+    fn sample_rate(&self) -> u32;
+
+    /// Called every update with the shared mixer. Backends without an audio thread of their own
+    /// pull `delta` seconds worth of samples here; others can ignore it.
+    fn update(&mut self, _mixer: &Mutex<Mixer>, _delta: f32) {}
+}
+
+/// Pulls `delta` seconds of samples out of the mixer, keeping track of the fractional frames.
+fn pull(mixer: &Mutex<Mixer>, delta: f32, carry: &mut f64) -> Vec<f32> {
+    let mut mixer = mixer.lock().unwrap();
+
+    *carry += delta as f64 * mixer.sample_rate as f64;
+    let frames = carry.floor();
+    *carry -= frames;
+
+    let mut output = vec![0.0; frames as usize * 2];
+    mixer.mix(&mut output);
+    output
+}
+
+/// Plays nothing, but still advances the sounds so that they finish on time.
+pub struct NullBackend {
+    sample_rate: u32,
+    carry: f64,
+}
+
+impl NullBackend {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            carry: 0.0,
+        }
+    }
+}
+
+impl AudioBackend for NullBackend {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn update(&mut self, mixer: &Mutex<Mixer>, delta: f32) {
+        pull(mixer, delta, &mut self.carry);
+    }
+}
+
+/// Interleaved stereo samples captured by a `RecordingBackend`.
+#[derive(Clone)]
+pub struct Recording {
+    samples: Arc<Mutex<Vec<f32>>>,
+    sample_rate: u32,
+}
+
+impl Recording {
+    pub fn samples(&self) -> Vec<f32> {
+        self.samples.lock().unwrap().clone()
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn clear(&self) {
+        self.samples.lock().unwrap().clear();
+    }
+
+    /// Write what was recorded so far as a 16-bit stereo WAV file.
+    pub fn save_wav(&self, filename: &str) -> Result<(), Error> {
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: self.sample_rate,
+            bits_per_sample: 16,
+            sample_format: SampleFormat::Int,
+        };
+
+        let mut writer = hound::WavWriter::create(filename, spec)?;
+
+        for &sample in self.samples.lock().unwrap().iter() {
+            let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            writer.write_sample(sample)?;
+        }
+
+        writer.finalize()?;
+        Ok(())
+    }
+}
+
+/// Keeps everything that would have been played, for tests and for capturing audio.
+pub struct RecordingBackend {
+    recording: Recording,
+    carry: f64,
+}
+
+impl RecordingBackend {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            recording: Recording {
+                samples: Arc::new(Mutex::new(Vec::new())),
+                sample_rate,
+            },
+            carry: 0.0,
+        }
+    }
+
+    /// A handle to the recorded samples; grab it before handing the backend to `Audio`.
+    pub fn recording(&self) -> Recording {
+        self.recording.clone()
+    }
+}
+
+impl AudioBackend for RecordingBackend {
+    fn sample_rate(&self) -> u32 {
+        self.recording.sample_rate
+    }
+
+    fn update(&mut self, mixer: &Mutex<Mixer>, delta: f32) {
+        let samples = pull(mixer, delta, &mut self.carry);
+        self.recording.samples.lock().unwrap().extend(samples);
+    }
+}
+
+/// Plays on the default output device, from an audio thread.
+#[cfg(feature = "audio-device")]
+pub struct DeviceBackend {
+    sample_rate: u32,
+    _stream: cpal::Stream,
+}
+
+#[cfg(feature = "audio-device")]
+impl DeviceBackend {
+    /// Returns `None` if there's no usable output device.
+    pub fn new(mixer: Arc<Mutex<Mixer>>) -> Option<Self> {
+        use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+        let device = cpal::default_host().default_output_device()?;
+        let supported = device.default_output_config().ok()?;
+        let format = supported.sample_format();
+        let config: cpal::StreamConfig = supported.into();
+
+        let sample_rate = config.sample_rate.0;
+        let channels = config.channels as usize;
+
+        mixer.lock().unwrap().sample_rate = sample_rate;
+
+        fn callback<T: cpal::Sample>(
+            mixer: Arc<Mutex<Mixer>>,
+            channels: usize,
+        ) -> impl FnMut(&mut [T], &cpal::OutputCallbackInfo) {
+            let mut stereo = Vec::new();
+
+            move |data, _| {
+                let frames = data.len() / channels;
+                stereo.resize(frames * 2, 0.0);
+                mixer.lock().unwrap().mix(&mut stereo);
+
+                for (output, input) in data.chunks_exact_mut(channels).zip(stereo.chunks_exact(2)) {
+                    for (channel, sample) in output.iter_mut().enumerate() {
+                        let value = if channel < 2 { input[channel] } else { 0.0 };
+                        *sample = T::from(&value);
+                    }
+                }
+            }
+        }
+
+        let error = |error| eprintln!("Audio stream error: {}", error);
+
+        let stream = match format {
+            cpal::SampleFormat::F32 => {
+                device.build_output_stream(&config, callback::<f32>(mixer, channels), error)
+            }
+            cpal::SampleFormat::I16 => {
+                device.build_output_stream(&config, callback::<i16>(mixer, channels), error)
+            }
+            cpal::SampleFormat::U16 => {
+                device.build_output_stream(&config, callback::<u16>(mixer, channels), error)
+            }
+        }
+        .ok()?;
+
+        stream.play().ok()?;
+
+        Some(Self {
+            sample_rate,
+            _stream: stream,
+        })
+    }
+}
+
+#[cfg(feature = "audio-device")]
+impl AudioBackend for DeviceBackend {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+}
+
+/// Plays sounds and music; get it with `Context::audio`.
+pub struct Audio {
+    mixer: Arc<Mutex<Mixer>>,
+    backend: Box<dyn AudioBackend>,
+    music: Option<VoiceId>,
+    next_id: u64,
+    follow_camera: bool,
+    rolloff: Rolloff,
+    distances: (f32, f32),
+}
+
+impl Audio {
+    pub fn new<B: AudioBackend + 'static>(backend: B) -> Self {
+        Self::from_backend(Box::new(backend))
+    }
+
+    pub(crate) fn from_backend(backend: Box<dyn AudioBackend>) -> Self {
+        Self::with_mixer(
+            Arc::new(Mutex::new(Mixer::new(backend.sample_rate()))),
+            backend,
+        )
+    }
+
+    /// Plays on the sound card with the `audio-device` feature, if there is one; otherwise on a
+    /// `NullBackend`, warning once that nothing will be heard.
+    pub fn with_default_backend() -> Self {
+        let mixer = Arc::new(Mutex::new(Mixer::new(DEFAULT_SAMPLE_RATE)));
+
+        #[cfg(feature = "audio-device")]
+        {
+            if let Some(backend) = DeviceBackend::new(mixer.clone()) {
+                return Self::with_mixer(mixer, Box::new(backend));
+            }
+        }
+
+        static WARNED: AtomicBool = AtomicBool::new(false);
+
+        if !WARNED.swap(true, Ordering::Relaxed) {
+            if cfg!(feature = "audio-device") {
+                eprintln!("No usable audio output device, so sounds won't be heard");
+            } else {
+                eprintln!(
+                    "gamelib was built without the `audio-device` feature, so sounds won't be heard"
+                );
+            }
+        }
+
+        Self::with_mixer(mixer, Box::new(NullBackend::new(DEFAULT_SAMPLE_RATE)))
+    }
+
+    fn with_mixer(mixer: Arc<Mutex<Mixer>>, backend: Box<dyn AudioBackend>) -> Self {
+        Self {
+            mixer,
+            backend,
+            music: None,
+            next_id: 0,
+            follow_camera: true,
+            rolloff: Rolloff::Linear,
+            distances: (1.0, 10.0),
+        }
+    }
+
+    /// Start playing a sound effect once its settings are committed. `Context::play` plays
+    /// sounds from files.
+    pub fn play_sound(&mut self, sound: &Sound) -> SoundBuilder<'_> {
+        let source = Box::new(Buffered {
+            sound: sound.clone(),
+            position: 0,
+        });
+
+        SoundBuilder::new(self, source, Group::Sfx)
+    }
+
+    /// Stream a music track in a loop, fading out the current one and this one in over
+    /// `crossfade` seconds. The current track keeps playing if this one can't be opened.
+    pub fn play_music(&mut self, filename: &str, crossfade: f32) -> Result<VoiceId, Error> {
+        let source = Box::new(Streamed::open(filename)?);
+        self.stop_music(crossfade);
+
+        let voice = SoundBuilder::new(self, source, Group::Music)
+            .looping()
+            .fade_in(crossfade)
+            .commit();
+
+        self.music = Some(voice);
+        Ok(voice)
+    }
+
+    /// Fade the music out over `fade` seconds.
+    pub fn stop_music(&mut self, fade: f32) {
+        if let Some(music) = self.music.take() {
+            self.fade_out(music, fade);
+        }
+    }
+
+    pub fn stop(&mut self, voice: VoiceId) {
+        self.mixer
+            .lock()
+            .unwrap()
+            .voices
+            .retain(|other| other.id != voice);
+    }
+
+    /// Stop a voice after fading it out over `seconds`.
+    pub fn fade_out(&mut self, voice: VoiceId, seconds: f32) {
+        let mut mixer = self.mixer.lock().unwrap();
+        let sample_rate = mixer.sample_rate;
+
+        if let Some(voice) = mixer.voice_mut(voice) {
+            voice.fade.to(0.0, seconds, sample_rate, true);
+        }
+    }
+
+    pub fn is_playing(&self, voice: VoiceId) -> bool {
+        self.mixer
+            .lock()
+            .unwrap()
+            .voices
+            .iter()
+            .any(|other| other.id == voice)
+    }
+
+    pub fn set_voice_volume(&mut self, voice: VoiceId, volume: f32) {
+        if let Some(voice) = self.mixer.lock().unwrap().voice_mut(voice) {
+            voice.volume = volume;
+        }
+    }
+
+    pub fn set_voice_pan(&mut self, voice: VoiceId, pan: f32) {
+        if let Some(voice) = self.mixer.lock().unwrap().voice_mut(voice) {
+            voice.pan = pan;
+        }
+    }
+
+    /// Move a voice that was played at a position.
+    pub fn set_voice_position(&mut self, voice: VoiceId, x: f32, y: f32) {
+        if let Some(voice) = self.mixer.lock().unwrap().voice_mut(voice) {
+            if let Some(spatial) = &mut voice.spatial {
+                spatial.position = Vec2::new(x, y);
+            }
+        }
+    }
+
+    /// Where positioned sounds are heard from. By default it's wherever the canvas camera looks
+    /// at; setting it stops following the camera.
+    pub fn set_listener(&mut self, x: f32, y: f32) {
+        self.follow_camera = false;
+        self.mixer.lock().unwrap().listener = Vec2::new(x, y);
+    }
+
+    pub fn listener(&self) -> Vec2 {
+        self.mixer.lock().unwrap().listener
+    }
+
+    /// Go back to hearing positioned sounds from the camera.
+    pub fn follow_camera(&mut self) {
+        self.follow_camera = true;
+    }
+
+    /// Called by the game loop after every render.
+    pub(crate) fn camera_moved(&mut self, position: Vec2) {
+        if self.follow_camera {
+            self.mixer.lock().unwrap().listener = position;
+        }
+    }
+
+    /// The rolloff and distances of sounds played at a position, unless set on the sound itself.
+    /// Linear from 1.0 to 10.0 by default.
+    pub fn set_rolloff(&mut self, rolloff: Rolloff, min_distance: f32, max_distance: f32) {
+        self.rolloff = rolloff;
+        self.distances = (min_distance, max_distance);
+    }
+
+    pub fn set_volume(&mut self, group: Group, volume: f32) {
+        self.mixer.lock().unwrap().volumes.insert(group, volume);
+    }
+
+    pub fn volume(&self, group: Group) -> f32 {
+        self.mixer.lock().unwrap().volume(group)
+    }
+
+    /// Called by the game loop before every update. Returns why voices stopped early since last
+    /// time, e.g. music that couldn't be reopened to loop.
+    pub fn update(&mut self, delta: f32) -> Vec<Error> {
+        self.backend.update(&self.mixer, delta);
+
+        let mut mixer = self.mixer.lock().unwrap();
+        mixer.errors.drain(..).map(Error::from).collect()
+    }
+}
+
+impl Default for Audio {
+    fn default() -> Self {
+        Self::with_default_backend()
+    }
+}
+
+pub struct SoundBuilder<'a> {
+    audio: &'a mut Audio,
+    source: Box<dyn Source>,
+    group: Group,
+    volume: f32,
+    pitch: f32,
+    pan: f32,
+    position: Option<Vec2>,
+    rolloff: Rolloff,
+    distances: (f32, f32),
+    looping: bool,
+    fade_in: f32,
+}
+
+impl<'a> SoundBuilder<'a> {
+    fn new(audio: &'a mut Audio, source: Box<dyn Source>, group: Group) -> Self {
+        Self {
+            rolloff: audio.rolloff,
+            distances: audio.distances,
+            audio,
+            source,
+            group,
+            volume: 1.0,
+            pitch: 1.0,
+            pan: 0.0,
+            position: None,
+            looping: false,
+            fade_in: 0.0,
+        }
+    }
+
+    pub fn volume(mut self, volume: f32) -> Self {
+        self.volume *= volume;
+        self
+    }
+
+    /// Playback speed: 2.0 plays twice as fast, an octave higher.
+    pub fn pitch(mut self, pitch: f32) -> Self {
+        self.pitch *= pitch;
+        self
+    }
+
+    /// From -1.0, fully left, to 1.0, fully right. Ignored for sounds played at a position.
+    pub fn pan(mut self, pan: f32) -> Self {
+        self.pan = pan;
+        self
+    }
+
+    /// Play the sound at a position in the world, panned and attenuated depending on where the
+    /// listener is.
+    pub fn at(mut self, x: f32, y: f32) -> Self {
+        self.position = Some(Vec2::new(x, y));
+        self
+    }
+
+    pub fn at_tup(self, (x, y): (f32, f32)) -> Self {
+        self.at(x, y)
+    }
+
+    /// Only for sounds played at a position; see `Audio::set_rolloff`.
+    pub fn rolloff(mut self, rolloff: Rolloff, min_distance: f32, max_distance: f32) -> Self {
+        self.rolloff = rolloff;
+        self.distances = (min_distance, max_distance);
+        self
+    }
+
+    pub fn looping(mut self) -> Self {
+        self.looping = true;
+        self
+    }
+
+    /// `Group::Sfx` by default.
+    pub fn group(mut self, group: Group) -> Self {
+        self.group = group;
+        self
+    }
+
+    pub fn fade_in(mut self, seconds: f32) -> Self {
+        self.fade_in = seconds;
+        self
+    }
+
+    pub fn commit(self) -> VoiceId {
+        self.audio.next_id += 1;
+        let id = VoiceId(self.audio.next_id);
+
+        let spatial = self.position.map(|position| Spatial {
+            position,
+            rolloff: self.rolloff,
+            min_distance: self.distances.0,
+            max_distance: self.distances.1,
+        });
+
+        let mut mixer = self.audio.mixer.lock().unwrap();
+
+        let mut fade = Fade {
+            gain: 1.0,
+            target: 1.0,
+            step: 0.0,
+            stop: false,
+        };
+
+        if self.fade_in > 0.0 {
+            fade.gain = 0.0;
+            fade.to(1.0, self.fade_in, mixer.sample_rate, false);
+        }
+
+        mixer.voices.push(Voice {
+            id,
+            source: self.source,
+            group: self.group,
+            volume: self.volume,
+            pan: self.pan,
+            spatial,
+            pitch: self.pitch,
+            looping: self.looping,
+            fade,
+            current: [0.0, 0.0],
+            next: None,
+            fraction: 0.0,
+            started: false,
+            finished: false,
+            error: None,
+        });
+
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 1000;
+
+    /// Audio recording everything, along with the recording.
+    fn recorded() -> (Audio, Recording) {
+        let backend = RecordingBackend::new(RATE);
+        let recording = backend.recording();
+        (Audio::new(backend), recording)
+    }
+
+    /// A mono sound at a constant level.
+    fn constant(level: f32, frames: usize) -> Sound {
+        Sound::from_mono(&vec![level; frames], RATE)
+    }
+
+    /// Left and right samples of every frame recorded so far.
+    fn frames(recording: &Recording) -> Vec<Frame> {
+        to_frames(&recording.samples(), 2)
+    }
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-3, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn decode_ogg() {
+        // Eleven packets of 128 silent frames, where the first one only primes the decoder.
+        let bytes = include_bytes!("../tests/fixtures/silence.ogg").to_vec();
+        let sound = Sound::from_bytes(bytes).unwrap();
+
+        assert_eq!(sound.sample_rate(), 8000);
+        assert_eq!(sound.frames.len(), 1280);
+    }
+
+    #[test]
+    fn decode_garbage() {
+        let error = Sound::from_bytes(b"not a sound".to_vec()).err().unwrap();
+        assert!(matches!(error, Error::Sound(_)), "{:?}", error);
+    }
+
+    #[test]
+    fn group_volumes() {
+        let (mut audio, recording) = recorded();
+        audio.set_volume(Group::Master, 0.5);
+        audio.set_volume(Group::Sfx, 0.5);
+
+        audio.play_sound(&constant(1.0, 100)).volume(0.8).commit();
+        audio.update(0.01);
+
+        for frame in frames(&recording) {
+            assert_close(frame[0], 0.2);
+            assert_close(frame[1], 0.2);
+        }
+
+        recording.clear();
+        audio
+            .play_sound(&constant(1.0, 100))
+            .group(Group::Music)
+            .commit();
+        audio.update(0.01);
+
+        // Only the master volume applies to the music, on top of the first sound.
+        for frame in frames(&recording) {
+            assert_close(frame[0], 0.7);
+        }
+    }
+
+    #[test]
+    fn pan() {
+        let (mut audio, recording) = recorded();
+        let left = audio.play_sound(&constant(1.0, 100)).pan(-1.0).commit();
+        audio.update(0.01);
+
+        assert!(frames(&recording).iter().all(|frame| *frame == [1.0, 0.0]));
+
+        recording.clear();
+        audio.set_voice_pan(left, 0.5);
+        audio.update(0.01);
+
+        assert!(frames(&recording).iter().all(|frame| *frame == [0.5, 1.0]));
+    }
+
+    #[test]
+    fn looping() {
+        let (mut audio, recording) = recorded();
+        let once = audio.play_sound(&constant(0.5, 10)).commit();
+        let looping = audio.play_sound(&constant(0.25, 10)).looping().commit();
+        audio.update(0.1);
+
+        assert!(!audio.is_playing(once));
+        assert!(audio.is_playing(looping));
+
+        let frames = frames(&recording);
+        assert_eq!(frames.len(), 100);
+
+        // Both until the short sound ends, then only the looping one.
+        assert_close(frames[5][0], 0.75);
+        assert!(frames[20..].iter().all(|frame| frame[0] == 0.25));
+    }
+
+    #[test]
+    fn crossfade_music() {
+        let path = std::env::temp_dir().join(format!("gamelib-music-{}.wav", std::process::id()));
+
+        // Half a second of music at a constant level.
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: RATE,
+            bits_per_sample: 32,
+            sample_format: SampleFormat::Float,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+
+        for _ in 0..RATE / 2 {
+            writer.write_sample(0.5f32).unwrap();
+        }
+
+        writer.finalize().unwrap();
+
+        let (mut audio, recording) = recorded();
+        let path = path.to_str().unwrap();
+
+        let first = audio.play_music(path, 0.0).unwrap();
+        audio.update(0.1);
+
+        let second = audio.play_music(path, 0.2).unwrap();
+        audio.update(0.3);
+
+        // One track fades out while the other fades in, keeping the level.
+        for frame in frames(&recording) {
+            assert_close(frame[0], 0.5);
+        }
+
+        assert!(!audio.is_playing(first));
+        assert!(audio.is_playing(second));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn missing_files() {
+        let (mut audio, _) = recorded();
+        let path = std::env::temp_dir().join(format!("gamelib-missing-{}.wav", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        assert!(matches!(Sound::load(path), Err(Error::Asset(error)) if error.path == path));
+        assert!(audio.play_music(path, 0.0).is_err());
+    }
+
+    #[test]
+    fn music_deleted_while_looping() {
+        let path = std::env::temp_dir().join(format!("gamelib-deleted-{}.wav", std::process::id()));
+
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: RATE,
+            bits_per_sample: 16,
+            sample_format: SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+
+        for _ in 0..10 {
+            writer.write_sample(0i16).unwrap();
+        }
+
+        writer.finalize().unwrap();
+
+        let (mut audio, _) = recorded();
+        let music = audio.play_music(path.to_str().unwrap(), 0.0).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // The music stops when it can't be reopened to loop, instead of taking the game down.
+        let errors = audio.update(0.1);
+        assert_eq!(errors.len(), 1);
+        assert!(!audio.is_playing(music));
+        assert!(audio.update(0.1).is_empty());
+    }
+
+    #[test]
+    fn fades() {
+        let (mut audio, recording) = recorded();
+        let voice = audio.play_sound(&constant(1.0, 1000)).fade_in(0.1).commit();
+        audio.update(0.1);
+
+        let fade_in: Vec<_> = frames(&recording).iter().map(|frame| frame[0]).collect();
+        assert!(fade_in.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_close(fade_in[99], 1.0);
+
+        recording.clear();
+        audio.fade_out(voice, 0.1);
+        audio.update(0.2);
+
+        let fade_out: Vec<_> = frames(&recording).iter().map(|frame| frame[0]).collect();
+        assert!(fade_out[..100].windows(2).all(|pair| pair[0] > pair[1]));
+        assert!(fade_out[100..].iter().all(|&sample| sample == 0.0));
+        assert!(!audio.is_playing(voice));
+    }
+
+    #[test]
+    fn pitch() {
+        let (mut audio, recording) = recorded();
+        let ramp: Vec<f32> = (0..100).map(|i| i as f32 / 100.0).collect();
+        let sound = Sound::from_mono(&ramp, RATE);
+
+        audio.play_sound(&sound).pitch(2.0).commit();
+        audio.update(0.1);
+
+        let frames = frames(&recording);
+
+        // Twice as fast, so done halfway.
+        for (i, frame) in frames[..49].iter().enumerate() {
+            assert_close(frame[0], ramp[i * 2]);
+        }
+
+        assert!(frames[50..].iter().all(|frame| frame[0] == 0.0));
+
+        // Half as fast, interpolating between the frames.
+        let (mut audio, recording) = recorded();
+        audio.play_sound(&sound).pitch(0.5).commit();
+        audio.update(0.01);
+
+        let frames = self::frames(&recording);
+        assert_close(frames[1][0], 0.005);
+        assert_close(frames[2][0], 0.01);
+    }
+
+    fn spatial(x: f32, y: f32, rolloff: Rolloff) -> Spatial {
+        Spatial {
+            position: Vec2::new(x, y),
+            rolloff,
+            min_distance: 1.0,
+            max_distance: 10.0,
+        }
+    }
+
+    #[test]
+    fn rolloffs() {
+        let listener = Vec2::new(0.0, 0.0);
+        let rolloffs = [
+            (Rolloff::Linear, 0.5),
+            (Rolloff::Inverse, 1.0 / 5.5),
+            (Rolloff::Exponential(2.0), 1.0 / 30.25),
+        ];
+
+        for (rolloff, halfway) in rolloffs {
+            assert_eq!(spatial(0.0, 0.0, rolloff).apply(listener), (1.0, 0.0));
+            assert_eq!(spatial(0.0, 0.5, rolloff).apply(listener).0, 1.0);
+            assert_eq!(spatial(0.0, 1.0, rolloff).apply(listener).0, 1.0);
+            assert_close(spatial(0.0, 5.5, rolloff).apply(listener).0, halfway);
+            assert_eq!(spatial(0.0, 10.0, rolloff).apply(listener).0, 0.0);
+            assert_eq!(spatial(0.0, 20.0, rolloff).apply(listener).0, 0.0);
+        }
+    }
+
+    #[test]
+    fn spatial_pan() {
+        let listener = Vec2::new(2.0, 3.0);
+
+        assert_eq!(spatial(7.0, 3.0, Rolloff::Linear).apply(listener).1, 1.0);
+        assert_eq!(spatial(-3.0, 3.0, Rolloff::Linear).apply(listener).1, -1.0);
+        assert_eq!(spatial(2.0, 8.0, Rolloff::Linear).apply(listener).1, 0.0);
+        assert_close(spatial(5.0, 7.0, Rolloff::Linear).apply(listener).1, 0.6);
+        assert_close(
+            spatial(1.0, 6.0, Rolloff::Linear).apply(listener).1,
+            -0.1f32.sqrt(),
+        );
+
+        // Within the minimum distance, towards the center.
+        assert_close(spatial(2.5, 3.0, Rolloff::Linear).apply(listener).1, 0.5);
+    }
+}
@@ -0,0 +1,25 @@
+//! Packs asset files into an archive for `gamelib::vfs::Archive`, keeping their paths as given:
+//!
+//!     gamelib-pack assets.pak textures sounds/music.ogg
+
+use std::env;
+use std::process;
+
+use gamelib::vfs::Archive;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    if args.len() < 2 {
+        eprintln!("Usage: gamelib-pack <archive> <file or directory>...");
+        process::exit(2);
+    }
+
+    match Archive::pack(&args[0], &args[1..]) {
+        Ok(count) => println!("Packed {} files into {}", count, args[0]),
+        Err(error) => {
+            eprintln!("Couldn't pack {}: {}", args[0], error);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,353 @@
+use glium::texture::RawImage2d;
+use glium::Display;
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, ImageError, RgbaImage};
+
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::error::Error;
+
+/// Frames recorded per second, at most. GIF delays are in hundredths of a second, so 25 keeps
+/// them exact.
+pub const RECORDING_FPS: f32 = 25.0;
+
+/// The last frame shown in the window.
+pub fn grab_frame(display: &Display) -> Result<RgbaImage, Error> {
+    let raw: RawImage2d<u8> = display.read_front_buffer()?;
+    let (width, height) = (raw.width, raw.height);
+    let data = raw.data.into_owned();
+    let length = data.len();
+
+    let mut image = RgbaImage::from_raw(width, height, data).ok_or(Error::PixelBufferSize {
+        width,
+        height,
+        length,
+    })?;
+
+    // OpenGL reads the bottom row first.
+    image::imageops::flip_vertical_in_place(&mut image);
+    Ok(image)
+}
+
+/// A name in the working directory that won't clash with earlier captures.
+pub fn timestamped(prefix: &str, extension: &str) -> String {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+
+    format!("{}-{}.{}", prefix, time, extension)
+}
+
+/// Where recorded frames go.
+enum Output {
+    Gif(GifEncoder<BufWriter<File>>),
+    /// Numbered PNGs in a directory.
+    Sequence(PathBuf, u32),
+}
+
+impl Output {
+    fn new(path: &str) -> Result<Self, ImageError> {
+        if path.to_lowercase().ends_with(".gif") {
+            let file = File::create(path)?;
+            // Quicker to encode than the best quality, to keep up with the game.
+            let mut encoder = GifEncoder::new_with_speed(BufWriter::new(file), 10);
+            encoder.set_repeat(Repeat::Infinite)?;
+
+            Ok(Output::Gif(encoder))
+        } else {
+            fs::create_dir_all(path)?;
+            Ok(Output::Sequence(PathBuf::from(path), 0))
+        }
+    }
+
+    fn write(&mut self, image: RgbaImage, delay: Duration) -> Result<(), ImageError> {
+        match self {
+            Output::Gif(encoder) => {
+                let delay = Delay::from_saturating_duration(delay);
+                encoder.encode_frame(Frame::from_parts(image, 0, 0, delay))
+            }
+            Output::Sequence(directory, count) => {
+                *count += 1;
+                image.save(directory.join(format!("frame-{:05}.png", count)))
+            }
+        }
+    }
+}
+
+/// An encoding thread, which stops at the first error.
+struct Encoder {
+    path: String,
+    thread: JoinHandle<Result<(), ImageError>>,
+}
+
+impl Encoder {
+    /// Wait for the thread to finish, and return how it went.
+    fn join(self) -> Result<(), Error> {
+        let path = self.path;
+
+        match self.thread.join() {
+            Ok(result) => result.map_err(|error| Error::Capture { path, error }),
+            // Panics are printed as they happen.
+            Err(_) => Ok(()),
+        }
+    }
+}
+
+/// Encodes frames on a thread of its own, since GIFs are slow to encode.
+struct Recording {
+    frames: Sender<(RgbaImage, Duration)>,
+    /// Frames are sent once the next one comes, to know how long they're shown.
+    pending: Option<(RgbaImage, Instant)>,
+}
+
+impl Recording {
+    fn start(path: &str) -> (Self, Encoder) {
+        let (frames, receiver) = mpsc::channel::<(RgbaImage, Duration)>();
+        let output_path = path.to_string();
+
+        let thread = thread::spawn(move || {
+            let mut output = Output::new(&output_path)?;
+
+            for (image, delay) in receiver {
+                output.write(image, delay)?;
+            }
+
+            Ok(())
+        });
+
+        let recording = Self {
+            frames,
+            pending: None,
+        };
+
+        let encoder = Encoder {
+            path: path.to_string(),
+            thread,
+        };
+
+        (recording, encoder)
+    }
+
+    /// Whether enough time passed since the last recorded frame.
+    fn is_due(&self, now: Instant) -> bool {
+        match &self.pending {
+            Some((_, last)) => now.duration_since(*last).as_secs_f32() >= 1.0 / RECORDING_FPS,
+            None => true,
+        }
+    }
+
+    fn add(&mut self, image: RgbaImage, now: Instant) {
+        if let Some((previous, last)) = self.pending.replace((image, now)) {
+            // The encoder is gone if it failed, which `Capture` reports.
+            let _ = self.frames.send((previous, now.duration_since(last)));
+        }
+    }
+
+    fn stop(mut self) {
+        if let Some((image, _)) = self.pending.take() {
+            let delay = Duration::from_secs_f32(1.0 / RECORDING_FPS);
+            let _ = self.frames.send((image, delay));
+        }
+    }
+}
+
+/// Screenshots and recordings asked for by the game, taken once frames are shown.
+#[derive(Default)]
+pub(crate) struct Capture {
+    screenshots: Vec<String>,
+    recording: Option<Recording>,
+    /// Finished before the game exits.
+    encoders: Vec<Encoder>,
+}
+
+impl Capture {
+    pub fn screenshot(&mut self, filename: &str) {
+        self.screenshots.push(filename.to_string());
+    }
+
+    pub fn start_recording(&mut self, path: &str) {
+        self.stop_recording();
+
+        let (recording, encoder) = Recording::start(path);
+        self.recording = Some(recording);
+        self.encoders.push(encoder);
+    }
+
+    pub fn stop_recording(&mut self) {
+        if let Some(recording) = self.recording.take() {
+            recording.stop();
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Save what was asked for from the frame just shown. Returns what went wrong, including
+    /// recordings that failed in the background since last time.
+    pub fn frame_shown(&mut self, display: &Display) -> Vec<Error> {
+        let mut errors = self.finished_encoders();
+
+        let now = Instant::now();
+        let recording = matches!(&self.recording, Some(recording) if recording.is_due(now));
+
+        if self.screenshots.is_empty() && !recording {
+            return errors;
+        }
+
+        let image = match grab_frame(display) {
+            Ok(image) => image,
+            Err(error) => {
+                self.screenshots.clear();
+                errors.push(error);
+                return errors;
+            }
+        };
+
+        for filename in self.screenshots.drain(..) {
+            if let Err(error) = image.save(&filename) {
+                errors.push(Error::Capture {
+                    path: filename,
+                    error,
+                });
+            }
+        }
+
+        if let (true, Some(recording)) = (recording, &mut self.recording) {
+            recording.add(image, now);
+        }
+
+        errors
+    }
+
+    /// Wait for recordings to be written out, returning the errors of those that failed.
+    pub fn finish(&mut self) -> Vec<Error> {
+        self.stop_recording();
+
+        self.encoders
+            .drain(..)
+            .filter_map(|encoder| encoder.join().err())
+            .collect()
+    }
+
+    /// Errors of the encoders that stopped, which only happens early when they fail.
+    fn finished_encoders(&mut self) -> Vec<Error> {
+        let mut errors = Vec::new();
+        let mut index = 0;
+
+        while index < self.encoders.len() {
+            if self.encoders[index].thread.is_finished() {
+                errors.extend(self.encoders.remove(index).join().err());
+            } else {
+                index += 1;
+            }
+        }
+
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use image::codecs::gif::GifDecoder;
+    use image::AnimationDecoder;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("gamelib-capture-{}", std::process::id()))
+            .join(name)
+    }
+
+    fn missing_path(name: &str) -> String {
+        temp_path("missing")
+            .join(name)
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn frames_are_due_at_the_recording_rate() {
+        let path = temp_path("due");
+        let (mut recording, encoder) = Recording::start(&path.to_string_lossy());
+        let start = Instant::now();
+        assert!(recording.is_due(start));
+
+        recording.add(RgbaImage::new(2, 2), start);
+        assert!(!recording.is_due(start + Duration::from_millis(20)));
+        assert!(recording.is_due(start + Duration::from_millis(40)));
+
+        recording.stop();
+        encoder.join().unwrap();
+        let _ = fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn recording_a_gif_writes_every_frame() {
+        let path = temp_path("clip.gif");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let (mut recording, encoder) = Recording::start(&path.to_string_lossy());
+
+        let start = Instant::now();
+        recording.add(RgbaImage::new(2, 2), start);
+        recording.add(RgbaImage::new(2, 2), start + Duration::from_millis(40));
+        recording.stop();
+        encoder.join().unwrap();
+
+        let decoder = GifDecoder::new(File::open(&path).unwrap()).unwrap();
+        assert_eq!(decoder.into_frames().count(), 2);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn recording_a_sequence_writes_numbered_frames() {
+        let directory = temp_path("sequence");
+        let mut output = Output::new(&directory.to_string_lossy()).unwrap();
+
+        let image = RgbaImage::new(2, 2);
+        output
+            .write(image.clone(), Duration::from_millis(20))
+            .unwrap();
+        output.write(image, Duration::from_millis(20)).unwrap();
+
+        assert!(directory.join("frame-00001.png").exists());
+        assert!(directory.join("frame-00002.png").exists());
+        let _ = fs::remove_dir_all(directory);
+    }
+
+    #[test]
+    fn recording_to_a_bad_path_is_reported() {
+        let mut capture = Capture::default();
+        let path = missing_path("clip.gif");
+        capture.start_recording(&path);
+
+        let errors = capture.finish();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(&errors[0], Error::Capture { path: failed, .. } if *failed == path));
+        assert!(capture.encoders.is_empty());
+    }
+
+    #[test]
+    fn failed_encoders_are_collected_once() {
+        let mut capture = Capture::default();
+        capture.start_recording(&missing_path("clip.gif"));
+        capture.stop_recording();
+
+        let encoder = &capture.encoders[0];
+        while !encoder.thread.is_finished() {
+            thread::yield_now();
+        }
+
+        assert_eq!(capture.finished_encoders().len(), 1);
+        assert!(capture.finished_encoders().is_empty());
+        assert!(capture.finish().is_empty());
+    }
+}
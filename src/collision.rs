@@ -0,0 +1,848 @@
+use crate::utils::{Rectangle, Vec2};
+
+/// Result of an overlap test.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Contact {
+    /// Unit vector pointing from the first shape towards the second one.
+    pub normal: Vec2,
+    /// How far the second shape has to move along `normal` to stop overlapping the first one.
+    pub depth: f32,
+}
+
+/// Result of a ray, segment or shape cast.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RayHit {
+    pub point: Vec2,
+    /// Unit surface normal of the hit shape, pointing against the cast.
+    pub normal: Vec2,
+    /// Portion of the cast travelled before the hit, in multiples of the cast direction.
+    pub fraction: f32,
+}
+
+impl Contact {
+    /// The same contact as seen from the other shape.
+    pub fn flipped(self) -> Self {
+        Self {
+            normal: -self.normal,
+            depth: self.depth,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Circle {
+    pub center: Vec2,
+    pub radius: f32,
+}
+
+impl Circle {
+    pub fn new(center: Vec2, radius: f32) -> Self {
+        Self { center, radius }
+    }
+}
+
+/// Oriented bounding box: a rectangle rotated around its center.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Obb {
+    pub center: Vec2,
+    pub half_extents: Vec2,
+    pub rotation: f32,
+}
+
+impl Obb {
+    pub fn new(center: Vec2, half_extents: Vec2, rotation: f32) -> Self {
+        Self {
+            center,
+            half_extents,
+            rotation,
+        }
+    }
+
+    pub fn corners(&self) -> [Vec2; 4] {
+        let x = Vec2::new(self.half_extents.x, 0.0).rotate(self.rotation);
+        let y = Vec2::new(0.0, self.half_extents.y).rotate(self.rotation);
+
+        [
+            self.center - x - y,
+            self.center + x - y,
+            self.center + x + y,
+            self.center - x + y,
+        ]
+    }
+}
+
+/// A convex polygon. The winding order of the points doesn't matter, but concave polygons produce
+/// wrong results.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polygon {
+    points: Vec<Vec2>,
+}
+
+impl Polygon {
+    pub fn new(points: Vec<Vec2>) -> Self {
+        Self { points }
+    }
+
+    pub fn points(&self) -> &[Vec2] {
+        &self.points
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Segment {
+    pub start: Vec2,
+    pub end: Vec2,
+}
+
+impl Segment {
+    pub fn new(start: Vec2, end: Vec2) -> Self {
+        Self { start, end }
+    }
+
+    pub fn closest_point(&self, point: Vec2) -> Vec2 {
+        let direction = self.end - self.start;
+        let length_squared = direction.length_squared();
+
+        if length_squared == 0.0 {
+            return self.start;
+        }
+
+        let t = ((point - self.start).dot(direction) / length_squared).clamp(0.0, 1.0);
+
+        self.start + direction * t
+    }
+}
+
+/// Anything that can be tested for overlap with `Shape::contact`.
+///
+/// Shapes merely touching each other don't count as overlapping.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+    Point(Vec2),
+    Segment(Segment),
+    Circle(Circle),
+    Rectangle(Rectangle),
+    Obb(Obb),
+    Polygon(Polygon),
+}
+
+impl Shape {
+    pub fn overlaps(&self, other: &Shape) -> bool {
+        self.contact(other).is_some()
+    }
+
+    pub fn contact(&self, other: &Shape) -> Option<Contact> {
+        match (self, other) {
+            (Shape::Circle(a), Shape::Circle(b)) => circle_circle(a, b),
+            (Shape::Circle(circle), _) => {
+                circle_vertices(circle, &other.vertices(), other).map(Contact::flipped)
+            }
+            (_, Shape::Circle(circle)) => circle_vertices(circle, &self.vertices(), self),
+            _ => sat(self, other),
+        }
+    }
+
+    pub fn contains_point(&self, point: Vec2) -> bool {
+        self.overlaps(&Shape::Point(point))
+    }
+
+    /// Cast a ray from `origin` towards `origin + direction * max_fraction`.
+    ///
+    /// Rays starting inside the shape hit it immediately, at fraction 0.
+    pub fn raycast(&self, origin: Vec2, direction: Vec2, max_fraction: f32) -> Option<RayHit> {
+        let hit = match self {
+            Shape::Circle(circle) => {
+                raycast_circle(circle.center, circle.radius, origin, direction)
+            }
+            _ => raycast_hull(&convex_hull(&self.vertices()), 0.0, origin, direction),
+        }?;
+
+        if hit.fraction <= max_fraction {
+            Some(hit)
+        } else {
+            None
+        }
+    }
+
+    /// Like `raycast`, but the fraction of the hit is relative to the segment's length.
+    pub fn segment_cast(&self, segment: &Segment) -> Option<RayHit> {
+        self.raycast(segment.start, segment.end - segment.start, 1.0)
+    }
+
+    /// Move this shape by `motion` and report when it first hits `target`, with the fraction
+    /// relative to `motion`.
+    ///
+    /// The normal points out of `target`, and the point is where the shapes touch.
+    pub fn cast(&self, motion: Vec2, target: &Shape) -> Option<RayHit> {
+        // The shapes touch when the offset of this shape lies within the Minkowski difference
+        // `target - self`, which turns the cast into a raycast from the origin.
+        let hit = match (self, target) {
+            (Shape::Circle(a), Shape::Circle(b)) => {
+                raycast_circle(b.center - a.center, a.radius + b.radius, Vec2::ZERO, motion)
+            }
+            (Shape::Circle(circle), _) => {
+                let difference: Vec<_> = target
+                    .vertices()
+                    .iter()
+                    .map(|&vertex| vertex - circle.center)
+                    .collect();
+
+                raycast_hull(&convex_hull(&difference), circle.radius, Vec2::ZERO, motion)
+            }
+            (_, Shape::Circle(circle)) => {
+                let difference: Vec<_> = self
+                    .vertices()
+                    .iter()
+                    .map(|&vertex| circle.center - vertex)
+                    .collect();
+
+                raycast_hull(&convex_hull(&difference), circle.radius, Vec2::ZERO, motion)
+            }
+            _ => {
+                let target_vertices = target.vertices();
+                let mut difference = Vec::new();
+
+                for vertex in self.vertices() {
+                    for &target_vertex in &target_vertices {
+                        difference.push(target_vertex - vertex);
+                    }
+                }
+
+                raycast_hull(&convex_hull(&difference), 0.0, Vec2::ZERO, motion)
+            }
+        }?;
+
+        if hit.fraction > 1.0 {
+            return None;
+        }
+
+        Some(RayHit {
+            point: self.support(-hit.normal) + motion * hit.fraction,
+            ..hit
+        })
+    }
+
+    /// The farthest point of the shape in the given direction.
+    fn support(&self, direction: Vec2) -> Vec2 {
+        match self {
+            Shape::Circle(circle) => circle.center + direction.normalize() * circle.radius,
+            _ => self
+                .vertices()
+                .into_iter()
+                .max_by(|a, b| a.dot(direction).total_cmp(&b.dot(direction)))
+                .unwrap_or(Vec2::ZERO),
+        }
+    }
+
+    pub fn center(&self) -> Vec2 {
+        match self {
+            Shape::Point(point) => *point,
+            Shape::Circle(circle) => circle.center,
+            Shape::Obb(obb) => obb.center,
+            _ => {
+                let vertices = self.vertices();
+                let sum = vertices
+                    .iter()
+                    .fold(Vec2::ZERO, |sum, &vertex| sum + vertex);
+                sum / vertices.len().max(1) as f32
+            }
+        }
+    }
+
+    /// The smallest axis-aligned rectangle containing the shape.
+    pub fn bounds(&self) -> Rectangle {
+        let (min, max) = match self {
+            Shape::Circle(circle) => {
+                let radius = Vec2::new(circle.radius, circle.radius);
+                (circle.center - radius, circle.center + radius)
+            }
+            _ => {
+                let vertices = self.vertices();
+                let first = vertices.first().copied().unwrap_or(Vec2::ZERO);
+
+                vertices.iter().fold((first, first), |(min, max), &vertex| {
+                    (min.min(vertex), max.max(vertex))
+                })
+            }
+        };
+
+        Rectangle::new(min.x, min.y, max.x - min.x, max.y - min.y)
+    }
+
+    /// A copy of the shape moved by `offset`.
+    pub fn translated(&self, offset: Vec2) -> Shape {
+        match self {
+            Shape::Point(point) => Shape::Point(*point + offset),
+            Shape::Segment(segment) => {
+                Shape::Segment(Segment::new(segment.start + offset, segment.end + offset))
+            }
+            Shape::Circle(circle) => {
+                Shape::Circle(Circle::new(circle.center + offset, circle.radius))
+            }
+            Shape::Rectangle(rect) => Shape::Rectangle(Rectangle::new(
+                rect.x + offset.x,
+                rect.y + offset.y,
+                rect.w,
+                rect.h,
+            )),
+            Shape::Obb(obb) => Shape::Obb(Obb::new(
+                obb.center + offset,
+                obb.half_extents,
+                obb.rotation,
+            )),
+            Shape::Polygon(polygon) => Shape::Polygon(Polygon::new(
+                polygon.points.iter().map(|&point| point + offset).collect(),
+            )),
+        }
+    }
+
+    /// Vertices of the shape; empty for circles.
+    pub(crate) fn vertices(&self) -> Vec<Vec2> {
+        match self {
+            Shape::Point(point) => vec![*point],
+            Shape::Segment(segment) => vec![segment.start, segment.end],
+            Shape::Circle(_) => Vec::new(),
+            Shape::Rectangle(rect) => vec![
+                Vec2::new(rect.x, rect.y),
+                Vec2::new(rect.x + rect.w, rect.y),
+                Vec2::new(rect.x + rect.w, rect.y + rect.h),
+                Vec2::new(rect.x, rect.y + rect.h),
+            ],
+            Shape::Obb(obb) => obb.corners().to_vec(),
+            Shape::Polygon(polygon) => polygon.points.clone(),
+        }
+    }
+
+    /// Separating axis candidates contributed by this shape.
+    fn axes(&self) -> Vec<Vec2> {
+        match self {
+            Shape::Point(_) => vec![Vec2::new(1.0, 0.0), Vec2::new(0.0, 1.0)],
+            Shape::Segment(segment) => {
+                let direction = (segment.end - segment.start).normalize();
+                vec![direction, direction.perp()]
+            }
+            Shape::Circle(_) => Vec::new(),
+            Shape::Rectangle(_) => vec![Vec2::new(1.0, 0.0), Vec2::new(0.0, 1.0)],
+            Shape::Obb(obb) => {
+                let x = Vec2::from_angle(obb.rotation);
+                vec![x, x.perp()]
+            }
+            Shape::Polygon(polygon) => {
+                let points = &polygon.points;
+
+                (0..points.len())
+                    .map(|i| {
+                        (points[(i + 1) % points.len()] - points[i])
+                            .perp()
+                            .normalize()
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+impl From<Rectangle> for Shape {
+    fn from(rect: Rectangle) -> Self {
+        Shape::Rectangle(rect)
+    }
+}
+
+impl From<Circle> for Shape {
+    fn from(circle: Circle) -> Self {
+        Shape::Circle(circle)
+    }
+}
+
+impl From<Obb> for Shape {
+    fn from(obb: Obb) -> Self {
+        Shape::Obb(obb)
+    }
+}
+
+impl From<Polygon> for Shape {
+    fn from(polygon: Polygon) -> Self {
+        Shape::Polygon(polygon)
+    }
+}
+
+impl From<Segment> for Shape {
+    fn from(segment: Segment) -> Self {
+        Shape::Segment(segment)
+    }
+}
+
+impl From<Vec2> for Shape {
+    fn from(point: Vec2) -> Self {
+        Shape::Point(point)
+    }
+}
+
+fn project(vertices: &[Vec2], axis: Vec2) -> (f32, f32) {
+    vertices.iter().map(|vertex| vertex.dot(axis)).fold(
+        (f32::INFINITY, f32::NEG_INFINITY),
+        |(min, max), projection| (min.min(projection), max.max(projection)),
+    )
+}
+
+/// Find the axis of least penetration among `axes`, oriented from `a` towards `b`.
+fn least_penetration(
+    axes: &[Vec2],
+    project_a: impl Fn(Vec2) -> (f32, f32),
+    project_b: impl Fn(Vec2) -> (f32, f32),
+) -> Option<Contact> {
+    let mut best: Option<Contact> = None;
+
+    for &axis in axes {
+        if axis == Vec2::ZERO {
+            continue;
+        }
+
+        let (min_a, max_a) = project_a(axis);
+        let (min_b, max_b) = project_b(axis);
+
+        // Pushing `b` forwards or backwards along the axis, whichever is shorter.
+        let forwards = max_a - min_b;
+        let backwards = max_b - min_a;
+
+        let contact = if forwards < backwards {
+            Contact {
+                normal: axis,
+                depth: forwards,
+            }
+        } else {
+            Contact {
+                normal: -axis,
+                depth: backwards,
+            }
+        };
+
+        if contact.depth <= 0.0 {
+            return None;
+        }
+
+        let shallower = match best {
+            Some(best) => contact.depth < best.depth,
+            None => true,
+        };
+
+        if shallower {
+            best = Some(contact);
+        }
+    }
+
+    best
+}
+
+fn sat(a: &Shape, b: &Shape) -> Option<Contact> {
+    let vertices_a = a.vertices();
+    let vertices_b = b.vertices();
+
+    let mut axes = a.axes();
+    axes.extend(b.axes());
+
+    least_penetration(
+        &axes,
+        |axis| project(&vertices_a, axis),
+        |axis| project(&vertices_b, axis),
+    )
+}
+
+fn circle_circle(a: &Circle, b: &Circle) -> Option<Contact> {
+    let offset = b.center - a.center;
+    let distance = offset.length();
+    let depth = a.radius + b.radius - distance;
+
+    if depth <= 0.0 {
+        return None;
+    }
+
+    let normal = if distance == 0.0 {
+        Vec2::new(1.0, 0.0)
+    } else {
+        offset / distance
+    };
+
+    Some(Contact { normal, depth })
+}
+
+/// Contact between a polygonal `shape` with the given vertices and a circle.
+fn circle_vertices(circle: &Circle, vertices: &[Vec2], shape: &Shape) -> Option<Contact> {
+    let closest = vertices.iter().copied().min_by(|a, b| {
+        a.distance(circle.center)
+            .total_cmp(&b.distance(circle.center))
+    })?;
+
+    let mut axes = shape.axes();
+    axes.push((circle.center - closest).normalize());
+
+    let project_circle = |axis: Vec2| {
+        let center = circle.center.dot(axis);
+        (center - circle.radius, center + circle.radius)
+    };
+
+    least_penetration(&axes, |axis| project(vertices, axis), project_circle)
+}
+
+/// Cast a ray through all `shapes`, returning the index of the first shape hit along with the hit.
+pub fn raycast_first<'a>(
+    shapes: impl IntoIterator<Item = &'a Shape>,
+    origin: Vec2,
+    direction: Vec2,
+    max_fraction: f32,
+) -> Option<(usize, RayHit)> {
+    shapes
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, shape)| {
+            shape
+                .raycast(origin, direction, max_fraction)
+                .map(|hit| (index, hit))
+        })
+        .min_by(|(_, a), (_, b)| a.fraction.total_cmp(&b.fraction))
+}
+
+/// Counter-clockwise convex hull of the points, using Andrew's monotone chain.
+fn convex_hull(points: &[Vec2]) -> Vec<Vec2> {
+    let mut points = points.to_vec();
+
+    points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+
+    points.dedup();
+
+    if points.len() < 3 {
+        return points;
+    }
+
+    let mut hull: Vec<Vec2> = Vec::new();
+
+    for pass in 0..2 {
+        let start = hull.len();
+
+        let iter: Box<dyn Iterator<Item = &Vec2>> = if pass == 0 {
+            Box::new(points.iter())
+        } else {
+            Box::new(points.iter().rev())
+        };
+
+        for &point in iter {
+            while hull.len() >= start + 2 {
+                let a = hull[hull.len() - 2];
+                let b = hull[hull.len() - 1];
+
+                if (b - a).cross(point - a) > 0.0 {
+                    break;
+                }
+
+                hull.pop();
+            }
+
+            hull.push(point);
+        }
+
+        // The last point of each chain is the first one of the next.
+        hull.pop();
+    }
+
+    hull
+}
+
+fn raycast_circle(center: Vec2, radius: f32, origin: Vec2, direction: Vec2) -> Option<RayHit> {
+    let offset = origin - center;
+    let c = offset.length_squared() - radius * radius;
+
+    if c < 0.0 {
+        return Some(inside_hit(origin, direction));
+    }
+
+    let a = direction.length_squared();
+    let b = 2.0 * offset.dot(direction);
+    let discriminant = b * b - 4.0 * a * c;
+
+    if a == 0.0 || discriminant < 0.0 {
+        return None;
+    }
+
+    let fraction = (-b - discriminant.sqrt()) / (2.0 * a);
+
+    if fraction < 0.0 {
+        return None;
+    }
+
+    let point = origin + direction * fraction;
+
+    Some(RayHit {
+        point,
+        normal: (point - center).normalize(),
+        fraction,
+    })
+}
+
+fn raycast_segment(start: Vec2, end: Vec2, origin: Vec2, direction: Vec2) -> Option<RayHit> {
+    let edge = end - start;
+    let denominator = direction.cross(edge);
+
+    if denominator == 0.0 {
+        return None;
+    }
+
+    let fraction = (start - origin).cross(edge) / denominator;
+    let along_edge = (start - origin).cross(direction) / denominator;
+
+    if fraction < 0.0 || !(0.0..=1.0).contains(&along_edge) {
+        return None;
+    }
+
+    let mut normal = edge.perp().normalize();
+
+    if normal.dot(direction) > 0.0 {
+        normal = -normal;
+    }
+
+    Some(RayHit {
+        point: origin + direction * fraction,
+        normal,
+        fraction,
+    })
+}
+
+fn inside_hit(origin: Vec2, direction: Vec2) -> RayHit {
+    RayHit {
+        point: origin,
+        normal: -direction.normalize(),
+        fraction: 0.0,
+    }
+}
+
+/// Raycast against a counter-clockwise convex hull inflated by `radius`.
+fn raycast_hull(hull: &[Vec2], radius: f32, origin: Vec2, direction: Vec2) -> Option<RayHit> {
+    let edges: Vec<(Vec2, Vec2)> = match hull.len() {
+        0 => return None,
+        1 => Vec::new(),
+        2 => vec![(hull[0], hull[1]), (hull[1], hull[0])],
+        _ => (0..hull.len())
+            .map(|i| (hull[i], hull[(i + 1) % hull.len()]))
+            .collect(),
+    };
+
+    // Outward normals of a counter-clockwise hull point to the right of the edges.
+    let outward = |(start, end): (Vec2, Vec2)| -(end - start).perp().normalize();
+
+    let inside_hull = hull.len() >= 3
+        && edges
+            .iter()
+            .all(|&edge| (origin - edge.0).dot(outward(edge)) < 0.0);
+
+    let inside_rounding = radius > 0.0
+        && (edges.iter().any(|&(start, end)| {
+            Segment::new(start, end)
+                .closest_point(origin)
+                .distance(origin)
+                < radius
+        }) || hull.iter().any(|&vertex| vertex.distance(origin) < radius));
+
+    if inside_hull || inside_rounding {
+        return Some(inside_hit(origin, direction));
+    }
+
+    let mut hits = Vec::new();
+
+    for &edge in &edges {
+        let offset = outward(edge) * radius;
+
+        if let Some(hit) = raycast_segment(edge.0 + offset, edge.1 + offset, origin, direction) {
+            // Only count hits on the outer side of the edge.
+            if hit.normal.dot(outward(edge)) > 0.0 {
+                hits.push(hit);
+            }
+        }
+    }
+
+    if radius > 0.0 {
+        for &vertex in hull {
+            hits.extend(raycast_circle(vertex, radius, origin, direction));
+        }
+    }
+
+    hits.into_iter()
+        .min_by(|a, b| a.fraction.total_cmp(&b.fraction))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Vec2, b: Vec2) {
+        assert!((a - b).length() < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    fn square() -> Shape {
+        Shape::Rectangle(Rectangle::new(0.0, 0.0, 2.0, 2.0))
+    }
+
+    fn assert_contact(contact: Option<Contact>, normal: Vec2, depth: f32) {
+        let contact = contact.expect("the shapes should overlap");
+
+        assert_close(contact.normal, normal);
+        assert!(
+            (contact.depth - depth).abs() < 1e-4,
+            "{} != {}",
+            contact.depth,
+            depth
+        );
+    }
+
+    fn circle(x: f32, y: f32, radius: f32) -> Shape {
+        Shape::Circle(Circle::new(Vec2::new(x, y), radius))
+    }
+
+    #[test]
+    fn rectangles_push_apart_along_the_shallow_axis() {
+        let other = Shape::Rectangle(Rectangle::new(1.5, 0.5, 2.0, 1.0));
+
+        assert_contact(square().contact(&other), Vec2::new(1.0, 0.0), 0.5);
+        assert_contact(other.contact(&square()), Vec2::new(-1.0, 0.0), 0.5);
+    }
+
+    #[test]
+    fn touching_shapes_dont_overlap() {
+        let right = Shape::Rectangle(Rectangle::new(2.0, 0.0, 1.0, 1.0));
+
+        assert!(square().contact(&right).is_none());
+        assert!(square().contact(&circle(3.0, 1.0, 1.0)).is_none());
+        assert!(circle(0.0, 0.0, 1.0)
+            .contact(&circle(2.0, 0.0, 1.0))
+            .is_none());
+        assert!(!square().contains_point(Vec2::new(2.0, 1.0)));
+        assert!(square().contains_point(Vec2::new(1.9, 1.0)));
+    }
+
+    #[test]
+    fn circles() {
+        let contact = circle(0.0, 0.0, 1.0).contact(&circle(1.5, 0.0, 1.0));
+        assert_contact(contact, Vec2::new(1.0, 0.0), 0.5);
+
+        // Circles at the same spot still get a valid normal.
+        let contact = circle(1.0, 1.0, 1.0).contact(&circle(1.0, 1.0, 0.5));
+        assert_contact(contact, Vec2::new(1.0, 0.0), 1.5);
+    }
+
+    #[test]
+    fn circle_first_pairs_flip_the_normal() {
+        let left = circle(-0.5, 1.0, 1.0);
+
+        assert_contact(left.contact(&square()), Vec2::new(1.0, 0.0), 0.5);
+        assert_contact(square().contact(&left), Vec2::new(-1.0, 0.0), 0.5);
+    }
+
+    #[test]
+    fn circle_against_a_corner() {
+        let corner = circle(2.5, 2.5, 1.0);
+        let diagonal = Vec2::new(1.0, 1.0).normalize();
+
+        assert_contact(square().contact(&corner), diagonal, 1.0 - 0.5f32.sqrt());
+        assert_contact(corner.contact(&square()), -diagonal, 1.0 - 0.5f32.sqrt());
+    }
+
+    #[test]
+    fn obb_corner_into_a_rectangle() {
+        let diamond = Shape::Obb(Obb::new(Vec2::ZERO, Vec2::ONE, std::f32::consts::FRAC_PI_4));
+        let wall = Shape::Rectangle(Rectangle::new(1.0, -1.0, 2.0, 2.0));
+
+        assert_contact(
+            diamond.contact(&wall),
+            Vec2::new(1.0, 0.0),
+            2f32.sqrt() - 1.0,
+        );
+    }
+
+    #[test]
+    fn polygons_of_either_winding() {
+        let points = vec![
+            Vec2::new(0.0, -1.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(0.0, 1.0),
+            Vec2::new(-1.0, 0.0),
+        ];
+        let wall = Shape::Rectangle(Rectangle::new(0.5, -2.0, 2.0, 4.0));
+
+        let counter_clockwise = Shape::Polygon(Polygon::new(points.clone()));
+        let clockwise = Shape::Polygon(Polygon::new(points.into_iter().rev().collect()));
+
+        assert_contact(counter_clockwise.contact(&wall), Vec2::new(1.0, 0.0), 0.5);
+        assert_contact(clockwise.contact(&wall), Vec2::new(1.0, 0.0), 0.5);
+        assert_contact(wall.contact(&clockwise), Vec2::new(-1.0, 0.0), 0.5);
+    }
+
+    #[test]
+    fn segment_through_a_rectangle() {
+        let segment = Shape::Segment(Segment::new(Vec2::new(-1.0, 1.0), Vec2::new(3.0, 1.0)));
+        let contact = segment.contact(&square()).unwrap();
+
+        assert!((contact.depth - 1.0).abs() < 1e-4);
+        assert!((contact.normal.y.abs() - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn raycast_hits_the_nearest_face() {
+        let hit = square()
+            .raycast(Vec2::new(-3.0, 1.0), Vec2::new(1.0, 0.0), 10.0)
+            .unwrap();
+
+        assert!((hit.fraction - 3.0).abs() < 1e-4);
+        assert_close(hit.point, Vec2::new(0.0, 1.0));
+        assert_close(hit.normal, Vec2::new(-1.0, 0.0));
+    }
+
+    #[test]
+    fn raycast_stops_at_max_fraction() {
+        let ray = (Vec2::new(-3.0, 1.0), Vec2::new(1.0, 0.0));
+
+        assert!(square().raycast(ray.0, ray.1, 2.0).is_none());
+        assert!(square().raycast(ray.0, -ray.1, 10.0).is_none());
+    }
+
+    #[test]
+    fn raycast_circle_and_first_hit() {
+        let circle = Shape::Circle(Circle::new(Vec2::new(5.0, 0.0), 1.0));
+        let hit = circle
+            .raycast(Vec2::ZERO, Vec2::new(1.0, 0.0), 10.0)
+            .unwrap();
+
+        assert!((hit.fraction - 4.0).abs() < 1e-4);
+        assert_close(hit.normal, Vec2::new(-1.0, 0.0));
+
+        let shapes = [
+            circle,
+            Shape::Rectangle(Rectangle::new(2.0, -1.0, 1.0, 2.0)),
+        ];
+        let (index, hit) = raycast_first(&shapes, Vec2::ZERO, Vec2::new(1.0, 0.0), 10.0).unwrap();
+
+        assert_eq!(index, 1);
+        assert!((hit.fraction - 2.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn cast_moves_shapes_until_they_touch() {
+        let circle = Shape::Circle(Circle::new(Vec2::new(-3.0, 1.0), 1.0));
+        let hit = circle.cast(Vec2::new(4.0, 0.0), &square()).unwrap();
+
+        assert!((hit.fraction - 0.5).abs() < 1e-4);
+        assert_close(hit.normal, Vec2::new(-1.0, 0.0));
+        assert_close(hit.point, Vec2::new(0.0, 1.0));
+
+        let small = Shape::Rectangle(Rectangle::new(0.5, 4.0, 1.0, 1.0));
+        let hit = small.cast(Vec2::new(0.0, -4.0), &square()).unwrap();
+
+        assert!((hit.fraction - 0.5).abs() < 1e-4);
+        assert_close(hit.normal, Vec2::new(0.0, 1.0));
+    }
+
+    #[test]
+    fn cast_misses_when_motion_falls_short() {
+        let circle = Shape::Circle(Circle::new(Vec2::new(-3.0, 1.0), 1.0));
+
+        assert!(circle.cast(Vec2::new(1.0, 0.0), &square()).is_none());
+        assert!(circle.cast(Vec2::new(0.0, 4.0), &square()).is_none());
+    }
+}
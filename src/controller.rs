@@ -0,0 +1,648 @@
+use crate::utils::{Rectangle, Vec2};
+
+/// Something a `CharacterController` can stand on or bump into.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Solid {
+    Block(Rectangle),
+    /// Only collides when landing on it from above.
+    OneWay(Rectangle),
+    /// Walkable from above only. The surface goes from `left` to `right`, both heights relative to
+    /// the bottom of the rectangle.
+    Slope {
+        bounds: Rectangle,
+        left: f32,
+        right: f32,
+    },
+}
+
+impl Solid {
+    pub fn bounds(&self) -> Rectangle {
+        match *self {
+            Solid::Block(bounds) | Solid::OneWay(bounds) | Solid::Slope { bounds, .. } => bounds,
+        }
+    }
+
+    /// Height of the slope's surface at `x`, or `None` if `x` is outside the slope.
+    fn surface_at(&self, x: f32) -> Option<f32> {
+        match *self {
+            Solid::Slope {
+                bounds,
+                left,
+                right,
+            } if x >= bounds.x && x <= bounds.x + bounds.w => {
+                let t = (x - bounds.x) / bounds.w;
+                Some(bounds.y + left + (right - left) * t)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A collection of solids a `CharacterController` moves through.
+pub trait Colliders {
+    /// Push all solids overlapping or touching `region` into `solids`.
+    fn solids_in(&self, region: &Rectangle, solids: &mut Vec<Solid>);
+}
+
+fn touches(a: &Rectangle, b: &Rectangle) -> bool {
+    a.x + a.w >= b.x && a.x <= b.x + b.w && a.y + a.h >= b.y && a.y <= b.y + b.h
+}
+
+impl Colliders for [Solid] {
+    fn solids_in(&self, region: &Rectangle, solids: &mut Vec<Solid>) {
+        solids.extend(
+            self.iter()
+                .filter(|solid| touches(&solid.bounds(), region))
+                .copied(),
+        );
+    }
+}
+
+impl Colliders for [Rectangle] {
+    fn solids_in(&self, region: &Rectangle, solids: &mut Vec<Solid>) {
+        solids.extend(
+            self.iter()
+                .filter(|rect| touches(rect, region))
+                .map(|&rect| Solid::Block(rect)),
+        );
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Tile {
+    Empty,
+    Solid,
+    OneWay,
+    /// Rises from the bottom left corner to the top right one.
+    SlopeUp,
+    /// Falls from the top left corner to the bottom right one.
+    SlopeDown,
+}
+
+/// A grid of square tiles, with the bottom left corner of the bottom left tile at `origin`.
+pub struct TileMap {
+    pub origin: Vec2,
+    pub tile_size: f32,
+    columns: usize,
+    rows: usize,
+    tiles: Vec<Tile>,
+}
+
+impl TileMap {
+    pub fn new(columns: usize, rows: usize, tile_size: f32) -> Self {
+        Self {
+            origin: Vec2::ZERO,
+            tile_size,
+            columns,
+            rows,
+            tiles: vec![Tile::Empty; columns * rows],
+        }
+    }
+
+    /// Build a map from text, the first line being the top row: `#` is solid, `-` one-way, `/`
+    /// and `\` slopes, anything else empty.
+    pub fn from_rows(rows: &[&str], tile_size: f32) -> Self {
+        let columns = rows
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut map = Self::new(columns, rows.len(), tile_size);
+
+        for (i, row) in rows.iter().enumerate() {
+            for (column, character) in row.chars().enumerate() {
+                let tile = match character {
+                    '#' => Tile::Solid,
+                    '-' => Tile::OneWay,
+                    '/' => Tile::SlopeUp,
+                    '\\' => Tile::SlopeDown,
+                    _ => Tile::Empty,
+                };
+
+                map.set(column, rows.len() - 1 - i, tile);
+            }
+        }
+
+        map
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Rows are counted from the bottom.
+    pub fn get(&self, column: usize, row: usize) -> Tile {
+        if column < self.columns && row < self.rows {
+            self.tiles[row * self.columns + column]
+        } else {
+            Tile::Empty
+        }
+    }
+
+    pub fn set(&mut self, column: usize, row: usize, tile: Tile) {
+        if column < self.columns && row < self.rows {
+            self.tiles[row * self.columns + column] = tile;
+        }
+    }
+
+    pub fn tile_bounds(&self, column: usize, row: usize) -> Rectangle {
+        Rectangle::new(
+            self.origin.x + column as f32 * self.tile_size,
+            self.origin.y + row as f32 * self.tile_size,
+            self.tile_size,
+            self.tile_size,
+        )
+    }
+}
+
+impl Colliders for TileMap {
+    fn solids_in(&self, region: &Rectangle, solids: &mut Vec<Solid>) {
+        let to_tile = |value: f32, origin: f32| ((value - origin) / self.tile_size).floor();
+
+        let first_column = to_tile(region.x, self.origin.x).max(0.0) as usize;
+        let first_row = to_tile(region.y, self.origin.y).max(0.0) as usize;
+        let last_column = to_tile(region.x + region.w, self.origin.x);
+        let last_row = to_tile(region.y + region.h, self.origin.y);
+
+        if last_column < 0.0 || last_row < 0.0 {
+            return;
+        }
+
+        let last_column = (last_column as usize).min(self.columns.saturating_sub(1));
+        let last_row = (last_row as usize).min(self.rows.saturating_sub(1));
+
+        for row in first_row..=last_row {
+            for column in first_column..=last_column {
+                let bounds = self.tile_bounds(column, row);
+                let size = self.tile_size;
+
+                let solid = match self.get(column, row) {
+                    Tile::Empty => continue,
+                    Tile::Solid => Solid::Block(bounds),
+                    Tile::OneWay => Solid::OneWay(bounds),
+                    Tile::SlopeUp => Solid::Slope {
+                        bounds,
+                        left: 0.0,
+                        right: size,
+                    },
+                    Tile::SlopeDown => Solid::Slope {
+                        bounds,
+                        left: size,
+                        right: 0.0,
+                    },
+                };
+
+                solids.push(solid);
+            }
+        }
+    }
+}
+
+/// Moves an axis-aligned box through a world of solids the way platformer characters move.
+///
+/// Set the horizontal velocity from input every update, call `jump` when the jump button is
+/// pressed, and then `update` with `Context::delta`.
+pub struct CharacterController {
+    pub bounds: Rectangle,
+    pub velocity: Vec2,
+    pub gravity: f32,
+    pub max_fall_speed: f32,
+    pub jump_speed: f32,
+    /// For how long after walking off a ledge jumping is still allowed, in seconds.
+    pub coyote_time: f32,
+    /// For how long a jump pressed before landing is remembered, in seconds.
+    pub jump_buffer_time: f32,
+    /// Ledges up to this high are walked onto instead of blocking the character. Also limits how
+    /// far the character snaps down onto the ground below it, or sinks into slopes.
+    pub step_height: f32,
+    grounded: bool,
+    coyote_timer: f32,
+    jump_buffer_timer: f32,
+    dropping: bool,
+    solids: Vec<Solid>,
+}
+
+impl CharacterController {
+    pub fn new(bounds: Rectangle) -> Self {
+        Self {
+            bounds,
+            velocity: Vec2::ZERO,
+            gravity: 30.0,
+            max_fall_speed: 15.0,
+            jump_speed: 12.0,
+            coyote_time: 0.1,
+            jump_buffer_time: 0.1,
+            step_height: bounds.h / 2.0,
+            grounded: false,
+            coyote_timer: 0.0,
+            jump_buffer_timer: 0.0,
+            dropping: false,
+            solids: Vec::new(),
+        }
+    }
+
+    pub fn is_grounded(&self) -> bool {
+        self.grounded
+    }
+
+    pub fn position(&self) -> Vec2 {
+        Vec2::new(self.bounds.x, self.bounds.y)
+    }
+
+    /// Jump as soon as possible: right away if standing on the ground (or having just left it),
+    /// or upon landing if that happens within `jump_buffer_time`.
+    pub fn jump(&mut self) {
+        self.jump_buffer_timer = self.jump_buffer_time;
+    }
+
+    /// Cut the jump short for lower jumps when the button is released early.
+    pub fn release_jump(&mut self) {
+        if self.velocity.y > 0.0 {
+            self.velocity.y /= 2.0;
+        }
+    }
+
+    /// Fall through the one-way platform the character is standing on.
+    pub fn drop_through(&mut self) {
+        self.dropping = true;
+    }
+
+    pub fn update<C: Colliders + ?Sized>(&mut self, delta: f32, colliders: &C) {
+        let was_grounded = self.grounded;
+        let jumping = self.jump_buffer_timer > 0.0 && (self.grounded || self.coyote_timer > 0.0);
+
+        if jumping {
+            self.velocity.y = self.jump_speed;
+            self.jump_buffer_timer = 0.0;
+            self.coyote_timer = 0.0;
+        } else {
+            // Counted down after checking, so that timers shorter than `delta` still work.
+            self.coyote_timer = if self.grounded {
+                self.coyote_time
+            } else {
+                self.coyote_timer - delta
+            };
+
+            self.jump_buffer_timer -= delta;
+        }
+
+        self.velocity.y = (self.velocity.y - self.gravity * delta).max(-self.max_fall_speed);
+        self.grounded = false;
+
+        let motion = self.velocity * delta;
+
+        // Gather everything the character could touch during this update.
+        let region = Rectangle::new(
+            self.bounds.x + motion.x.min(0.0) - self.bounds.w,
+            self.bounds.y + motion.y.min(0.0) - self.bounds.h,
+            self.bounds.w * 3.0 + motion.x.abs(),
+            self.bounds.h * 3.0 + motion.y.abs(),
+        );
+
+        let mut solids = std::mem::take(&mut self.solids);
+        solids.clear();
+        colliders.solids_in(&region, &mut solids);
+
+        self.move_horizontally(motion.x, &solids, was_grounded && !jumping);
+        self.move_vertically(motion.y, &solids);
+
+        let snap = was_grounded && !jumping && self.velocity.y <= 0.0;
+        self.land_on_ground(&solids, if snap { self.step_height } else { 0.0 });
+
+        self.dropping = false;
+        self.solids = solids;
+    }
+
+    /// The first block hit when moving `bounds` by `motion`.
+    fn first_hit(
+        bounds: &Rectangle,
+        motion: Vec2,
+        blocks: impl Iterator<Item = Rectangle>,
+    ) -> Option<Rectangle> {
+        blocks
+            .filter_map(|block| {
+                let hit = bounds.sweep(motion, &block)?;
+                Some((hit.fraction, block))
+            })
+            .min_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, block)| block)
+    }
+
+    fn blocks(solids: &[Solid]) -> impl Iterator<Item = Rectangle> + '_ {
+        solids.iter().filter_map(|solid| match solid {
+            Solid::Block(block) => Some(*block),
+            _ => None,
+        })
+    }
+
+    fn move_horizontally(&mut self, dx: f32, solids: &[Solid], can_step: bool) {
+        if dx == 0.0 {
+            return;
+        }
+
+        let motion = Vec2::new(dx, 0.0);
+
+        let block = match Self::first_hit(&self.bounds, motion, Self::blocks(solids)) {
+            Some(block) => block,
+            None => {
+                self.bounds.x += dx;
+                return;
+            }
+        };
+
+        // Step onto low ledges, e.g. at the top of slopes, if there's room.
+        let rise = block.y + block.h - self.bounds.y;
+
+        if can_step && rise > 0.0 && rise <= self.step_height {
+            let raised = Rectangle::new(
+                self.bounds.x,
+                block.y + block.h,
+                self.bounds.w,
+                self.bounds.h,
+            );
+
+            let blocked = Self::blocks(solids).any(|block| raised.collides_with(&block))
+                || Self::first_hit(&raised, motion, Self::blocks(solids)).is_some();
+
+            if !blocked {
+                self.bounds.x += dx;
+                self.bounds.y = raised.y;
+                return;
+            }
+        }
+
+        // Snap right next to the wall to slide along it without gaps or overlaps.
+        self.bounds.x = if dx > 0.0 {
+            block.x - self.bounds.w
+        } else {
+            block.x + block.w
+        };
+
+        self.velocity.x = 0.0;
+    }
+
+    fn move_vertically(&mut self, dy: f32, solids: &[Solid]) {
+        if dy == 0.0 {
+            return;
+        }
+
+        let bottom = self.bounds.y;
+        let dropping = self.dropping;
+
+        let blocks = solids.iter().filter_map(|solid| match *solid {
+            Solid::Block(block) => Some(block),
+            Solid::OneWay(platform)
+                if dy < 0.0 && !dropping && bottom >= platform.y + platform.h =>
+            {
+                Some(platform)
+            }
+            _ => None,
+        });
+
+        match Self::first_hit(&self.bounds, Vec2::new(0.0, dy), blocks) {
+            Some(block) => {
+                if dy < 0.0 {
+                    self.bounds.y = block.y + block.h;
+                    self.grounded = true;
+                } else {
+                    self.bounds.y = block.y - self.bounds.h;
+                }
+
+                self.velocity.y = 0.0;
+            }
+            None => self.bounds.y += dy,
+        }
+    }
+
+    /// Put the character on top of slopes it sank into, or on the ground at most `snap_distance`
+    /// below it, so that walking down slopes doesn't turn into a series of small falls.
+    fn land_on_ground(&mut self, solids: &[Solid], snap_distance: f32) {
+        if self.velocity.y > 0.0 {
+            return;
+        }
+
+        let left = self.bounds.x;
+        let right = left + self.bounds.w;
+        let center = left + self.bounds.w / 2.0;
+        let bottom = self.bounds.y;
+        let step_height = self.step_height;
+
+        let surface = solids
+            .iter()
+            .filter_map(|solid| match *solid {
+                Solid::Slope { .. } => solid
+                    .surface_at(center)
+                    .filter(|&surface| bottom >= surface - step_height),
+                // Flat ground is never sunk into, only snapped down onto.
+                Solid::Block(block) | Solid::OneWay(block)
+                    if block.x < right && block.x + block.w > left =>
+                {
+                    Some(block.y + block.h).filter(|&top| top <= bottom)
+                }
+                _ => None,
+            })
+            .filter(|&surface| bottom <= surface + snap_distance)
+            .fold(None, |highest: Option<f32>, surface| {
+                Some(highest.map_or(surface, |highest| highest.max(surface)))
+            });
+
+        if let Some(surface) = surface {
+            if self.grounded && surface < bottom {
+                return;
+            }
+
+            self.bounds.y = surface;
+            self.velocity.y = 0.0;
+            self.grounded = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DELTA: f32 = 1.0 / 60.0;
+
+    /// A character standing on the ground of `map`, which should be at y = 1, at `x`.
+    fn standing(map: &TileMap, x: f32) -> CharacterController {
+        let mut controller = CharacterController::new(Rectangle::new(x, 1.0, 0.8, 0.9));
+        controller.update(DELTA, map);
+        assert!(controller.is_grounded());
+        controller
+    }
+
+    fn run(controller: &mut CharacterController, map: &TileMap, seconds: f32) {
+        for _ in 0..(seconds / DELTA).round() as usize {
+            controller.update(DELTA, map);
+        }
+    }
+
+    #[test]
+    fn falls_onto_the_ground() {
+        let map = TileMap::from_rows(&["#####"], 1.0);
+        let mut controller = CharacterController::new(Rectangle::new(1.0, 3.0, 0.8, 0.9));
+
+        run(&mut controller, &map, 1.0);
+
+        assert!(controller.is_grounded());
+        assert_eq!(controller.position(), Vec2::new(1.0, 1.0));
+        assert_eq!(controller.velocity.y, 0.0);
+    }
+
+    #[test]
+    fn jumps_from_the_ground_without_coyote_time() {
+        let map = TileMap::from_rows(&["#####"], 1.0);
+        let mut controller = standing(&map, 1.0);
+        controller.coyote_time = 0.0;
+
+        controller.jump();
+        controller.update(DELTA, &map);
+
+        assert!(!controller.is_grounded());
+        assert!(controller.velocity.y > 0.0);
+    }
+
+    #[test]
+    fn jumps_with_a_buffer_shorter_than_an_update() {
+        let map = TileMap::from_rows(&["#####"], 1.0);
+        let mut controller = standing(&map, 1.0);
+
+        // 10 updates per second, as long as the default jump buffer.
+        controller.jump();
+        controller.update(0.1, &map);
+
+        assert!(controller.velocity.y > 0.0);
+    }
+
+    #[test]
+    fn buffered_jumps_happen_on_landing() {
+        let map = TileMap::from_rows(&["#####"], 1.0);
+        let mut early = CharacterController::new(Rectangle::new(1.0, 2.0, 0.8, 0.9));
+        let mut late = CharacterController::new(Rectangle::new(1.0, 1.1, 0.8, 0.9));
+
+        // Too long before landing, and just before.
+        early.jump();
+        late.jump();
+
+        let mut jumped = (false, false);
+
+        for _ in 0..30 {
+            early.update(DELTA, &map);
+            late.update(DELTA, &map);
+            jumped.0 |= early.velocity.y > 0.0;
+            jumped.1 |= late.velocity.y > 0.0;
+        }
+
+        assert_eq!(jumped, (false, true));
+        assert!(early.is_grounded());
+    }
+
+    #[test]
+    fn coyote_time_after_walking_off_a_ledge() {
+        let map = TileMap::from_rows(&["##    "], 1.0);
+        let mut quick = standing(&map, 1.0);
+        let mut slow = standing(&map, 1.0);
+
+        while quick.is_grounded() {
+            quick.velocity.x = 3.0;
+            quick.update(DELTA, &map);
+            slow.velocity.x = 3.0;
+            slow.update(DELTA, &map);
+        }
+
+        quick.jump();
+        quick.update(DELTA, &map);
+        assert!(quick.velocity.y > 0.0);
+
+        run(&mut slow, &map, 0.2);
+        slow.jump();
+        slow.update(DELTA, &map);
+        assert!(slow.velocity.y < 0.0);
+    }
+
+    #[test]
+    fn walls_stop_the_character() {
+        let map = TileMap::from_rows(&["   #", "####"], 1.0);
+        let mut controller = standing(&map, 1.0);
+
+        for _ in 0..60 {
+            controller.velocity.x = 5.0;
+            controller.update(DELTA, &map);
+        }
+
+        assert_eq!(controller.bounds.x + controller.bounds.w, 3.0);
+        assert_eq!(controller.velocity.x, 0.0);
+        assert!(controller.is_grounded());
+    }
+
+    #[test]
+    fn one_way_platforms() {
+        let map = TileMap::from_rows(&["  -  ", "     ", "#####"], 1.0);
+        let mut controller = standing(&map, 2.1);
+
+        // Jumps through the platform from below, then lands on it.
+        controller.jump();
+        run(&mut controller, &map, 1.5);
+
+        assert!(controller.is_grounded());
+        assert_eq!(controller.position().y, 3.0);
+
+        controller.drop_through();
+        run(&mut controller, &map, 1.5);
+
+        assert!(controller.is_grounded());
+        assert_eq!(controller.position().y, 1.0);
+    }
+
+    #[test]
+    fn walks_up_and_down_slopes() {
+        let map = TileMap::from_rows(&["   /#", "#####"], 1.0);
+        let mut controller = standing(&map, 1.0);
+
+        for _ in 0..60 {
+            controller.velocity.x = 3.0;
+            controller.update(DELTA, &map);
+            assert!(controller.is_grounded());
+        }
+
+        assert_eq!(controller.position().y, 2.0);
+
+        for _ in 0..60 {
+            controller.velocity.x = -3.0;
+            controller.update(DELTA, &map);
+            assert!(controller.is_grounded());
+        }
+
+        assert_eq!(controller.position().y, 1.0);
+    }
+
+    #[test]
+    fn steps_onto_low_ledges_only() {
+        let ground = Rectangle::new(-10.0, 0.0, 20.0, 1.0);
+        let low = [
+            Solid::Block(ground),
+            Solid::Block(Rectangle::new(2.0, 1.0, 5.0, 0.3)),
+        ];
+        let high = [
+            Solid::Block(ground),
+            Solid::Block(Rectangle::new(2.0, 1.0, 5.0, 0.6)),
+        ];
+
+        for (solids, height) in [(&low, 1.3), (&high, 1.0)] {
+            let mut controller = CharacterController::new(Rectangle::new(0.0, 1.0, 0.8, 0.9));
+
+            for _ in 0..60 {
+                controller.velocity.x = 3.0;
+                controller.update(DELTA, &solids[..]);
+            }
+
+            assert!(controller.is_grounded());
+            assert_eq!(controller.position().y, height);
+        }
+    }
+}
@@ -0,0 +1,608 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+use crate::collision::{Contact, Shape};
+use crate::game::Context;
+use crate::render::Canvas;
+use crate::spatial::SpatialHash;
+use crate::utils::{Color, Vec2};
+
+pub use crate::node::Transform;
+
+/// Identifies an entity of a `World`. Handles of despawned entities stay invalid even after their
+/// slot gets reused.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+trait AnyStorage {
+    fn remove_index(&mut self, index: usize);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+struct Storage<T> {
+    components: Vec<Option<T>>,
+}
+
+impl<T: 'static> AnyStorage for Storage<T> {
+    fn remove_index(&mut self, index: usize) {
+        if let Some(component) = self.components.get_mut(index) {
+            *component = None;
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+/// Entities and their components. Components can be of any `'static` type; each entity has at
+/// most one component of every type.
+pub struct World {
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    free: Vec<u32>,
+    storages: HashMap<TypeId, Box<dyn AnyStorage>>,
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self {
+            generations: Vec::new(),
+            alive: Vec::new(),
+            free: Vec::new(),
+            storages: HashMap::new(),
+        }
+    }
+
+    /// Create an entity, adding its components with `EntityBuilder::with`.
+    pub fn spawn(&mut self) -> EntityBuilder<'_> {
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => {
+                self.generations.push(0);
+                self.alive.push(false);
+                self.generations.len() as u32 - 1
+            }
+        };
+
+        self.alive[index as usize] = true;
+
+        let entity = Entity {
+            index,
+            generation: self.generations[index as usize],
+        };
+
+        EntityBuilder {
+            world: self,
+            entity,
+        }
+    }
+
+    /// Remove the entity along with all of its components. Returns false if it was already gone.
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+
+        let index = entity.index as usize;
+
+        for storage in self.storages.values_mut() {
+            storage.remove_index(index);
+        }
+
+        self.alive[index] = false;
+        self.generations[index] += 1;
+        self.free.push(entity.index);
+
+        true
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        let index = entity.index as usize;
+        self.alive.get(index) == Some(&true) && self.generations[index] == entity.generation
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.alive
+            .iter()
+            .enumerate()
+            .filter(|(_, &alive)| alive)
+            .map(move |(index, _)| self.entity_at(index))
+    }
+
+    /// Add a component to the entity, replacing and returning the one of the same type it had.
+    ///
+    /// Panics if the entity was despawned.
+    pub fn insert<T: 'static>(&mut self, entity: Entity, component: T) -> Option<T> {
+        assert!(self.is_alive(entity), "{:?} was despawned", entity);
+
+        let components = &mut self.storage_mut::<T>().components;
+        let index = entity.index as usize;
+
+        if components.len() <= index {
+            components.resize_with(index + 1, || None);
+        }
+
+        components[index].replace(component)
+    }
+
+    pub fn remove<T: 'static>(&mut self, entity: Entity) -> Option<T> {
+        if !self.is_alive(entity) {
+            return None;
+        }
+
+        self.storage_mut::<T>()
+            .components
+            .get_mut(entity.index as usize)?
+            .take()
+    }
+
+    pub fn get<T: 'static>(&self, entity: Entity) -> Option<&T> {
+        if !self.is_alive(entity) {
+            return None;
+        }
+
+        self.storage::<T>()?
+            .components
+            .get(entity.index as usize)?
+            .as_ref()
+    }
+
+    pub fn get_mut<T: 'static>(&mut self, entity: Entity) -> Option<&mut T> {
+        if !self.is_alive(entity) {
+            return None;
+        }
+
+        self.storage_mut::<T>()
+            .components
+            .get_mut(entity.index as usize)?
+            .as_mut()
+    }
+
+    pub fn has<T: 'static>(&self, entity: Entity) -> bool {
+        self.get::<T>(entity).is_some()
+    }
+
+    /// Every entity having a component of type `T`, along with it.
+    pub fn iter<T: 'static>(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.storage::<T>()
+            .into_iter()
+            .flat_map(|storage| storage.components.iter().enumerate())
+            .filter_map(move |(index, component)| {
+                Some((self.entity_at(index), component.as_ref()?))
+            })
+    }
+
+    /// Entities having components of both types.
+    pub fn query<A: 'static, B: 'static>(&self) -> Vec<Entity> {
+        self.iter::<A>()
+            .map(|(entity, _)| entity)
+            .filter(|&entity| self.has::<B>(entity))
+            .collect()
+    }
+
+    /// Call `f` for every entity having a component of type `A`.
+    pub fn each<A: 'static, F>(&mut self, mut f: F)
+    where
+        F: FnMut(Entity, &mut A),
+    {
+        let generations = &self.generations;
+
+        if let Some(storage) = self.storages.get_mut(&TypeId::of::<A>()) {
+            let storage = storage.as_any_mut().downcast_mut::<Storage<A>>().unwrap();
+
+            for (index, component) in storage.components.iter_mut().enumerate() {
+                if let Some(a) = component {
+                    f(entity_at(generations, index), a);
+                }
+            }
+        }
+    }
+
+    /// Call `f` for every entity having components of both types, which must differ.
+    pub fn each2<A: 'static, B: 'static, F>(&mut self, mut f: F)
+    where
+        F: FnMut(Entity, &mut A, &mut B),
+    {
+        assert_ne!(
+            TypeId::of::<A>(),
+            TypeId::of::<B>(),
+            "Component types must differ"
+        );
+
+        let mut storage_b = self.take_storage::<B>();
+
+        self.each::<A, _>(|entity, a| {
+            if let Some(Some(b)) = storage_b.components.get_mut(entity.index as usize) {
+                f(entity, a, b);
+            }
+        });
+
+        self.put_storage(storage_b);
+    }
+
+    /// Call `f` for every entity having components of all three types, which must differ.
+    pub fn each3<A: 'static, B: 'static, C: 'static, F>(&mut self, mut f: F)
+    where
+        F: FnMut(Entity, &mut A, &mut B, &mut C),
+    {
+        assert_ne!(
+            TypeId::of::<A>(),
+            TypeId::of::<C>(),
+            "Component types must differ"
+        );
+        assert_ne!(
+            TypeId::of::<B>(),
+            TypeId::of::<C>(),
+            "Component types must differ"
+        );
+
+        let mut storage_c = self.take_storage::<C>();
+
+        self.each2::<A, B, _>(|entity, a, b| {
+            if let Some(Some(c)) = storage_c.components.get_mut(entity.index as usize) {
+                f(entity, a, b, c);
+            }
+        });
+
+        self.put_storage(storage_c);
+    }
+
+    fn entity_at(&self, index: usize) -> Entity {
+        entity_at(&self.generations, index)
+    }
+
+    fn storage<T: 'static>(&self) -> Option<&Storage<T>> {
+        let storage = self.storages.get(&TypeId::of::<T>())?;
+        storage.as_any().downcast_ref()
+    }
+
+    fn storage_mut<T: 'static>(&mut self) -> &mut Storage<T> {
+        self.storages
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| {
+                Box::new(Storage::<T> {
+                    components: Vec::new(),
+                })
+            })
+            .as_any_mut()
+            .downcast_mut()
+            .unwrap()
+    }
+
+    /// Temporarily take a storage out, so that it can be borrowed along with another one.
+    fn take_storage<T: 'static>(&mut self) -> Storage<T> {
+        self.storage_mut::<T>();
+
+        let storage = self.storages.remove(&TypeId::of::<T>()).unwrap();
+        *storage.into_any().downcast().unwrap()
+    }
+
+    fn put_storage<T: 'static>(&mut self, storage: Storage<T>) {
+        self.storages.insert(TypeId::of::<T>(), Box::new(storage));
+    }
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn entity_at(generations: &[u32], index: usize) -> Entity {
+    Entity {
+        index: index as u32,
+        generation: generations[index],
+    }
+}
+
+pub struct EntityBuilder<'a> {
+    world: &'a mut World,
+    entity: Entity,
+}
+
+impl<'a> EntityBuilder<'a> {
+    pub fn with<T: 'static>(self, component: T) -> Self {
+        self.world.insert(self.entity, component);
+        self
+    }
+
+    pub fn build(self) -> Entity {
+        self.entity
+    }
+}
+
+/// Game logic operating on the components of a `World`. Both functions do nothing by default.
+pub trait System {
+    fn update(&mut self, _world: &mut World, _context: &mut Context) {}
+    fn render(&mut self, _world: &mut World, _canvas: &mut Canvas, _context: &mut Context) {}
+}
+
+/// Update-only systems can be plain functions or closures.
+impl<F: FnMut(&mut World, &mut Context)> System for F {
+    fn update(&mut self, world: &mut World, context: &mut Context) {
+        self(world, context);
+    }
+}
+
+/// Ordered list of labeled systems. Call `update` from `Game::update` and `render` from
+/// `Game::render`.
+pub struct Schedule {
+    systems: Vec<(String, Box<dyn System>)>,
+}
+
+impl Schedule {
+    pub fn new() -> Self {
+        Self {
+            systems: Vec::new(),
+        }
+    }
+
+    /// Run the system after all the others.
+    pub fn add<S: System + 'static>(&mut self, label: &str, system: S) -> &mut Self {
+        self.systems.push((label.to_string(), Box::new(system)));
+        self
+    }
+
+    /// Run the system right before the one labeled `before`. Panics if there's no such system.
+    pub fn add_before<S: System + 'static>(
+        &mut self,
+        before: &str,
+        label: &str,
+        system: S,
+    ) -> &mut Self {
+        let index = self.position(before);
+        self.systems
+            .insert(index, (label.to_string(), Box::new(system)));
+        self
+    }
+
+    /// Run the system right after the one labeled `after`. Panics if there's no such system.
+    pub fn add_after<S: System + 'static>(
+        &mut self,
+        after: &str,
+        label: &str,
+        system: S,
+    ) -> &mut Self {
+        let index = self.position(after) + 1;
+        self.systems
+            .insert(index, (label.to_string(), Box::new(system)));
+        self
+    }
+
+    pub fn remove(&mut self, label: &str) -> Option<Box<dyn System>> {
+        let index = self.systems.iter().position(|(other, _)| other == label)?;
+        Some(self.systems.remove(index).1)
+    }
+
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.systems.iter().map(|(label, _)| label.as_str())
+    }
+
+    pub fn update(&mut self, world: &mut World, context: &mut Context) {
+        for (_, system) in &mut self.systems {
+            system.update(world, context);
+        }
+    }
+
+    pub fn render(&mut self, world: &mut World, canvas: &mut Canvas, context: &mut Context) {
+        for (_, system) in &mut self.systems {
+            system.render(world, canvas, context);
+        }
+    }
+
+    fn position(&self, label: &str) -> usize {
+        self.systems
+            .iter()
+            .position(|(other, _)| other == label)
+            .unwrap_or_else(|| panic!("No system labeled {}", label))
+    }
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Drawn by `SpriteRenderer` at the entity's `Transform`.
+#[derive(Clone)]
+pub struct Sprite {
+    /// Passed to `Context::render`; `None` for an untextured sprite, as in
+    /// `Context::render_blank`.
+    pub filename: Option<String>,
+    pub size: Vec2,
+    pub shade: Color,
+    pub tint: Color,
+    /// Sprites with a higher layer are drawn on top.
+    pub layer: i32,
+    pub visible: bool,
+}
+
+impl Sprite {
+    pub fn new(filename: &str, width: f32, height: f32) -> Self {
+        Self {
+            filename: Some(filename.to_string()),
+            ..Self::blank(width, height)
+        }
+    }
+
+    pub fn blank(width: f32, height: f32) -> Self {
+        Self {
+            filename: None,
+            size: Vec2::new(width, height),
+            shade: (0.0, 0.0, 0.0),
+            tint: (1.0, 1.0, 1.0),
+            layer: 0,
+            visible: true,
+        }
+    }
+
+    pub fn with_shade(mut self, red: f32, green: f32, blue: f32) -> Self {
+        self.shade = (red, green, blue);
+        self
+    }
+
+    pub fn with_tint(mut self, red: f32, green: f32, blue: f32) -> Self {
+        self.tint = (red, green, blue);
+        self
+    }
+
+    pub fn with_layer(mut self, layer: i32) -> Self {
+        self.layer = layer;
+        self
+    }
+}
+
+/// Draws every entity having both a `Transform` and a `Sprite`.
+pub struct SpriteRenderer;
+
+impl System for SpriteRenderer {
+    fn render(&mut self, world: &mut World, canvas: &mut Canvas, context: &mut Context) {
+        let mut sprites: Vec<_> = world
+            .iter::<Sprite>()
+            .filter(|(_, sprite)| sprite.visible)
+            .filter_map(|(entity, sprite)| Some((world.get::<Transform>(entity)?, sprite)))
+            .collect();
+
+        sprites.sort_by_key(|(_, sprite)| sprite.layer);
+
+        for (transform, sprite) in sprites {
+            let builder = match &sprite.filename {
+                Some(filename) => context.render(filename),
+                None => context.render_blank(),
+            };
+
+            builder
+                .transform(transform.matrix())
+                .scale(sprite.size.x, sprite.size.y)
+                .shade_tup(sprite.shade)
+                .tint_tup(sprite.tint)
+                .commit(canvas);
+        }
+    }
+}
+
+/// Collision shape of an entity, positioned at its `Transform`. Rotation and scale are ignored.
+pub struct Collider {
+    pub shape: Shape,
+    /// Filled by `CollisionDetector` with every other collider overlapping this one, the normals
+    /// pointing away from this one.
+    pub contacts: Vec<(Entity, Contact)>,
+}
+
+impl Collider {
+    pub fn new<S: Into<Shape>>(shape: S) -> Self {
+        Self {
+            shape: shape.into(),
+            contacts: Vec::new(),
+        }
+    }
+
+    pub fn world_shape(&self, transform: &Transform) -> Shape {
+        self.shape.translated(transform.position)
+    }
+}
+
+/// Updates `Collider::contacts` of every entity having both a `Transform` and a `Collider`. Add
+/// it before the systems reacting to collisions.
+pub struct CollisionDetector {
+    /// Cell size of the broad phase; about the size of a typical collider.
+    pub cell_size: f32,
+}
+
+impl CollisionDetector {
+    pub fn new(cell_size: f32) -> Self {
+        Self { cell_size }
+    }
+}
+
+impl System for CollisionDetector {
+    fn update(&mut self, world: &mut World, _context: &mut Context) {
+        let shapes: HashMap<Entity, Shape> = world
+            .iter::<Collider>()
+            .filter_map(|(entity, collider)| {
+                Some((entity, collider.world_shape(world.get(entity)?)))
+            })
+            .collect();
+
+        let mut hash = SpatialHash::new(self.cell_size);
+
+        for (&entity, shape) in &shapes {
+            hash.insert(entity, shape.bounds());
+        }
+
+        world.each::<Collider, _>(|_, collider| collider.contacts.clear());
+
+        for (a, b) in hash.pairs() {
+            if let Some(contact) = shapes[&a].contact(&shapes[&b]) {
+                if let Some(collider) = world.get_mut::<Collider>(a) {
+                    collider.contacts.push((b, contact));
+                }
+
+                if let Some(collider) = world.get_mut::<Collider>(b) {
+                    collider.contacts.push((a, contact.flipped()));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Health(u32);
+
+    #[test]
+    fn despawned_entities_stay_invalid_after_reuse() {
+        let mut world = World::new();
+        let old = world.spawn().with(Health(1)).build();
+
+        assert!(world.despawn(old));
+        assert!(!world.despawn(old));
+
+        let new = world.spawn().with(Health(2)).build();
+
+        // The slot is reused, but the old handle doesn't alias the new entity.
+        assert_eq!(new.index, old.index);
+        assert_ne!(new, old);
+        assert!(!world.is_alive(old));
+        assert!(world.is_alive(new));
+
+        assert_eq!(world.get::<Health>(old), None);
+        assert_eq!(world.get_mut::<Health>(old), None);
+        assert_eq!(world.remove::<Health>(old), None);
+        assert!(!world.despawn(old));
+
+        assert_eq!(world.get::<Health>(new), Some(&Health(2)));
+        assert_eq!(world.entities().collect::<Vec<_>>(), vec![new]);
+    }
+
+    #[test]
+    fn despawning_removes_components() {
+        let mut world = World::new();
+        let entity = world.spawn().with(Health(1)).with(0.5f32).build();
+
+        world.despawn(entity);
+        let reused = world.spawn().build();
+
+        assert!(!world.has::<Health>(reused));
+        assert!(!world.has::<f32>(reused));
+        assert_eq!(world.iter::<Health>().count(), 0);
+    }
+}
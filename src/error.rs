@@ -0,0 +1,220 @@
+use glium::backend::glutin::DisplayCreationError;
+use glium::framebuffer::ValidationError;
+use glium::texture::TextureCreationError;
+use glium::{index, vertex, DrawError, ProgramCreationError, ReadError, SwapBuffersError};
+
+use image::ImageError;
+
+use std::error;
+use std::fmt;
+use std::io;
+
+use crate::assets::AssetError;
+
+/// Everything that can go wrong in gamelib outside of loading assets in the background, which
+/// `Assets` keeps track of instead.
+#[derive(Debug)]
+pub enum Error {
+    /// The window or its OpenGL context couldn't be created.
+    Display(DisplayCreationError),
+    Shader(ProgramCreationError),
+    Texture(TextureCreationError),
+    VertexBuffer(vertex::BufferCreationError),
+    IndexBuffer(index::BufferCreationError),
+    Framebuffer(ValidationError),
+    Draw(DrawError),
+    /// The OpenGL context was lost, e.g. after a driver reset. `run_game` recreates it, along
+    /// with the textures loaded from files.
+    ContextLost,
+    /// The frame couldn't be shown for another reason.
+    SwapBuffers(SwapBuffersError),
+    Asset(AssetError),
+    /// An image couldn't be decoded or encoded.
+    Image(ImageError),
+    /// A screenshot or a recording couldn't be saved to `path`.
+    Capture {
+        path: String,
+        error: ImageError,
+    },
+    /// The frame couldn't be read back from the window.
+    ReadFrame(ReadError),
+    /// Pixels handed to a texture aren't `width` by `height` RGBA pixels.
+    PixelBufferSize {
+        width: u32,
+        height: u32,
+        length: usize,
+    },
+    /// A region written to isn't inside the texture.
+    OutsideTexture {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    },
+    Io(io::Error),
+    /// A sound in memory couldn't be decoded.
+    Sound(String),
+    /// A font in memory couldn't be parsed.
+    Font(rusttype::Error),
+    /// A WAV file couldn't be written.
+    Wav(hound::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Display(error) => write!(f, "Couldn't create the window: {}", error),
+            Error::Shader(error) => write!(f, "Couldn't compile a shader: {}", error),
+            Error::Texture(error) => write!(f, "Couldn't create a texture: {}", error),
+            Error::VertexBuffer(error) => write!(f, "Couldn't create a vertex buffer: {}", error),
+            Error::IndexBuffer(error) => write!(f, "Couldn't create an index buffer: {}", error),
+            Error::Framebuffer(error) => write!(f, "Couldn't create a framebuffer: {}", error),
+            Error::Draw(error) => write!(f, "Couldn't draw: {}", error),
+            Error::ContextLost => write!(f, "The OpenGL context was lost"),
+            Error::SwapBuffers(error) => write!(f, "Couldn't show the frame: {}", error),
+            Error::Asset(error) => error.fmt(f),
+            Error::Image(error) => write!(f, "Couldn't process an image: {}", error),
+            Error::Capture { path, error } => write!(f, "Couldn't save {}: {}", path, error),
+            Error::ReadFrame(error) => write!(f, "Couldn't read the frame back: {}", error),
+            Error::PixelBufferSize {
+                width,
+                height,
+                length,
+            } => write!(
+                f,
+                "Expected {}x{} RGBA pixels, got {} bytes",
+                width, height, length
+            ),
+            Error::OutsideTexture {
+                x,
+                y,
+                width,
+                height,
+            } => write!(
+                f,
+                "The {}x{} region at ({}, {}) is outside the texture",
+                width, height, x, y
+            ),
+            Error::Io(error) => write!(f, "Input/output error: {}", error),
+            Error::Sound(error) => write!(f, "Couldn't decode a sound: {}", error),
+            Error::Font(error) => write!(f, "Couldn't parse a font: {}", error),
+            Error::Wav(error) => write!(f, "Couldn't write a WAV file: {}", error),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Display(error) => Some(error),
+            Error::Shader(error) => Some(error),
+            Error::Texture(error) => Some(error),
+            Error::VertexBuffer(error) => Some(error),
+            Error::IndexBuffer(error) => Some(error),
+            Error::Framebuffer(error) => Some(error),
+            Error::Draw(error) => Some(error),
+            Error::ContextLost => None,
+            Error::SwapBuffers(error) => Some(error),
+            // Displayed as the asset error itself, so skip to its source.
+            Error::Asset(error) => error.source(),
+            Error::Image(error) => Some(error),
+            Error::Capture { error, .. } => Some(error),
+            Error::ReadFrame(error) => Some(error),
+            Error::PixelBufferSize { .. } | Error::OutsideTexture { .. } => None,
+            Error::Io(error) => Some(error),
+            Error::Sound(_) => None,
+            Error::Font(error) => Some(error),
+            Error::Wav(error) => Some(error),
+        }
+    }
+}
+
+impl From<DisplayCreationError> for Error {
+    fn from(error: DisplayCreationError) -> Self {
+        Error::Display(error)
+    }
+}
+
+impl From<ProgramCreationError> for Error {
+    fn from(error: ProgramCreationError) -> Self {
+        Error::Shader(error)
+    }
+}
+
+impl From<TextureCreationError> for Error {
+    fn from(error: TextureCreationError) -> Self {
+        Error::Texture(error)
+    }
+}
+
+impl From<vertex::BufferCreationError> for Error {
+    fn from(error: vertex::BufferCreationError) -> Self {
+        Error::VertexBuffer(error)
+    }
+}
+
+impl From<index::BufferCreationError> for Error {
+    fn from(error: index::BufferCreationError) -> Self {
+        Error::IndexBuffer(error)
+    }
+}
+
+impl From<ValidationError> for Error {
+    fn from(error: ValidationError) -> Self {
+        Error::Framebuffer(error)
+    }
+}
+
+impl From<DrawError> for Error {
+    fn from(error: DrawError) -> Self {
+        Error::Draw(error)
+    }
+}
+
+impl From<SwapBuffersError> for Error {
+    fn from(error: SwapBuffersError) -> Self {
+        match error {
+            SwapBuffersError::ContextLost => Error::ContextLost,
+            error => Error::SwapBuffers(error),
+        }
+    }
+}
+
+impl From<AssetError> for Error {
+    fn from(error: AssetError) -> Self {
+        Error::Asset(error)
+    }
+}
+
+impl From<ReadError> for Error {
+    fn from(error: ReadError) -> Self {
+        Error::ReadFrame(error)
+    }
+}
+
+impl From<ImageError> for Error {
+    fn from(error: ImageError) -> Self {
+        Error::Image(error)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<rusttype::Error> for Error {
+    fn from(error: rusttype::Error) -> Self {
+        Error::Font(error)
+    }
+}
+
+impl From<hound::Error> for Error {
+    fn from(error: hound::Error) -> Self {
+        match error {
+            hound::Error::IoError(error) => Error::Io(error),
+            error => Error::Wav(error),
+        }
+    }
+}
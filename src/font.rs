@@ -0,0 +1,135 @@
+use std::panic;
+
+use image::{Rgba, RgbaImage};
+use rusttype::{point, Scale};
+
+use crate::error::Error;
+
+/// A TrueType or OpenType font, to draw text into images with. Load it with `Assets::load`, or
+/// with `from_bytes`.
+#[derive(Clone)]
+pub struct Font {
+    font: rusttype::Font<'static>,
+}
+
+impl Font {
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, Error> {
+        Ok(Self::decode(bytes)?)
+    }
+
+    /// `from_bytes`, with the error `Assets` wraps in an `AssetError`.
+    pub(crate) fn decode(bytes: Vec<u8>) -> Result<Self, rusttype::Error> {
+        // rusttype panics on some malformed fonts instead of failing, e.g. ones under 4 bytes.
+        let font = panic::catch_unwind(|| rusttype::Font::from_bytes(bytes))
+            .unwrap_or(Err(rusttype::Error::IllFormed))?;
+
+        Ok(Self { font })
+    }
+
+    /// The width and height, in pixels, of a line of text `size` pixels high.
+    pub fn measure(&self, text: &str, size: f32) -> (u32, u32) {
+        let scale = Scale::uniform(size);
+
+        let width = self
+            .font
+            .layout(text, scale, point(0.0, 0.0))
+            .last()
+            .map_or(0.0, |glyph| {
+                glyph.position().x + glyph.unpositioned().h_metrics().advance_width
+            });
+
+        (width.ceil() as u32, self.line_height(size).ceil() as u32)
+    }
+
+    /// From the top of the highest glyphs to the bottom of the lowest ones.
+    pub fn line_height(&self, size: f32) -> f32 {
+        let metrics = self.font.v_metrics(Scale::uniform(size));
+        metrics.ascent - metrics.descent
+    }
+
+    /// A line of text in `color`, on a transparent image as big as `measure` says. Turn it into
+    /// a texture with `Assets::create_texture`.
+    pub fn rasterize(&self, text: &str, size: f32, color: Rgba<u8>) -> RgbaImage {
+        let (width, height) = self.measure(text, size);
+        let mut image = RgbaImage::new(width, height);
+
+        let scale = Scale::uniform(size);
+        let ascent = self.font.v_metrics(scale).ascent;
+
+        for glyph in self.font.layout(text, scale, point(0.0, ascent)) {
+            // Spaces have nothing to draw.
+            let bounds = match glyph.pixel_bounding_box() {
+                Some(bounds) => bounds,
+                None => continue,
+            };
+
+            glyph.draw(|x, y, coverage| {
+                let x = bounds.min.x + x as i32;
+                let y = bounds.min.y + y as i32;
+
+                if x < 0 || y < 0 || x >= width as i32 || y >= height as i32 {
+                    return;
+                }
+
+                let pixel = image.get_pixel_mut(x as u32, y as u32);
+                let alpha = (coverage * color[3] as f32).round() as u8;
+
+                // Overlapping glyphs keep the most opaque coverage.
+                if alpha > pixel[3] {
+                    *pixel = Rgba([color[0], color[1], color[2], alpha]);
+                }
+            });
+        }
+
+        image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn font() -> Font {
+        let bytes = include_bytes!("../tests/fixtures/SourceSansPro-Regular-Tiny.ttf").to_vec();
+        Font::from_bytes(bytes).unwrap()
+    }
+
+    #[test]
+    fn invalid_fonts() {
+        assert!(matches!(
+            Font::from_bytes(b"not a font".to_vec()),
+            Err(Error::Font(_))
+        ));
+        assert!(matches!(Font::from_bytes(vec![0, 1]), Err(Error::Font(_))));
+    }
+
+    #[test]
+    fn measure_grows_with_the_text_and_size() {
+        let font = font();
+        let (width, height) = font.measure("AB", 20.0);
+
+        assert!(width > 0);
+        assert_eq!(height, font.line_height(20.0).ceil() as u32);
+        assert!(font.measure("ABAB", 20.0).0 > width);
+        assert_eq!(font.measure("", 20.0).0, 0);
+
+        let (double_width, double_height) = font.measure("AB", 40.0);
+        assert!((double_width as i32 - 2 * width as i32).abs() <= 2);
+        assert!((double_height as i32 - 2 * height as i32).abs() <= 2);
+    }
+
+    #[test]
+    fn rasterize_draws_inside_the_measured_size() {
+        let font = font();
+        let color = Rgba([255, 128, 0, 255]);
+        let image = font.rasterize("AB", 20.0, color);
+
+        assert_eq!(image.dimensions(), font.measure("AB", 20.0));
+        assert!(image.pixels().any(|pixel| pixel[3] == 255));
+        assert!(image.pixels().any(|pixel| pixel[3] == 0));
+        assert!(image
+            .pixels()
+            .filter(|pixel| pixel[3] > 0)
+            .all(|pixel| pixel.0[..3] == color.0[..3]));
+    }
+}
@@ -1,24 +1,65 @@
 use glium::glutin::dpi::LogicalSize;
 use glium::glutin::event_loop::ControlFlow;
-use glium::glutin::window::WindowBuilder;
-use glium::glutin::ContextBuilder;
+use glium::glutin::platform::desktop::EventLoopExtDesktop;
+use glium::glutin::window::{Window, WindowBuilder};
+use glium::glutin::{ContextBuilder, NotCurrent};
 
 use glium::glutin::event::{ElementState, Event, WindowEvent};
 
 use glium::{Display, Program};
 
+use std::cell::Ref;
 use std::collections::{HashMap, HashSet};
-use std::sync::Mutex;
+use std::future::Future;
 use std::time::Instant;
 
-use crate::render::{Canvas, Model, ModelRenderBuilder, FRAGMENT_SHADER, VERTEX_SHADER};
+use image::RgbaImage;
+
+use crate::assets::{AssetError, AssetErrorKind, Assets, Handle, LoadState};
+use crate::audio::{Audio, AudioBackend, Sound, SoundBuilder};
+use crate::capture::{self, Capture};
+use crate::error::Error;
+#[cfg(feature = "hot-reload")]
+use crate::hot_reload::ShaderWatcher;
+use crate::scheduler::{Coroutine, Scheduler, TaskId};
+use crate::vfs;
+use crate::window::{self, CursorIcon, WindowMode};
+
+use crate::render::{
+    Canvas, MeshBuilder, Model, ModelRenderBuilder, NineSliceBuilder, Texture, FRAGMENT_SHADER,
+    VERTEX_SHADER,
+};
 
 pub use glium::glutin::event::VirtualKeyCode as KeyCode;
 
 pub struct GameConfig {
     pub title: String,
     pub window_size: (u32, u32),
+    pub window_mode: WindowMode,
+    pub resizable: bool,
+    pub min_window_size: Option<(u32, u32)>,
+    /// Image file for the title bar and taskbar.
+    pub icon: Option<String>,
+    /// Wait for the monitor to refresh before showing a frame. Can't be changed once running.
+    pub vsync: bool,
+    /// Samples per pixel for antialiasing, 0 for none. Can't be changed once running.
+    pub multisampling: u16,
+    pub cursor_visible: bool,
+    /// Keep the cursor inside the window.
+    pub cursor_grab: bool,
+    /// Stop updating the game while the window isn't focused.
+    pub pause_on_focus_lost: bool,
     pub update_fps: f32,
+    /// Where sounds are played; `None` for the sound card, if any.
+    pub audio: Option<Box<dyn AudioBackend>>,
+    /// Saves a screenshot to the working directory when pressed.
+    pub screenshot_key: Option<KeyCode>,
+    /// Starts and stops recording a GIF to the working directory.
+    pub record_key: Option<KeyCode>,
+    /// Vertex and fragment shader files to draw sprites with instead of the built-in shaders.
+    /// They get the same uniforms: `mvp`, `shade`, `tint`, `uv_offset`, `uv_size` and `tex`. With
+    /// the `hot-reload` feature, they're recompiled whenever they change.
+    pub shaders: Option<(String, String)>,
 }
 
 pub trait Game {
@@ -26,35 +67,147 @@ pub trait Game {
     fn init(&mut self, _context: &mut Context) {}
     fn render(&mut self, _canvas: &mut Canvas, _context: &mut Context) {}
     fn update(&mut self, _context: &mut Context) {}
+    /// The window's new size, in pixels.
+    fn resized(&mut self, _context: &mut Context, _width: u32, _height: u32) {}
+    fn focus_changed(&mut self, _context: &mut Context, _focused: bool) {}
+
+    /// Something went wrong while running, e.g. a sprite couldn't be loaded. Return the error to
+    /// stop the game, and have `run_game` return it; by default, it's printed and the game goes
+    /// on.
+    fn error(&mut self, _context: &mut Context, error: Error) -> Result<(), Error> {
+        eprintln!("{}", error);
+        Ok(())
+    }
+
+    /// The OpenGL context was recreated after being lost. Textures loaded from files are back,
+    /// but generated textures and meshes have to be created again.
+    fn context_restored(&mut self, _context: &mut Context) {}
 }
 
 pub struct Context {
     pub delta: f32,
-    models: HashMap<String, Model>,
+    /// Textures loaded by `get_sprite`, kept until the end of the game; `None` for the ones that
+    /// couldn't be loaded.
+    sprites: HashMap<String, Option<Handle<Texture>>>,
+    /// Drawn by `render_blank`, and in place of sprites that couldn't be loaded.
+    blank: Handle<Texture>,
+    /// Sounds loaded by `play`, kept until the end of the game.
+    sounds: HashMap<String, Handle<Sound>>,
+    assets: Assets,
     pressed: HashSet<KeyCode>,
     display: Display,
+    pub(crate) scheduler: Scheduler,
+    audio: Audio,
+    capture: Capture,
+    window_state: WindowState,
+    focused: bool,
+    /// Passed to `Game::error` after the frame.
+    errors: Vec<Error>,
 }
 
-type EventLoop = glium::glutin::event_loop::EventLoop<()>;
-
-impl Context {
-    fn new(config: &GameConfig, event_loop: &EventLoop) -> Self {
-        let (width, height) = config.window_size;
-
-        let window_builder = WindowBuilder::new()
-            .with_inner_size(LogicalSize::new(width, height))
-            .with_title(config.title.to_string());
+/// The window settings, kept up to date with the changes made while running, to build the window
+/// again after the OpenGL context was lost.
+struct WindowState {
+    title: String,
+    size: (u32, u32),
+    mode: WindowMode,
+    resizable: bool,
+    min_size: Option<(u32, u32)>,
+    icon: Option<String>,
+    cursor_visible: bool,
+    cursor_grab: bool,
+    cursor_icon: CursorIcon,
+}
 
-        let context_builder = ContextBuilder::new();
+impl WindowState {
+    fn new(config: &GameConfig) -> Self {
+        Self {
+            title: config.title.clone(),
+            size: config.window_size,
+            mode: config.window_mode,
+            resizable: config.resizable,
+            min_size: config.min_window_size,
+            icon: config.icon.clone(),
+            cursor_visible: config.cursor_visible,
+            cursor_grab: config.cursor_grab,
+            cursor_icon: CursorIcon::default(),
+        }
+    }
+}
 
-        let display = Display::new(window_builder, context_builder, &event_loop).unwrap();
+type EventLoop = glium::glutin::event_loop::EventLoop<()>;
 
-        Self {
+impl Context {
+    fn new(config: &mut GameConfig, event_loop: &EventLoop) -> Result<Self, Error> {
+        let window_state = WindowState::new(config);
+        let display = Display::new(
+            window_builder(&window_state)?,
+            context_builder(config),
+            event_loop,
+        )?;
+        let mut assets = Assets::new(Some(display.clone()));
+        let blank = assets.insert(Texture::blank(&display)?);
+
+        let mut context = Self {
             delta: 0.0,
-            models: HashMap::new(),
+            sprites: HashMap::new(),
+            blank,
+            sounds: HashMap::new(),
+            assets,
             pressed: HashSet::new(),
             display,
+            scheduler: Scheduler::default(),
+            audio: match config.audio.take() {
+                Some(backend) => Audio::from_backend(backend),
+                None => Audio::with_default_backend(),
+            },
+            capture: Capture::default(),
+            window_state,
+            focused: true,
+            errors: Vec::new(),
+        };
+
+        context.apply_window_state();
+        Ok(context)
+    }
+
+    /// Recreate the window and its OpenGL context after the context was lost, as the game last
+    /// set it up.
+    fn restore(&mut self, config: &GameConfig, event_loop: &EventLoop) -> Result<(), Error> {
+        // Resized by the player, or by the game in ways `set_window_size` doesn't see.
+        if self.window_state.mode == WindowMode::Windowed {
+            self.window_state.size = self.window_size();
         }
+
+        self.display.rebuild(
+            window_builder(&self.window_state)?,
+            context_builder(config),
+            event_loop,
+        )?;
+
+        self.assets.reload_all::<Texture>();
+
+        self.blank = self.assets.insert(Texture::blank(&self.display)?);
+
+        self.apply_window_state();
+        Ok(())
+    }
+
+    /// What the window can't be built with.
+    fn apply_window_state(&mut self) {
+        self.set_window_mode(self.window_state.mode);
+        self.set_cursor_visible(self.window_state.cursor_visible);
+        self.set_cursor_grab(self.window_state.cursor_grab);
+        self.set_cursor_icon(self.window_state.cursor_icon);
+    }
+
+    /// Pass an error to `Game::error` once the frame is done.
+    pub(crate) fn report(&mut self, error: Error) {
+        self.errors.push(error);
+    }
+
+    fn window(&self) -> Ref<'_, Window> {
+        Ref::map(self.display.gl_window(), |gl_window| gl_window.window())
     }
 
     fn press(&mut self, key: KeyCode) {
@@ -65,6 +218,10 @@ impl Context {
         self.pressed.remove(&key);
     }
 
+    pub(crate) fn display(&self) -> &Display {
+        &self.display
+    }
+
     pub fn is_held(&self, key: KeyCode) -> bool {
         self.pressed.contains(&key)
     }
@@ -75,85 +232,512 @@ impl Context {
         held
     }
 
+    /// The sprite model of a texture, loaded right away the first time. Textures that can't be
+    /// loaded are reported to `Game::error`, and drawn blank.
     pub fn get_sprite(&mut self, filename: &str) -> &Model {
-        if self.models.contains_key(&filename.to_string()) {
-            return self.models.get(&filename.to_string()).unwrap();
+        let loaded = match self.sprites.get(filename) {
+            Some(Some(handle)) => self.assets.state(handle) == LoadState::Loaded,
+            // Not tried again, so that the error is only reported once.
+            Some(None) => true,
+            None => false,
+        };
+
+        if !loaded {
+            let handle = match self.assets.load_now(filename) {
+                Ok(handle) => Some(handle),
+                Err(error) => {
+                    self.report(error.into());
+                    None
+                }
+            };
+
+            self.sprites.insert(filename.to_string(), handle);
         }
 
-        let model = Model::square(
-            &self.display,
-            if filename == "rect" {
-                None
-            } else {
-                Some(filename)
-            },
-        );
-
-        self.models.insert(filename.to_string(), model);
-        self.models.get(&filename.to_string()).unwrap()
+        let handle = self.sprites[filename].as_ref().unwrap_or(&self.blank);
+        self.assets.get(handle).unwrap().model()
     }
 
-    pub fn render(&mut self, filename: &str) -> ModelRenderBuilder {
+    pub fn render(&mut self, filename: &str) -> ModelRenderBuilder<'_> {
         ModelRenderBuilder::new(self.get_sprite(filename))
     }
-}
 
-pub fn run_game<T: 'static + Game>(game: T) {
-    let mut game = Mutex::new(game);
+    /// An untextured sprite, drawn in the shade color only.
+    pub fn render_blank(&self) -> ModelRenderBuilder<'_> {
+        ModelRenderBuilder::new(self.assets.get(&self.blank).unwrap().model())
+    }
+
+    /// `None` until the texture is loaded.
+    pub fn render_texture(&self, texture: &Handle<Texture>) -> Option<ModelRenderBuilder<'_>> {
+        let texture = self.assets.get(texture)?;
+        Some(ModelRenderBuilder::new(texture.model()))
+    }
+
+    pub fn render_nine_slice(&mut self, filename: &str) -> NineSliceBuilder<'_> {
+        NineSliceBuilder::new(self.get_sprite(filename))
+    }
+
+    /// Unlike sprites, meshes aren't cached: you own the returned model.
+    pub fn build_mesh(&self, builder: &MeshBuilder) -> Result<Model, Error> {
+        builder.build(&self.display)
+    }
+
+    pub fn update_mesh(&self, model: &mut Model, builder: &MeshBuilder) -> Result<(), Error> {
+        model.update(&self.display, builder)
+    }
+
+    /// Start a one-shot timer; check it with `fired`.
+    pub fn after(&mut self, seconds: f32) -> TaskId {
+        self.scheduler.add_timer(seconds, None, None)
+    }
+
+    /// Start a timer firing every `seconds`; check it with `fired`.
+    pub fn every(&mut self, seconds: f32) -> TaskId {
+        self.scheduler.add_timer(seconds, Some(seconds), None)
+    }
+
+    /// Call `f` once, `seconds` from now, right before an update.
+    pub fn run_after<F: FnOnce(&mut Context) + 'static>(&mut self, seconds: f32, f: F) -> TaskId {
+        let mut f = Some(f);
+
+        let callback = Box::new(move |context: &mut Context| {
+            if let Some(f) = f.take() {
+                f(context);
+            }
+        });
+
+        self.scheduler.add_timer(seconds, None, Some(callback))
+    }
+
+    /// Call `f` every `seconds`, right before updates.
+    pub fn run_every<F: FnMut(&mut Context) + 'static>(&mut self, seconds: f32, f: F) -> TaskId {
+        self.scheduler
+            .add_timer(seconds, Some(seconds), Some(Box::new(f)))
+    }
+
+    /// How many times the timer fired right before this update: usually 0 or 1, more for
+    /// repeating timers shorter than `delta`.
+    pub fn fired(&self, timer: TaskId) -> u32 {
+        self.scheduler.fired(timer)
+    }
+
+    /// Start a coroutine, e.g. `context.spawn(|co| async move { co.wait(1.0).await; ... })`. It
+    /// runs until its first wait right away, then resumes right before updates.
+    pub fn spawn<F, Fut>(&mut self, coroutine: F) -> TaskId
+    where
+        F: FnOnce(Coroutine) -> Fut,
+        Fut: Future<Output = ()> + 'static,
+    {
+        self.scheduler.spawn(coroutine)
+    }
+
+    /// Stop a timer or a coroutine. Returns false if it already finished.
+    pub fn cancel(&mut self, task: TaskId) -> bool {
+        self.scheduler.cancel(task)
+    }
+
+    /// Whether a timer or a coroutine is still going.
+    pub fn is_running(&self, task: TaskId) -> bool {
+        self.scheduler.is_active(task)
+    }
+
+    pub fn audio(&mut self) -> &mut Audio {
+        &mut self.audio
+    }
+
+    /// Start playing a sound effect once its settings are committed. The sound is loaded right
+    /// away the first time, or found under the name given to `add_sound`.
+    pub fn play(&mut self, filename: &str) -> Result<SoundBuilder<'_>, Error> {
+        let handle = match self.sounds.get(filename) {
+            Some(handle) if self.assets.state(handle) == LoadState::Loaded => handle.clone(),
+            _ => {
+                let handle = self.assets.load_now(filename)?;
+                self.sounds.insert(filename.to_string(), handle.clone());
+                handle
+            }
+        };
+
+        Ok(self.audio.play_sound(self.assets.get(&handle).unwrap()))
+    }
+
+    /// `None` until the sound is loaded.
+    pub fn play_sound(&mut self, sound: &Handle<Sound>) -> Option<SoundBuilder<'_>> {
+        let sound = self.assets.get(sound)?;
+        Some(self.audio.play_sound(sound))
+    }
+
+    /// Keep a sound under a name, so that `play` finds it, e.g. one made with a `Synth`.
+    pub fn add_sound(&mut self, name: &str, sound: Sound) {
+        let handle = self.assets.insert(sound);
+        self.sounds.insert(name.to_string(), handle);
+    }
+
+    pub fn assets(&mut self) -> &mut Assets {
+        &mut self.assets
+    }
+
+    /// Save the current frame once it's rendered. The format follows the extension, e.g. PNG.
+    pub fn screenshot(&mut self, filename: &str) {
+        self.capture.screenshot(filename);
+    }
+
+    /// The last frame shown, e.g. for a save game thumbnail.
+    pub fn grab_frame(&self) -> Result<RgbaImage, Error> {
+        capture::grab_frame(&self.display)
+    }
+
+    /// Record frames into an animated GIF if `path` ends in `.gif`, otherwise into numbered PNGs
+    /// in the `path` directory. Stops the current recording, if any.
+    pub fn start_recording(&mut self, path: &str) {
+        self.capture.start_recording(path);
+    }
+
+    /// The recording is finished in the background.
+    pub fn stop_recording(&mut self) {
+        self.capture.stop_recording();
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.capture.is_recording()
+    }
+
+    pub fn set_window_mode(&mut self, mode: WindowMode) {
+        let window = self.window();
+        window.set_fullscreen(window::fullscreen(&window, mode));
+        drop(window);
+
+        self.window_state.mode = mode;
+    }
+
+    pub fn window_mode(&self) -> WindowMode {
+        self.window_state.mode
+    }
+
+    /// Ignored in fullscreen.
+    pub fn set_window_size(&mut self, width: u32, height: u32) {
+        self.window()
+            .set_inner_size(LogicalSize::new(width, height));
+
+        if self.window_state.mode == WindowMode::Windowed {
+            self.window_state.size = (width, height);
+        }
+    }
+
+    /// The same units as `GameConfig::window_size`, which might not be pixels on high DPI
+    /// screens.
+    pub fn window_size(&self) -> (u32, u32) {
+        let window = self.window();
+        window
+            .inner_size()
+            .to_logical::<u32>(window.scale_factor())
+            .into()
+    }
+
+    pub fn set_resizable(&mut self, resizable: bool) {
+        self.window().set_resizable(resizable);
+        self.window_state.resizable = resizable;
+    }
+
+    pub fn set_min_window_size(&mut self, size: Option<(u32, u32)>) {
+        let logical_size = size.map(|(width, height)| LogicalSize::new(width, height));
+        self.window().set_min_inner_size(logical_size);
+        self.window_state.min_size = size;
+    }
+
+    pub fn set_title(&mut self, title: &str) {
+        self.window().set_title(title);
+        self.window_state.title = title.to_string();
+    }
+
+    /// `None` for the default icon.
+    pub fn set_icon(&mut self, filename: Option<&str>) -> Result<(), Error> {
+        let icon = match filename {
+            Some(filename) => Some(window::load_icon(filename)?),
+            None => None,
+        };
+
+        self.window().set_window_icon(icon);
+        self.window_state.icon = filename.map(str::to_string);
+        Ok(())
+    }
+
+    pub fn set_cursor_visible(&mut self, visible: bool) {
+        self.window().set_cursor_visible(visible);
+        self.window_state.cursor_visible = visible;
+    }
+
+    /// Keep the cursor inside the window, or let it go again.
+    pub fn set_cursor_grab(&mut self, grab: bool) {
+        // Not supported on every platform, where the cursor just stays free.
+        let _ = self.window().set_cursor_grab(grab);
+        self.window_state.cursor_grab = grab;
+    }
+
+    /// One of the system's cursors, e.g. a hand over buttons.
+    pub fn set_cursor_icon(&mut self, icon: CursorIcon) {
+        self.window().set_cursor_icon(icon);
+        self.window_state.cursor_icon = icon;
+    }
+
+    /// Whether the window has the keyboard focus.
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    fn focus(&mut self, focused: bool) {
+        self.focused = focused;
+
+        // Keys released while away would stay held otherwise.
+        if !focused {
+            self.pressed.clear();
+        }
+    }
+}
 
+/// Runs until the window is closed, or `Game::error` returns an error.
+pub fn run_game<T: 'static + Game>(mut game: T) -> Result<(), Error> {
     let mut config = GameConfig {
         title: "My Game".into(),
         window_size: (640, 420),
+        window_mode: WindowMode::Windowed,
+        resizable: true,
+        min_window_size: None,
+        icon: None,
+        vsync: false,
+        multisampling: 0,
+        cursor_visible: true,
+        cursor_grab: false,
+        pause_on_focus_lost: false,
         update_fps: 24.0,
+        audio: None,
+        screenshot_key: None,
+        record_key: None,
+        shaders: None,
     };
 
-    game.get_mut().unwrap().configure(&mut config);
+    game.configure(&mut config);
 
-    let event_loop = EventLoop::new();
+    let mut event_loop = EventLoop::new();
 
-    let mut context = Context::new(&config, &event_loop);
+    let mut context = Context::new(&mut config, &event_loop)?;
     context.delta = 1.0 / config.update_fps;
 
-    let program =
-        Program::from_source(&context.display, VERTEX_SHADER, FRAGMENT_SHADER, None).unwrap();
+    game.init(&mut context);
+
+    loop {
+        match run_frames(&mut game, &mut context, &config, &mut event_loop)? {
+            Exit::Closed => return Ok(()),
+            Exit::ContextLost => {
+                context.restore(&config, &event_loop)?;
+                game.context_restored(&mut context);
+            }
+        }
+    }
+}
+
+/// Why `run_frames` stopped.
+enum Exit {
+    Closed,
+    ContextLost,
+}
+
+/// Run the event loop until the window is closed or the OpenGL context is lost.
+fn run_frames<T: Game>(
+    game: &mut T,
+    context: &mut Context,
+    config: &GameConfig,
+    event_loop: &mut EventLoop,
+) -> Result<Exit, Error> {
+    #[cfg_attr(not(feature = "hot-reload"), allow(unused_mut))]
+    let mut program = sprite_program(&context.display, config)?;
 
-    game.get_mut().unwrap().init(&mut context);
+    #[cfg(feature = "hot-reload")]
+    let mut shaders = ShaderWatcher::new(config);
 
     let mut previous_frame = Instant::now();
+    let mut exit = None;
 
-    event_loop.run(move |event, _, control_flow| {
-        if let Some(event) = event.to_static() {
+    event_loop.run_return(|event, _, control_flow| {
+        // Events still come in after exiting, e.g. `LoopDestroyed`.
+        if exit.is_some() {
+            *control_flow = ControlFlow::Exit;
+            return;
+        }
+
+        if let Some(Event::WindowEvent { event, .. }) = event.to_static() {
             match event {
-                Event::WindowEvent { event, .. } => match event {
-                    WindowEvent::CloseRequested => {
-                        *control_flow = ControlFlow::Exit;
-                        return;
+                WindowEvent::CloseRequested => {
+                    for error in context.capture.finish() {
+                        context.report(error);
                     }
-                    WindowEvent::KeyboardInput { input, .. } => {
-                        if let Some(key) = input.virtual_keycode {
-                            match input.state {
-                                ElementState::Pressed => context.press(key),
-                                ElementState::Released => context.release(key),
-                            }
+
+                    // The game can still fail on the errors of unfinished recordings.
+                    exit = match handle_errors(game, context) {
+                        Some(Err(error)) => Some(Err(error)),
+                        _ => Some(Ok(Exit::Closed)),
+                    };
+                    *control_flow = ControlFlow::Exit;
+                    return;
+                }
+                WindowEvent::KeyboardInput { input, .. } => {
+                    if let Some(key) = input.virtual_keycode {
+                        // Ignore key repeat.
+                        if input.state == ElementState::Pressed && !context.is_held(key) {
+                            capture_key(context, config, key);
+                        }
+
+                        match input.state {
+                            ElementState::Pressed => context.press(key),
+                            ElementState::Released => context.release(key),
                         }
                     }
-                    _ => {}
-                },
+                }
+                WindowEvent::Resized(size) => {
+                    game.resized(context, size.width, size.height);
+                }
+                WindowEvent::Focused(focused) => {
+                    context.focus(focused);
+                    game.focus_changed(context, focused);
+                }
                 _ => {}
             }
         }
 
+        for error in context.assets.update() {
+            context.report(error);
+        }
+
+        #[cfg(feature = "hot-reload")]
+        {
+            if shaders.changed() {
+                // Keep drawing with the old shaders until the new ones compile.
+                match sprite_program(&context.display, config) {
+                    Ok(new_program) => program = new_program,
+                    Err(error) => context.report(error),
+                }
+            }
+        }
+
         let this_frame = Instant::now();
         let mut catchup = this_frame.duration_since(previous_frame).as_secs_f32();
 
+        if config.pause_on_focus_lost && !context.focused {
+            // Don't catch up on the time spent paused.
+            previous_frame = this_frame;
+            catchup = 0.0;
+        }
+
         while catchup > context.delta {
-            game.get_mut().unwrap().update(&mut context);
+            let delta = context.delta;
+            Scheduler::update(context, delta);
+            for error in context.audio.update(delta) {
+                context.report(error);
+            }
+
+            game.update(context);
             catchup -= context.delta;
             previous_frame = this_frame;
         }
 
         let mut canvas = Canvas::new(context.display.draw(), &program);
-        game.get_mut().unwrap().render(&mut canvas, &mut context);
-        canvas.finish();
+        game.render(&mut canvas, context);
+        context.audio.camera_moved(canvas.camera_position());
+
+        if let Err(error) = canvas.finish() {
+            context.report(error);
+        }
+
+        exit = handle_errors(game, context);
+
+        if exit.is_some() {
+            *control_flow = ControlFlow::Exit;
+            return;
+        }
+
+        for error in context.capture.frame_shown(&context.display) {
+            context.report(error);
+        }
     });
+
+    exit.unwrap_or(Ok(Exit::Closed))
+}
+
+/// Pass the errors of the frame to the game. `Some` if the game should stop running, for good if
+/// the game returned an error, or to recover the lost OpenGL context.
+fn handle_errors<T: Game>(game: &mut T, context: &mut Context) -> Option<Result<Exit, Error>> {
+    let mut exit = None;
+
+    for error in std::mem::take(&mut context.errors) {
+        let lost = matches!(error, Error::ContextLost);
+
+        if let Err(error) = game.error(context, error) {
+            return Some(Err(error));
+        }
+
+        if lost {
+            exit = Some(Ok(Exit::ContextLost));
+        }
+    }
+
+    exit
+}
+
+/// What sprites are drawn with: the built-in shaders, or the ones of `GameConfig::shaders`.
+fn sprite_program(display: &Display, config: &GameConfig) -> Result<Program, Error> {
+    let (vertex, fragment) = match &config.shaders {
+        Some((vertex, fragment)) => (read_shader(vertex)?, read_shader(fragment)?),
+        None => (VERTEX_SHADER.to_string(), FRAGMENT_SHADER.to_string()),
+    };
+
+    Ok(Program::from_source(display, &vertex, &fragment, None)?)
+}
+
+fn read_shader(path: &str) -> Result<String, Error> {
+    let error = |kind| AssetError {
+        path: path.to_string(),
+        kind,
+    };
+
+    let bytes = vfs::read(path).map_err(|io| error(AssetErrorKind::Io(io)))?;
+
+    String::from_utf8(bytes).map_err(|utf8| error(AssetErrorKind::Decode(utf8.to_string())).into())
+}
+
+fn window_builder(window_state: &WindowState) -> Result<WindowBuilder, Error> {
+    let (width, height) = window_state.size;
+
+    let mut window_builder = WindowBuilder::new()
+        .with_inner_size(LogicalSize::new(width, height))
+        .with_title(window_state.title.to_string())
+        .with_resizable(window_state.resizable);
+
+    if let Some((width, height)) = window_state.min_size {
+        window_builder = window_builder.with_min_inner_size(LogicalSize::new(width, height));
+    }
+
+    if let Some(icon) = &window_state.icon {
+        window_builder = window_builder.with_window_icon(Some(window::load_icon(icon)?));
+    }
+
+    Ok(window_builder)
+}
+
+fn context_builder(config: &GameConfig) -> ContextBuilder<'static, NotCurrent> {
+    ContextBuilder::new()
+        .with_vsync(config.vsync)
+        .with_multisampling(config.multisampling)
+}
+
+fn capture_key(context: &mut Context, config: &GameConfig, key: KeyCode) {
+    if config.screenshot_key == Some(key) {
+        context.screenshot(&capture::timestamped("screenshot", "png"));
+    }
+
+    if config.record_key == Some(key) {
+        if context.is_recording() {
+            context.stop_recording();
+        } else {
+            context.start_recording(&capture::timestamped("recording", "gif"));
+        }
+    }
 }
@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::game::GameConfig;
+use crate::vfs;
+
+/// How often files are checked for changes.
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// What a file looked like when last checked; `None` if it didn't exist.
+type Stamp = Option<(SystemTime, u64)>;
+
+fn stamp(path: &Path) -> Stamp {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Notices changes to files by polling their modification time and size. `Assets` uses one to
+/// reload the files it loaded, but it works on any file, e.g. shaders of your own.
+#[derive(Default)]
+pub struct FileWatcher {
+    files: HashMap<PathBuf, Stamp>,
+}
+
+impl FileWatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Changes are looked for from now on. Files that don't exist yet are reported once created.
+    pub fn watch<P: AsRef<Path>>(&mut self, path: P) {
+        let path = path.as_ref();
+
+        if !self.files.contains_key(path) {
+            self.files.insert(path.to_path_buf(), stamp(path));
+        }
+    }
+
+    pub fn unwatch<P: AsRef<Path>>(&mut self, path: P) {
+        self.files.remove(path.as_ref());
+    }
+
+    pub fn is_watching<P: AsRef<Path>>(&self, path: P) -> bool {
+        self.files.contains_key(path.as_ref())
+    }
+
+    /// The files written to since the last poll. Deleted files aren't reported until they come
+    /// back.
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+
+        for (path, old) in &mut self.files {
+            let new = stamp(path);
+
+            if new != *old {
+                *old = new;
+
+                if new.is_some() {
+                    changed.push(path.clone());
+                }
+            }
+        }
+
+        changed
+    }
+}
+
+/// Tells the game loop when the shader files of `GameConfig::shaders` change, to recompile them.
+pub(crate) struct ShaderWatcher {
+    watcher: FileWatcher,
+    last_poll: Instant,
+}
+
+impl ShaderWatcher {
+    pub(crate) fn new(config: &GameConfig) -> Self {
+        let mut watcher = FileWatcher::new();
+
+        if let Some((vertex, fragment)) = &config.shaders {
+            // Packed and embedded files don't change.
+            for path in [vertex, fragment].iter() {
+                if let Some(file) = vfs::local_path(path) {
+                    watcher.watch(file);
+                }
+            }
+        }
+
+        Self {
+            watcher,
+            last_poll: Instant::now(),
+        }
+    }
+
+    /// Whether a shader changed since the last time it was checked.
+    pub(crate) fn changed(&mut self) -> bool {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return false;
+        }
+
+        self.last_poll = Instant::now();
+        !self.watcher.poll().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A file in a directory of its own, so that tests don't step on each other. Tests change its
+    /// size on every write, since the modification time may stay the same in between.
+    fn temp_file(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("gamelib-watch-{}-{}", std::process::id(), name));
+        fs::create_dir_all(&directory).unwrap();
+        directory.join("file.txt")
+    }
+
+    #[test]
+    fn modified_files_are_reported_once() {
+        let path = temp_file("modify");
+        fs::write(&path, "a").unwrap();
+
+        let mut watcher = FileWatcher::new();
+        watcher.watch(&path);
+        assert!(watcher.poll().is_empty());
+
+        fs::write(&path, "ab").unwrap();
+        assert_eq!(watcher.poll(), vec![path.clone()]);
+        assert!(watcher.poll().is_empty());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn deleted_files_are_reported_when_recreated() {
+        let path = temp_file("delete");
+        fs::write(&path, "a").unwrap();
+
+        let mut watcher = FileWatcher::new();
+        watcher.watch(&path);
+
+        fs::remove_file(&path).unwrap();
+        assert!(watcher.poll().is_empty());
+        assert!(watcher.is_watching(&path));
+
+        fs::write(&path, "ab").unwrap();
+        assert_eq!(watcher.poll(), vec![path.clone()]);
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn missing_files_are_reported_once_created() {
+        let path = temp_file("create");
+
+        let mut watcher = FileWatcher::new();
+        watcher.watch(&path);
+        assert!(watcher.poll().is_empty());
+
+        fs::write(&path, "a").unwrap();
+        assert_eq!(watcher.poll(), vec![path.clone()]);
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn unwatched_files_are_not_reported() {
+        let path = temp_file("unwatch");
+        fs::write(&path, "a").unwrap();
+
+        let mut watcher = FileWatcher::new();
+        watcher.watch(&path);
+        watcher.unwatch(&path);
+        assert!(!watcher.is_watching(&path));
+
+        fs::write(&path, "ab").unwrap();
+        assert!(watcher.poll().is_empty());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -1,9 +1,34 @@
 #[macro_use]
 extern crate glium;
 
+pub mod assets;
+pub mod audio;
+pub mod capture;
+pub mod collision;
+pub mod controller;
+pub mod ecs;
+pub mod error;
+pub mod font;
 pub mod game;
+#[cfg(feature = "hot-reload")]
+pub mod hot_reload;
+pub mod math;
+pub mod node;
+#[cfg(feature = "physics")]
+pub mod physics;
 pub mod render;
+pub mod scene;
+pub mod scheduler;
+pub mod spatial;
+pub mod synth;
+pub mod transition;
+pub mod tween;
 pub mod utils;
+pub mod vfs;
+pub mod window;
 
+pub use crate::error::Error;
 pub use crate::game::{run_game, Context, Game, GameConfig, KeyCode};
-pub use crate::render::{Canvas, Model};
+pub use crate::render::{
+    Canvas, MeshBuilder, Model, ModelRenderBuilder, NineSliceBuilder, PrimitiveType,
+};
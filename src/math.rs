@@ -0,0 +1,533 @@
+use glium::uniforms::{AsUniformValue, UniformValue};
+
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Vec2 {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Vec2 {
+    pub const ZERO: Vec2 = Vec2 { x: 0.0, y: 0.0 };
+    pub const ONE: Vec2 = Vec2 { x: 1.0, y: 1.0 };
+
+    pub fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+
+    /// Unit vector pointing at `rad` radians counter-clockwise from the X axis.
+    pub fn from_angle(rad: f32) -> Self {
+        Self::new(rad.cos(), rad.sin())
+    }
+
+    pub fn length(self) -> f32 {
+        self.length_squared().sqrt()
+    }
+
+    pub fn length_squared(self) -> f32 {
+        self.dot(self)
+    }
+
+    pub fn distance(self, other: Vec2) -> f32 {
+        (other - self).length()
+    }
+
+    /// Returns a zero vector instead of NaNs for zero-length vectors.
+    pub fn normalize(self) -> Self {
+        let length = self.length();
+
+        if length == 0.0 {
+            Self::ZERO
+        } else {
+            self / length
+        }
+    }
+
+    pub fn dot(self, other: Vec2) -> f32 {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// The Z component of the 3D cross product: positive if `other` is counter-clockwise from
+    /// `self`.
+    pub fn cross(self, other: Vec2) -> f32 {
+        self.x * other.y - self.y * other.x
+    }
+
+    /// Rotated 90 degrees counter-clockwise.
+    pub fn perp(self) -> Self {
+        Self::new(-self.y, self.x)
+    }
+
+    pub fn lerp(self, other: Vec2, t: f32) -> Self {
+        self + (other - self) * t
+    }
+
+    /// Angle in radians counter-clockwise from the X axis, in `-PI..=PI`.
+    pub fn angle(self) -> f32 {
+        self.y.atan2(self.x)
+    }
+
+    /// Signed angle in radians to rotate `self` by to point in the direction of `other`.
+    pub fn angle_to(self, other: Vec2) -> f32 {
+        self.cross(other).atan2(self.dot(other))
+    }
+
+    pub fn rotate(self, rad: f32) -> Self {
+        let (sin, cos) = rad.sin_cos();
+        Self::new(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+    }
+
+    pub fn abs(self) -> Self {
+        Self::new(self.x.abs(), self.y.abs())
+    }
+
+    pub fn min(self, other: Vec2) -> Self {
+        Self::new(self.x.min(other.x), self.y.min(other.y))
+    }
+
+    pub fn max(self, other: Vec2) -> Self {
+        Self::new(self.x.max(other.x), self.y.max(other.y))
+    }
+}
+
+impl From<(f32, f32)> for Vec2 {
+    fn from((x, y): (f32, f32)) -> Self {
+        Self::new(x, y)
+    }
+}
+
+impl From<Vec2> for (f32, f32) {
+    fn from(vec: Vec2) -> Self {
+        (vec.x, vec.y)
+    }
+}
+
+impl Add for Vec2 {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl Sub for Vec2 {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+/// Component-wise multiplication.
+impl Mul for Vec2 {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self::new(self.x * other.x, self.y * other.y)
+    }
+}
+
+impl Mul<f32> for Vec2 {
+    type Output = Self;
+
+    fn mul(self, scalar: f32) -> Self {
+        Self::new(self.x * scalar, self.y * scalar)
+    }
+}
+
+impl Mul<Vec2> for f32 {
+    type Output = Vec2;
+
+    fn mul(self, vec: Vec2) -> Vec2 {
+        vec * self
+    }
+}
+
+impl Div<f32> for Vec2 {
+    type Output = Self;
+
+    fn div(self, scalar: f32) -> Self {
+        Self::new(self.x / scalar, self.y / scalar)
+    }
+}
+
+impl Neg for Vec2 {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.x, -self.y)
+    }
+}
+
+impl AddAssign for Vec2 {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Vec2 {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl MulAssign<f32> for Vec2 {
+    fn mul_assign(&mut self, scalar: f32) {
+        *self = *self * scalar;
+    }
+}
+
+impl DivAssign<f32> for Vec2 {
+    fn div_assign(&mut self, scalar: f32) {
+        *self = *self / scalar;
+    }
+}
+
+/// A 2D affine transformation, stored row by row.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Mat3(pub [[f32; 3]; 3]);
+
+impl Mat3 {
+    pub fn identity() -> Self {
+        Self([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]])
+    }
+
+    pub fn translation(dx: f32, dy: f32) -> Self {
+        Self([[1.0, 0.0, dx], [0.0, 1.0, dy], [0.0, 0.0, 1.0]])
+    }
+
+    pub fn rotation(rad: f32) -> Self {
+        let (sin, cos) = rad.sin_cos();
+        Self([[cos, -sin, 0.0], [sin, cos, 0.0], [0.0, 0.0, 1.0]])
+    }
+
+    pub fn scale(x_mult: f32, y_mult: f32) -> Self {
+        Self([[x_mult, 0.0, 0.0], [0.0, y_mult, 0.0], [0.0, 0.0, 1.0]])
+    }
+
+    pub fn determinant(&self) -> f32 {
+        let m = &self.0;
+
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    /// `None` if the matrix is singular, e.g. scales by zero, or holds NaNs or infinities.
+    pub fn inverse(&self) -> Option<Self> {
+        let det = self.determinant();
+
+        if det == 0.0 || !det.is_finite() {
+            return None;
+        }
+
+        let m = &self.0;
+        let mut result = [[0.0; 3]; 3];
+
+        for (i, row) in result.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
+                // Cofactor of the transposed element.
+                let (r0, r1) = match j {
+                    0 => (1, 2),
+                    1 => (0, 2),
+                    _ => (0, 1),
+                };
+
+                let (c0, c1) = match i {
+                    0 => (1, 2),
+                    1 => (0, 2),
+                    _ => (0, 1),
+                };
+
+                let minor = m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0];
+                let sign = if (i + j) % 2 == 0 { 1.0 } else { -1.0 };
+
+                *cell = sign * minor / det;
+            }
+        }
+
+        Some(Self(result))
+    }
+
+    pub fn transform_point(&self, point: Vec2) -> Vec2 {
+        let m = &self.0;
+
+        Vec2::new(
+            m[0][0] * point.x + m[0][1] * point.y + m[0][2],
+            m[1][0] * point.x + m[1][1] * point.y + m[1][2],
+        )
+    }
+
+    /// Like `transform_point`, but ignores translation.
+    pub fn transform_vector(&self, vector: Vec2) -> Vec2 {
+        let m = &self.0;
+
+        Vec2::new(
+            m[0][0] * vector.x + m[0][1] * vector.y,
+            m[1][0] * vector.x + m[1][1] * vector.y,
+        )
+    }
+}
+
+impl Mul for Mat3 {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        let mut result = self;
+
+        for i in 0..3 {
+            for j in 0..3 {
+                result.0[i][j] = 0.0;
+
+                for n in 0..3 {
+                    result.0[i][j] += self.0[i][n] * other.0[n][j];
+                }
+            }
+        }
+
+        result
+    }
+}
+
+impl From<Mat3> for Mat4 {
+    fn from(mat: Mat3) -> Self {
+        let m = mat.0;
+
+        Self([
+            [m[0][0], m[0][1], 0.0, m[0][2]],
+            [m[1][0], m[1][1], 0.0, m[1][2]],
+            [0.0, 0.0, 1.0, 0.0],
+            [m[2][0], m[2][1], 0.0, m[2][2]],
+        ])
+    }
+}
+
+/// Stored row by row; the shaders multiply vectors from the left to account for that.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Mat4(pub [[f32; 4]; 4]);
+
+impl Mat4 {
+    pub fn identity() -> Self {
+        Self([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn translation(dx: f32, dy: f32) -> Self {
+        Self([
+            [1.0, 0.0, 0.0, dx],
+            [0.0, 1.0, 0.0, dy],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Rotation around the Z axis, i.e. counter-clockwise in 2D.
+    pub fn rotation(rad: f32) -> Self {
+        let (sin, cos) = rad.sin_cos();
+
+        Self([
+            [cos, -sin, 0.0, 0.0],
+            [sin, cos, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn scale(x_mult: f32, y_mult: f32) -> Self {
+        Self([
+            [x_mult, 0.0, 0.0, 0.0],
+            [0.0, y_mult, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Shear by the tangents of the given angles along the X and Y axes.
+    pub fn skew(x_rad: f32, y_rad: f32) -> Self {
+        Self([
+            [1.0, x_rad.tan(), 0.0, 0.0],
+            [y_rad.tan(), 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Maps the given box onto the `-1.0..=1.0` cube of clip space.
+    pub fn orthographic(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Self {
+        Self([
+            [
+                2.0 / (right - left),
+                0.0,
+                0.0,
+                -(right + left) / (right - left),
+            ],
+            [
+                0.0,
+                2.0 / (top - bottom),
+                0.0,
+                -(top + bottom) / (top - bottom),
+            ],
+            [0.0, 0.0, -2.0 / (far - near), -(far + near) / (far - near)],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut result = *self;
+
+        for i in 0..4 {
+            for j in 0..4 {
+                result.0[i][j] = self.0[j][i];
+            }
+        }
+
+        result
+    }
+
+    /// `None` if the matrix is singular, e.g. scales by zero, or holds NaNs or infinities.
+    pub fn inverse(&self) -> Option<Self> {
+        // Gauss-Jordan elimination with partial pivoting.
+        let mut left = self.0;
+        let mut right = Self::identity().0;
+
+        for column in 0..4 {
+            let pivot = (column..4)
+                .max_by(|&a, &b| left[a][column].abs().total_cmp(&left[b][column].abs()))
+                .unwrap();
+
+            if left[pivot][column] == 0.0 || !left[pivot][column].is_finite() {
+                return None;
+            }
+
+            left.swap(column, pivot);
+            right.swap(column, pivot);
+
+            let divisor = left[column][column];
+
+            for j in 0..4 {
+                left[column][j] /= divisor;
+                right[column][j] /= divisor;
+            }
+
+            for row in 0..4 {
+                if row == column {
+                    continue;
+                }
+
+                let factor = left[row][column];
+
+                for j in 0..4 {
+                    left[row][j] -= factor * left[column][j];
+                    right[row][j] -= factor * right[column][j];
+                }
+            }
+        }
+
+        Some(Self(right))
+    }
+
+    /// Transform a point lying on the Z = 0 plane.
+    pub fn transform_point(&self, point: Vec2) -> Vec2 {
+        let m = &self.0;
+
+        let x = m[0][0] * point.x + m[0][1] * point.y + m[0][3];
+        let y = m[1][0] * point.x + m[1][1] * point.y + m[1][3];
+        let w = m[3][0] * point.x + m[3][1] * point.y + m[3][3];
+
+        Vec2::new(x / w, y / w)
+    }
+
+    /// Like `transform_point`, but ignores translation.
+    pub fn transform_vector(&self, vector: Vec2) -> Vec2 {
+        let m = &self.0;
+
+        Vec2::new(
+            m[0][0] * vector.x + m[0][1] * vector.y,
+            m[1][0] * vector.x + m[1][1] * vector.y,
+        )
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        let mut result = self;
+
+        for i in 0..4 {
+            for j in 0..4 {
+                result.0[i][j] = 0.0;
+
+                for n in 0..4 {
+                    result.0[i][j] += self.0[i][n] * other.0[n][j];
+                }
+            }
+        }
+
+        result
+    }
+}
+
+impl AsUniformValue for Mat4 {
+    fn as_uniform_value(&self) -> UniformValue<'_> {
+        AsUniformValue::as_uniform_value(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Mat4, b: Mat4) {
+        for i in 0..4 {
+            for j in 0..4 {
+                assert!((a.0[i][j] - b.0[i][j]).abs() < 1e-5, "{:?} != {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_undoes_the_transformation() {
+        let matrix = Mat4::translation(3.0, -2.0) * Mat4::rotation(0.7) * Mat4::scale(2.0, 0.5);
+        let inverse = matrix.inverse().unwrap();
+
+        assert_close(matrix * inverse, Mat4::identity());
+        assert_close(inverse * matrix, Mat4::identity());
+
+        let point = Vec2::new(1.5, 4.0);
+        let back = inverse.transform_point(matrix.transform_point(point));
+        assert!((back - point).length() < 1e-5);
+    }
+
+    #[test]
+    fn inverse_of_singular_matrices_is_none() {
+        assert!(Mat4::scale(0.0, 1.0).inverse().is_none());
+        assert!(Mat4::scale(f32::NAN, 1.0).inverse().is_none());
+    }
+
+    #[test]
+    fn mat3_inverse_undoes_the_transformation() {
+        let matrix = Mat3::translation(3.0, -2.0) * Mat3::rotation(0.7) * Mat3::scale(2.0, 0.5);
+        let inverse = matrix.inverse().unwrap();
+
+        // Compared as `Mat4`s, which also checks the conversion.
+        assert_close((matrix * inverse).into(), Mat4::identity());
+        assert_close((inverse * matrix).into(), Mat4::identity());
+
+        let point = Vec2::new(1.5, 4.0);
+        let back = inverse.transform_point(matrix.transform_point(point));
+        assert!((back - point).length() < 1e-5);
+    }
+
+    #[test]
+    fn mat3_inverse_of_singular_matrices_is_none() {
+        assert!(Mat3::scale(0.0, 1.0).inverse().is_none());
+        assert!(Mat3::scale(f32::NAN, 1.0).inverse().is_none());
+        assert!(Mat3::scale(f32::INFINITY, 1.0).inverse().is_none());
+    }
+}
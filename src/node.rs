@@ -0,0 +1,280 @@
+use crate::game::Context;
+use crate::render::Canvas;
+use crate::utils::{Color, Mat4, Vec2};
+
+/// Local transformation of a `Node` relative to its parent.
+#[derive(Copy, Clone)]
+pub struct Transform {
+    pub position: Vec2,
+    pub rotation: f32,
+    pub scale: Vec2,
+    /// The local point that rotation and scaling happen around.
+    pub pivot: Vec2,
+}
+
+impl Transform {
+    pub fn new() -> Self {
+        Self {
+            position: Vec2::ZERO,
+            rotation: 0.0,
+            scale: Vec2::ONE,
+            pivot: Vec2::ZERO,
+        }
+    }
+
+    pub fn matrix(&self) -> Mat4 {
+        Mat4::translation(self.position.x, self.position.y)
+            * Mat4::rotation(self.rotation)
+            * Mat4::scale(self.scale.x, self.scale.y)
+            * Mat4::translation(-self.pivot.x, -self.pivot.y)
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct Sprite {
+    /// `None` for a blank one.
+    filename: Option<String>,
+    size: Vec2,
+}
+
+/// A node of a scene graph. Every node is positioned relative to its parent, so moving, rotating
+/// or scaling a node does the same to all of its children.
+pub struct Node {
+    pub name: Option<String>,
+    pub transform: Transform,
+    /// Hides the node along with its children.
+    pub visible: bool,
+    /// Multiplies the color of the node along with its children.
+    pub tint: Color,
+    sprite: Option<Sprite>,
+    /// Of the sprite only, unlike the tint.
+    shade: Color,
+    children: Vec<Node>,
+}
+
+impl Node {
+    pub fn new() -> Self {
+        Self {
+            name: None,
+            transform: Transform::new(),
+            visible: true,
+            tint: (1.0, 1.0, 1.0),
+            sprite: None,
+            shade: (0.0, 0.0, 0.0),
+            children: Vec::new(),
+        }
+    }
+
+    pub fn named(name: &str) -> Self {
+        let mut node = Self::new();
+        node.name = Some(name.to_string());
+        node
+    }
+
+    /// Draw a sprite centered at the node's origin. `filename` is passed to `Context::render`.
+    pub fn with_sprite(mut self, filename: &str, width: f32, height: f32) -> Self {
+        self.sprite = Some(Sprite {
+            filename: Some(filename.to_string()),
+            size: Vec2::new(width, height),
+        });
+
+        self
+    }
+
+    /// Draw an untextured sprite in the shade color, as in `Context::render_blank`.
+    pub fn with_blank_sprite(mut self, width: f32, height: f32) -> Self {
+        self.sprite = Some(Sprite {
+            filename: None,
+            size: Vec2::new(width, height),
+        });
+
+        self
+    }
+
+    /// Shade the node's sprite, whether it's set before or after; see
+    /// `ModelRenderBuilder::shade`.
+    pub fn with_shade(mut self, red: f32, green: f32, blue: f32) -> Self {
+        self.shade = (red, green, blue);
+        self
+    }
+
+    pub fn with_position(mut self, x: f32, y: f32) -> Self {
+        self.transform.position = Vec2::new(x, y);
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.transform.rotation = rotation;
+        self
+    }
+
+    pub fn with_scale(mut self, x: f32, y: f32) -> Self {
+        self.transform.scale = Vec2::new(x, y);
+        self
+    }
+
+    pub fn with_pivot(mut self, x: f32, y: f32) -> Self {
+        self.transform.pivot = Vec2::new(x, y);
+        self
+    }
+
+    pub fn with_child(mut self, child: Node) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// Returns the index of the new child.
+    pub fn add_child(&mut self, child: Node) -> usize {
+        self.children.push(child);
+        self.children.len() - 1
+    }
+
+    pub fn remove_child(&mut self, index: usize) -> Node {
+        self.children.remove(index)
+    }
+
+    pub fn children(&self) -> &[Node] {
+        &self.children
+    }
+
+    pub fn children_mut(&mut self) -> &mut [Node] {
+        &mut self.children
+    }
+
+    /// Depth-first search through the subtree, including this node.
+    pub fn find(&self, name: &str) -> Option<&Node> {
+        if self.name.as_deref() == Some(name) {
+            return Some(self);
+        }
+
+        self.children.iter().find_map(|child| child.find(name))
+    }
+
+    pub fn find_mut(&mut self, name: &str) -> Option<&mut Node> {
+        if self.name.as_deref() == Some(name) {
+            return Some(self);
+        }
+
+        self.children
+            .iter_mut()
+            .find_map(|child| child.find_mut(name))
+    }
+
+    /// Transformation from the node's local space into the space of the root node.
+    pub fn world_matrix(&self, parent: Mat4) -> Mat4 {
+        parent * self.transform.matrix()
+    }
+
+    pub fn render(&self, canvas: &mut Canvas, context: &mut Context) {
+        self.render_with_parent(canvas, context, Mat4::identity(), (1.0, 1.0, 1.0));
+    }
+
+    fn render_with_parent(
+        &self,
+        canvas: &mut Canvas,
+        context: &mut Context,
+        parent: Mat4,
+        parent_tint: Color,
+    ) {
+        if !self.visible {
+            return;
+        }
+
+        let matrix = self.world_matrix(parent);
+
+        let tint = (
+            parent_tint.0 * self.tint.0,
+            parent_tint.1 * self.tint.1,
+            parent_tint.2 * self.tint.2,
+        );
+
+        if let Some(sprite) = &self.sprite {
+            let builder = match &sprite.filename {
+                Some(filename) => context.render(filename),
+                None => context.render_blank(),
+            };
+
+            builder
+                .transform(matrix)
+                .scale(sprite.size.x, sprite.size.y)
+                .shade_tup(self.shade)
+                .tint_tup(tint)
+                .commit(canvas);
+        }
+
+        for child in &self.children {
+            child.render_with_parent(canvas, context, matrix, tint);
+        }
+    }
+}
+
+impl Default for Node {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn assert_close(a: Vec2, b: Vec2) {
+        assert!((a - b).length() < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn shade_before_the_sprite() {
+        let node = Node::new()
+            .with_shade(0.5, 0.25, 1.0)
+            .with_blank_sprite(1.0, 1.0);
+
+        assert_eq!(node.shade, (0.5, 0.25, 1.0));
+    }
+
+    #[test]
+    fn pivot_rotation_and_scale() {
+        // Rotates a quarter turn around (1, 0), then moves to (10, 0).
+        let node = Node::new()
+            .with_position(10.0, 0.0)
+            .with_rotation(FRAC_PI_2)
+            .with_scale(2.0, 2.0)
+            .with_pivot(1.0, 0.0);
+
+        let matrix = node.world_matrix(Mat4::identity());
+        assert_close(
+            matrix.transform_point(Vec2::new(1.0, 0.0)),
+            Vec2::new(10.0, 0.0),
+        );
+        assert_close(
+            matrix.transform_point(Vec2::new(2.0, 0.0)),
+            Vec2::new(10.0, 2.0),
+        );
+    }
+
+    #[test]
+    fn transforms_compose_through_the_parents() {
+        let root = Node::new().with_position(5.0, 5.0).with_child(
+            Node::named("arm").with_rotation(FRAC_PI_2).with_child(
+                Node::named("hand")
+                    .with_position(2.0, 0.0)
+                    .with_scale(3.0, 1.0),
+            ),
+        );
+
+        let arm = root.find("arm").unwrap();
+        let hand = arm.find("hand").unwrap();
+
+        let matrix = hand.world_matrix(arm.world_matrix(root.world_matrix(Mat4::identity())));
+        assert_close(matrix.transform_point(Vec2::ZERO), Vec2::new(5.0, 7.0));
+        assert_close(
+            matrix.transform_point(Vec2::new(1.0, 0.0)),
+            Vec2::new(5.0, 10.0),
+        );
+    }
+}
@@ -0,0 +1,532 @@
+use std::collections::HashSet;
+
+use crate::collision::{Contact, Shape};
+use crate::spatial::SpatialHash;
+use crate::utils::Vec2;
+
+/// Fraction of the penetration corrected every step; less than 1.0 to avoid jitter.
+const POSITION_CORRECTION: f32 = 0.8;
+
+/// Penetration left uncorrected, relative to the size of the smaller body, so that resting bodies
+/// keep touching instead of falling onto each other every other step.
+const PENETRATION_SLOP: f32 = 0.01;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BodyType {
+    /// Never moves, e.g. the level geometry.
+    Static,
+    /// Moves with its velocity, but isn't affected by gravity or collisions, e.g. moving platforms.
+    Kinematic,
+    /// Fully simulated.
+    Dynamic,
+}
+
+/// A rigid body. Rotation isn't simulated: use an `Obb` or a `Polygon` for slanted shapes.
+pub struct Body {
+    pub body_type: BodyType,
+    /// The collision shape, relative to `position`.
+    pub shape: Shape,
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub mass: f32,
+    pub friction: f32,
+    /// Bounciness: 0.0 stops on impact, 1.0 bounces back with the same speed.
+    pub restitution: f32,
+    pub gravity_scale: f32,
+    force: Vec2,
+}
+
+impl Body {
+    pub fn new(body_type: BodyType, shape: Shape) -> Self {
+        Self {
+            body_type,
+            shape,
+            position: Vec2::ZERO,
+            velocity: Vec2::ZERO,
+            mass: 1.0,
+            friction: 0.3,
+            restitution: 0.0,
+            gravity_scale: 1.0,
+            force: Vec2::ZERO,
+        }
+    }
+
+    pub fn with_position(mut self, x: f32, y: f32) -> Self {
+        self.position = Vec2::new(x, y);
+        self
+    }
+
+    pub fn with_velocity(mut self, x: f32, y: f32) -> Self {
+        self.velocity = Vec2::new(x, y);
+        self
+    }
+
+    pub fn with_mass(mut self, mass: f32) -> Self {
+        self.mass = mass;
+        self
+    }
+
+    pub fn with_friction(mut self, friction: f32) -> Self {
+        self.friction = friction;
+        self
+    }
+
+    pub fn with_restitution(mut self, restitution: f32) -> Self {
+        self.restitution = restitution;
+        self
+    }
+
+    pub fn with_gravity_scale(mut self, gravity_scale: f32) -> Self {
+        self.gravity_scale = gravity_scale;
+        self
+    }
+
+    /// Change the velocity instantly, e.g. for jumps or explosions.
+    pub fn apply_impulse(&mut self, impulse: Vec2) {
+        self.velocity += impulse * self.inverse_mass();
+    }
+
+    /// Accelerate the body during the next step only.
+    pub fn apply_force(&mut self, force: Vec2) {
+        self.force += force;
+    }
+
+    /// The collision shape in world coordinates.
+    pub fn world_shape(&self) -> Shape {
+        self.shape.translated(self.position)
+    }
+
+    fn inverse_mass(&self) -> f32 {
+        match self.body_type {
+            BodyType::Dynamic if self.mass > 0.0 => 1.0 / self.mass,
+            _ => 0.0,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BodyHandle(usize);
+
+#[derive(Copy, Clone)]
+struct Manifold {
+    a: BodyHandle,
+    b: BodyHandle,
+    contact: Contact,
+    slop: f32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CollisionEventKind {
+    Started,
+    Ongoing,
+    Ended,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct CollisionEvent {
+    pub kind: CollisionEventKind,
+    pub a: BodyHandle,
+    pub b: BodyHandle,
+    /// Normal pointing from `a` towards `b`; `None` for ended collisions.
+    pub contact: Option<Contact>,
+}
+
+/// A simple impulse-based physics simulation. Call `step` from `Game::update` with
+/// `Context::delta`.
+pub struct World {
+    pub gravity: Vec2,
+    /// More iterations make stacks of bodies more stable at the cost of performance.
+    pub iterations: usize,
+    bodies: Vec<Option<Body>>,
+    touching: HashSet<(BodyHandle, BodyHandle)>,
+    events: Vec<CollisionEvent>,
+}
+
+impl World {
+    pub fn new(gravity: Vec2) -> Self {
+        Self {
+            gravity,
+            iterations: 4,
+            bodies: Vec::new(),
+            touching: HashSet::new(),
+            events: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, body: Body) -> BodyHandle {
+        self.bodies.push(Some(body));
+        BodyHandle(self.bodies.len() - 1)
+    }
+
+    pub fn remove(&mut self, handle: BodyHandle) -> Option<Body> {
+        let body = self.bodies.get_mut(handle.0)?.take()?;
+
+        let ended: Vec<_> = self
+            .touching
+            .iter()
+            .copied()
+            .filter(|&(a, b)| a == handle || b == handle)
+            .collect();
+
+        for (a, b) in ended {
+            self.touching.remove(&(a, b));
+
+            self.events.push(CollisionEvent {
+                kind: CollisionEventKind::Ended,
+                a,
+                b,
+                contact: None,
+            });
+        }
+
+        Some(body)
+    }
+
+    pub fn get(&self, handle: BodyHandle) -> Option<&Body> {
+        self.bodies.get(handle.0)?.as_ref()
+    }
+
+    pub fn get_mut(&mut self, handle: BodyHandle) -> Option<&mut Body> {
+        self.bodies.get_mut(handle.0)?.as_mut()
+    }
+
+    pub fn bodies(&self) -> impl Iterator<Item = (BodyHandle, &Body)> {
+        self.bodies
+            .iter()
+            .enumerate()
+            .filter_map(|(index, body)| Some((BodyHandle(index), body.as_ref()?)))
+    }
+
+    /// Collision events produced by `step` since the last call to this function. Call it every
+    /// update if you care about events, otherwise they pile up.
+    pub fn drain_events(&mut self) -> Vec<CollisionEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    pub fn step(&mut self, delta: f32) {
+        let gravity = self.gravity;
+
+        for body in self.bodies.iter_mut().flatten() {
+            if body.body_type == BodyType::Dynamic {
+                let acceleration = gravity * body.gravity_scale + body.force * body.inverse_mass();
+                body.velocity += acceleration * delta;
+            }
+
+            body.force = Vec2::ZERO;
+        }
+
+        let contacts = self.find_contacts();
+
+        // Impacts slower than what gravity accumulates over a couple of steps don't bounce, so that
+        // resting bodies stay at rest.
+        let bounce_speed = gravity.length() * delta * 2.0;
+
+        for _ in 0..self.iterations {
+            for &manifold in &contacts {
+                self.resolve_velocity(manifold, bounce_speed);
+            }
+        }
+
+        for &manifold in &contacts {
+            self.correct_position(manifold);
+        }
+
+        for body in self.bodies.iter_mut().flatten() {
+            if body.body_type != BodyType::Static {
+                body.position += body.velocity * delta;
+            }
+        }
+
+        self.update_events(&contacts);
+    }
+
+    fn find_contacts(&self) -> Vec<Manifold> {
+        let shapes: Vec<_> = self
+            .bodies()
+            .map(|(handle, body)| (handle, body.world_shape()))
+            .collect();
+
+        if shapes.is_empty() {
+            return Vec::new();
+        }
+
+        // Cells about the size of an average body.
+        let average_size = shapes
+            .iter()
+            .map(|(_, shape)| {
+                let bounds = shape.bounds();
+                bounds.w.max(bounds.h)
+            })
+            .sum::<f32>()
+            / shapes.len() as f32;
+
+        let mut hash = SpatialHash::new(if average_size > 0.0 {
+            average_size
+        } else {
+            1.0
+        });
+
+        for (index, (_, shape)) in shapes.iter().enumerate() {
+            hash.insert(index, shape.bounds());
+        }
+
+        let mut contacts = Vec::new();
+
+        for (i, j) in hash.pairs() {
+            let (i, j) = (i.min(j), i.max(j));
+            let (handle_a, shape_a) = &shapes[i];
+            let (handle_b, shape_b) = &shapes[j];
+
+            let dynamic = |handle: BodyHandle| {
+                self.get(handle).map(|body| body.body_type) == Some(BodyType::Dynamic)
+            };
+
+            if !dynamic(*handle_a) && !dynamic(*handle_b) {
+                continue;
+            }
+
+            if let Some(contact) = shape_a.contact(shape_b) {
+                let (bounds_a, bounds_b) = (shape_a.bounds(), shape_b.bounds());
+                let size = bounds_a.w.min(bounds_a.h).min(bounds_b.w.min(bounds_b.h));
+
+                contacts.push(Manifold {
+                    a: *handle_a,
+                    b: *handle_b,
+                    contact,
+                    slop: size * PENETRATION_SLOP,
+                });
+            }
+        }
+
+        contacts
+    }
+
+    fn pair_mut(&mut self, a: BodyHandle, b: BodyHandle) -> (&mut Body, &mut Body) {
+        let (first, second) = self.bodies.split_at_mut(b.0);
+        (first[a.0].as_mut().unwrap(), second[0].as_mut().unwrap())
+    }
+
+    fn resolve_velocity(&mut self, manifold: Manifold, bounce_speed: f32) {
+        let contact = manifold.contact;
+        let (body_a, body_b) = self.pair_mut(manifold.a, manifold.b);
+
+        let inverse_a = body_a.inverse_mass();
+        let inverse_b = body_b.inverse_mass();
+        let inverse_sum = inverse_a + inverse_b;
+
+        // Neither body can be moved by impulses.
+        if inverse_sum == 0.0 {
+            return;
+        }
+
+        let relative = body_b.velocity - body_a.velocity;
+        let normal_speed = relative.dot(contact.normal);
+
+        if normal_speed > 0.0 {
+            return;
+        }
+
+        let restitution = if -normal_speed > bounce_speed {
+            body_a.restitution.max(body_b.restitution)
+        } else {
+            0.0
+        };
+        let impulse = -(1.0 + restitution) * normal_speed / inverse_sum;
+
+        body_a.velocity -= contact.normal * impulse * inverse_a;
+        body_b.velocity += contact.normal * impulse * inverse_b;
+
+        // Coulomb friction along the contact surface.
+        let relative = body_b.velocity - body_a.velocity;
+        let tangent = (relative - contact.normal * relative.dot(contact.normal)).normalize();
+        let friction = (body_a.friction * body_b.friction).sqrt();
+        let max_friction = impulse * friction;
+        let friction_impulse =
+            (-relative.dot(tangent) / inverse_sum).clamp(-max_friction, max_friction);
+
+        body_a.velocity -= tangent * friction_impulse * inverse_a;
+        body_b.velocity += tangent * friction_impulse * inverse_b;
+    }
+
+    fn correct_position(&mut self, manifold: Manifold) {
+        let contact = manifold.contact;
+        let (body_a, body_b) = self.pair_mut(manifold.a, manifold.b);
+
+        let inverse_a = body_a.inverse_mass();
+        let inverse_b = body_b.inverse_mass();
+        let inverse_sum = inverse_a + inverse_b;
+
+        if inverse_sum == 0.0 {
+            return;
+        }
+
+        let depth = (contact.depth - manifold.slop).max(0.0);
+        let correction = contact.normal * (depth * POSITION_CORRECTION / inverse_sum);
+
+        body_a.position -= correction * inverse_a;
+        body_b.position += correction * inverse_b;
+    }
+
+    fn update_events(&mut self, contacts: &[Manifold]) {
+        let mut touching = HashSet::new();
+
+        for &Manifold { a, b, contact, .. } in contacts {
+            touching.insert((a, b));
+
+            let kind = if self.touching.contains(&(a, b)) {
+                CollisionEventKind::Ongoing
+            } else {
+                CollisionEventKind::Started
+            };
+
+            self.events.push(CollisionEvent {
+                kind,
+                a,
+                b,
+                contact: Some(contact),
+            });
+        }
+
+        for &(a, b) in self.touching.difference(&touching) {
+            self.events.push(CollisionEvent {
+                kind: CollisionEventKind::Ended,
+                a,
+                b,
+                contact: None,
+            });
+        }
+
+        self.touching = touching;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::Rectangle;
+
+    const DELTA: f32 = 1.0 / 60.0;
+
+    /// A 1 by 1 box centered on its position.
+    fn unit_box(body_type: BodyType) -> Body {
+        Body::new(body_type, Rectangle::new(-0.5, -0.5, 1.0, 1.0).into())
+    }
+
+    /// A wide static floor with its top at y = 0.
+    fn floor() -> Body {
+        Body::new(
+            BodyType::Static,
+            Rectangle::new(-10.0, -1.0, 20.0, 1.0).into(),
+        )
+    }
+
+    fn kinds(world: &mut World) -> Vec<CollisionEventKind> {
+        world
+            .drain_events()
+            .iter()
+            .map(|event| event.kind)
+            .collect()
+    }
+
+    #[test]
+    fn resting_body_stays_put() {
+        let mut world = World::new(Vec2::new(0.0, -10.0));
+        world.add(floor());
+        let body = world.add(unit_box(BodyType::Dynamic).with_position(0.0, 0.5));
+
+        for _ in 0..240 {
+            world.step(DELTA);
+        }
+
+        let body = world.get(body).unwrap();
+        assert!((body.position.y - 0.5).abs() < 0.02, "{:?}", body.position);
+        assert!(body.velocity.length() < 0.2, "{:?}", body.velocity);
+    }
+
+    #[test]
+    fn restitution_bounces() {
+        let mut world = World::new(Vec2::ZERO);
+        world.add(floor());
+        let ball = world.add(
+            unit_box(BodyType::Dynamic)
+                .with_position(0.0, 0.6)
+                .with_velocity(0.0, -5.0)
+                .with_restitution(1.0),
+        );
+        let lump = world.add(
+            unit_box(BodyType::Dynamic)
+                .with_position(5.0, 0.6)
+                .with_velocity(0.0, -5.0),
+        );
+
+        for _ in 0..5 {
+            world.step(DELTA);
+        }
+
+        assert!((world.get(ball).unwrap().velocity.y - 5.0).abs() < 1e-3);
+        assert!(world.get(lump).unwrap().velocity.y.abs() < 1e-3);
+    }
+
+    #[test]
+    fn static_and_kinematic_pairs_are_skipped() {
+        let mut world = World::new(Vec2::new(0.0, -10.0));
+        world.add(floor());
+        let platform = world.add(
+            unit_box(BodyType::Kinematic)
+                .with_position(0.0, 0.0)
+                .with_velocity(1.0, 0.0),
+        );
+
+        world.step(1.0);
+
+        // Gravity and the overlap with the floor don't affect it.
+        let platform = world.get(platform).unwrap();
+        assert_eq!(platform.position, Vec2::new(1.0, 0.0));
+        assert_eq!(platform.velocity, Vec2::new(1.0, 0.0));
+        assert!(world.drain_events().is_empty());
+    }
+
+    #[test]
+    fn events_start_go_on_and_end() {
+        let mut world = World::new(Vec2::new(0.0, -10.0));
+        let floor = world.add(floor());
+        let body = world.add(unit_box(BodyType::Dynamic).with_position(0.0, 0.49));
+
+        world.step(DELTA);
+        let events = world.drain_events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, CollisionEventKind::Started);
+        assert_eq!((events[0].a, events[0].b), (floor, body));
+        assert_eq!(events[0].contact.unwrap().normal, Vec2::new(0.0, 1.0));
+
+        world.step(DELTA);
+        assert_eq!(kinds(&mut world), [CollisionEventKind::Ongoing]);
+
+        world.get_mut(body).unwrap().position.y = 5.0;
+        world.step(DELTA);
+        assert_eq!(kinds(&mut world), [CollisionEventKind::Ended]);
+
+        world.step(DELTA);
+        assert!(world.drain_events().is_empty());
+    }
+
+    #[test]
+    fn removing_a_body_ends_its_collisions() {
+        let mut world = World::new(Vec2::new(0.0, -10.0));
+        world.add(floor());
+        let body = world.add(unit_box(BodyType::Dynamic).with_position(0.0, 0.49));
+
+        world.step(DELTA);
+        assert_eq!(kinds(&mut world), [CollisionEventKind::Started]);
+
+        assert!(world.remove(body).is_some());
+        let events = world.drain_events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, CollisionEventKind::Ended);
+        assert!(events[0].contact.is_none());
+
+        world.step(DELTA);
+        assert!(world.drain_events().is_empty());
+        assert!(world.remove(body).is_none());
+    }
+}
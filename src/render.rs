@@ -1,39 +1,209 @@
-use glium::index::PrimitiveType;
+use glium::index::NoIndices;
 
-use glium::texture::{
-    CompressedMipmapsOption, CompressedSrgbFormat, CompressedSrgbTexture2d, RawImage2d,
-};
+use glium::texture::{RawImage2d, SrgbTexture2d, TextureCreationError};
 
-use glium::{Display, DrawParameters, Frame, Program, Rect, Surface};
+use image::RgbaImage;
+
+use glium::uniforms::Uniforms;
+
+use glium::framebuffer::SimpleFrameBuffer;
+
+use glium::{Display, DrawError, DrawParameters, Frame, Program, Rect, Surface};
 
 use std::cmp;
 
+use crate::assets::{AssetError, AssetErrorKind};
+use crate::error::Error;
 use crate::utils::{Color, Mat4, Vec2};
+use crate::vfs;
+
+pub use glium::index::PrimitiveType;
 
 #[derive(Copy, Clone)]
-struct Vertex {
-    position: (f32, f32),
-    tex_coords: (f32, f32),
+pub struct Vertex {
+    pub position: (f32, f32),
+    pub tex_coords: (f32, f32),
+    pub color: Color,
 }
 
-implement_vertex!(Vertex, position, tex_coords);
+implement_vertex!(Vertex, position, tex_coords, color);
 
 impl Vertex {
-    fn new(x: f32, y: f32, tx: f32, ty: f32) -> Self {
+    pub fn new(x: f32, y: f32, tx: f32, ty: f32) -> Self {
         Self {
             position: (x, y),
             tex_coords: (tx, ty),
+            color: (1.0, 1.0, 1.0),
         }
     }
 }
 
 type VertexBuffer = glium::VertexBuffer<Vertex>;
-type IndexBuffer = glium::IndexBuffer<u16>;
+type IndexBuffer = glium::IndexBuffer<u32>;
+
+fn load_texture(display: &Display, texture: Option<&str>) -> Result<SrgbTexture2d, Error> {
+    let filename = match texture {
+        Some(filename) => filename,
+        None => return Ok(blank_texture(display)?),
+    };
+
+    let error = |kind| AssetError {
+        path: filename.to_string(),
+        kind,
+    };
+
+    let bytes = vfs::read(filename).map_err(|io| error(AssetErrorKind::Io(io)))?;
+    let image = image::load_from_memory(&bytes)
+        .map_err(|decode| error(AssetErrorKind::Decode(decode.to_string())))?
+        .to_rgba8();
+
+    Ok(upload_image(display, image)?)
+}
+
+fn upload_image(
+    display: &Display,
+    image: RgbaImage,
+) -> Result<SrgbTexture2d, TextureCreationError> {
+    let dimensions = image.dimensions();
+    let image = RawImage2d::from_raw_rgba_reversed(&image.into_raw(), dimensions);
+
+    SrgbTexture2d::new(display, image)
+}
+
+fn blank_texture(display: &Display) -> Result<SrgbTexture2d, TextureCreationError> {
+    SrgbTexture2d::empty(display, 1, 1)
+}
+
+/// An image on the GPU, along with the square model drawing it as a sprite. Usually loaded
+/// through `Assets`.
+pub struct Texture {
+    model: Model,
+}
+
+impl Texture {
+    pub fn from_image(display: &Display, image: RgbaImage) -> Result<Self, Error> {
+        let texture = upload_image(display, image)?;
+
+        Ok(Self {
+            model: MeshBuilder::square().build_with_texture(display, texture)?,
+        })
+    }
+
+    /// From an encoded image in memory, e.g. a PNG built in with `include_bytes!`.
+    pub fn from_bytes(display: &Display, bytes: &[u8]) -> Result<Self, Error> {
+        let image = image::load_from_memory(bytes)?;
+        Self::from_image(display, image.to_rgba8())
+    }
+
+    /// From raw RGBA pixels, four bytes each, row by row from the top left. Like blank textures,
+    /// 1x1 ones are drawn in the shade color only.
+    pub fn from_rgba(
+        display: &Display,
+        width: u32,
+        height: u32,
+        pixels: &[u8],
+    ) -> Result<Self, Error> {
+        Self::from_image(display, rgba_image(width, height, pixels)?)
+    }
+
+    /// Replace every pixel, e.g. of a generated map. Takes the same layout as `from_rgba`.
+    pub fn write(&mut self, pixels: &[u8]) -> Result<(), Error> {
+        let (width, height) = self.size();
+        self.write_region(0, 0, width, height, pixels)
+    }
+
+    /// Replace the pixels of a part of the texture, with `x` and `y` from the top left corner.
+    /// Cheaper than `write` for small changes, like revealing fog of war around a unit.
+    pub fn write_region(
+        &mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        pixels: &[u8],
+    ) -> Result<(), Error> {
+        let rect = texture_region(self.size(), x, y, width, height)?;
+        check_pixels(width, height, pixels)?;
+
+        let image = RawImage2d::from_raw_rgba_reversed(pixels, (width, height));
+        self.model.texture.write(rect, image);
+        Ok(())
+    }
+
+    /// An untextured texture, drawn in the shade color only.
+    pub fn blank(display: &Display) -> Result<Self, Error> {
+        Ok(Self {
+            model: MeshBuilder::square().build_with_texture(display, blank_texture(display)?)?,
+        })
+    }
+
+    /// In texels.
+    pub fn size(&self) -> (u32, u32) {
+        self.model.texture_size()
+    }
+
+    pub fn model(&self) -> &Model {
+        &self.model
+    }
+}
+
+/// Raw RGBA pixels as an image, laid out as in `Texture::from_rgba`.
+pub(crate) fn rgba_image(width: u32, height: u32, pixels: &[u8]) -> Result<RgbaImage, Error> {
+    check_pixels(width, height, pixels)?;
+
+    // Can't fail once the size is checked.
+    Ok(RgbaImage::from_raw(width, height, pixels.to_vec()).unwrap())
+}
+
+/// Fails unless `pixels` holds `width` by `height` RGBA pixels.
+fn check_pixels(width: u32, height: u32, pixels: &[u8]) -> Result<(), Error> {
+    let expected = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|count| count.checked_mul(4));
+
+    if expected == Some(pixels.len()) {
+        Ok(())
+    } else {
+        Err(Error::PixelBufferSize {
+            width,
+            height,
+            length: pixels.len(),
+        })
+    }
+}
+
+/// Where a region with `x` and `y` from the top left corner lies in a texture of the given size,
+/// which is stored bottom row first.
+fn texture_region(
+    (texture_width, texture_height): (u32, u32),
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+) -> Result<Rect, Error> {
+    match (x.checked_add(width), y.checked_add(height)) {
+        (Some(right), Some(bottom)) if right <= texture_width && bottom <= texture_height => {
+            Ok(Rect {
+                left: x,
+                bottom: texture_height - bottom,
+                width,
+                height,
+            })
+        }
+        _ => Err(Error::OutsideTexture {
+            x,
+            y,
+            width,
+            height,
+        }),
+    }
+}
 
 pub struct Model {
     vertex_buffer: VertexBuffer,
-    index_buffer: IndexBuffer,
-    texture: CompressedSrgbTexture2d,
+    index_buffer: Option<IndexBuffer>,
+    primitive: PrimitiveType,
+    texture: SrgbTexture2d,
 }
 
 impl Model {
@@ -42,52 +212,230 @@ impl Model {
         vertices: &[(f32, f32, f32, f32)],
         indices: &[u16],
         texture: Option<&str>,
-    ) -> Self {
-        let vertices: Vec<_> = vertices
-            .iter()
-            .map(|points| Vertex::new(points.0, points.1, points.2, points.3))
-            .collect();
+    ) -> Result<Self, Error> {
+        let mut builder = MeshBuilder::new(PrimitiveType::TriangleStrip);
 
+        for points in vertices {
+            builder = builder
+                .vertex(points.0, points.1)
+                .tex_coords(points.2, points.3);
+        }
+
+        for &index in indices {
+            builder = builder.index(index.into());
+        }
+
+        if let Some(filename) = texture {
+            builder = builder.texture(filename);
+        }
+
+        builder.build(display)
+    }
+
+    /// Dimensions of the texture in texels; `(1, 1)` for untextured models.
+    pub fn texture_size(&self) -> (u32, u32) {
+        self.texture.dimensions()
+    }
+
+    /// Mostly for internal use; `Context::get_sprite` manages these for you.
+    pub fn square(display: &Display, texture: Option<&str>) -> Result<Self, Error> {
+        let mut builder = MeshBuilder::square();
+
+        if let Some(filename) = texture {
+            builder = builder.texture(filename);
+        }
+
+        builder.build(display)
+    }
+
+    /// Replace the geometry of this model with the one described by `builder`.
+    ///
+    /// Buffers of models built with `MeshBuilder::dynamic` are written in place as long as the
+    /// vertex and index counts stay the same; otherwise they are reallocated. The texture and the
+    /// primitive type of the builder are ignored: both stay as they were.
+    pub fn update(&mut self, display: &Display, builder: &MeshBuilder) -> Result<(), Error> {
+        if self.vertex_buffer.len() == builder.vertices.len() {
+            self.vertex_buffer.write(&builder.vertices);
+        } else {
+            self.vertex_buffer = builder.vertex_buffer(display)?;
+        }
+
+        match (&mut self.index_buffer, &builder.indices) {
+            (Some(index_buffer), Some(indices)) if index_buffer.len() == indices.len() => {
+                index_buffer.write(indices);
+            }
+            _ => self.index_buffer = builder.index_buffer(display, self.primitive)?,
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn draw<S: Surface, U: Uniforms>(
+        &self,
+        surface: &mut S,
+        program: &Program,
+        uniforms: &U,
+        parameters: &DrawParameters,
+    ) -> Result<(), DrawError> {
+        match &self.index_buffer {
+            Some(index_buffer) => surface.draw(
+                &self.vertex_buffer,
+                index_buffer,
+                program,
+                uniforms,
+                parameters,
+            ),
+            None => surface.draw(
+                &self.vertex_buffer,
+                NoIndices(self.primitive),
+                program,
+                uniforms,
+                parameters,
+            ),
+        }
+    }
+}
+
+/// Describes arbitrary geometry for a `Model`.
+///
+/// Vertices are pushed one by one; `tex_coords` and `color` modify the last pushed vertex. If no
+/// indices are given, the vertices are drawn in the order they were pushed.
+pub struct MeshBuilder<'a> {
+    primitive: PrimitiveType,
+    vertices: Vec<Vertex>,
+    indices: Option<Vec<u32>>,
+    texture: Option<&'a str>,
+    dynamic: bool,
+}
+
+impl<'a> MeshBuilder<'a> {
+    pub fn new(primitive: PrimitiveType) -> Self {
         Self {
-            vertex_buffer: VertexBuffer::new(display, &vertices).unwrap(),
-            index_buffer: IndexBuffer::new(display, PrimitiveType::TriangleStrip, indices).unwrap(),
-            texture: match texture {
-                Some(filename) => {
-                    let image = image::open(&filename)
-                        .expect(format!("Couldn't load image {}", filename).as_str())
-                        .to_rgba();
+            primitive,
+            vertices: Vec::new(),
+            indices: None,
+            texture: None,
+            dynamic: false,
+        }
+    }
+
+    /// A unit square centered on the origin, like sprites.
+    pub fn square() -> Self {
+        Self::new(PrimitiveType::TriangleStrip)
+            .vertex(-0.5, -0.5)
+            .tex_coords(0.0, 0.0)
+            .vertex(0.5, -0.5)
+            .tex_coords(1.0, 0.0)
+            .vertex(0.5, 0.5)
+            .tex_coords(1.0, 1.0)
+            .vertex(-0.5, 0.5)
+            .tex_coords(0.0, 1.0)
+            .indices(&[0, 1, 3, 2])
+    }
 
-                    let dimensions = image.dimensions();
+    pub fn vertex(mut self, x: f32, y: f32) -> Self {
+        self.vertices.push(Vertex::new(x, y, 0.0, 0.0));
+        self
+    }
 
-                    let image = RawImage2d::from_raw_rgba_reversed(&image.into_raw(), dimensions);
+    pub fn vertex_tup(self, (x, y): (f32, f32)) -> Self {
+        self.vertex(x, y)
+    }
 
-                    CompressedSrgbTexture2d::new(display, image).unwrap()
-                }
-                None => CompressedSrgbTexture2d::empty_with_format(
-                    display,
-                    CompressedSrgbFormat::S3tcDxt1Alpha,
-                    CompressedMipmapsOption::NoMipmap,
-                    1,
-                    1,
-                )
-                .unwrap(),
-            },
+    pub fn tex_coords(mut self, u: f32, v: f32) -> Self {
+        if let Some(vertex) = self.vertices.last_mut() {
+            vertex.tex_coords = (u, v);
         }
+
+        self
     }
 
-    /// Mostly for internal use; `Context::get_sprite` manages these for you.
-    pub fn square(display: &Display, texture: Option<&str>) -> Self {
-        Self::new(
-            display,
-            &[
-                (-0.5, -0.5, 0.0, 0.0),
-                (0.5, -0.5, 1.0, 0.0),
-                (0.5, 0.5, 1.0, 1.0),
-                (-0.5, 0.5, 0.0, 1.0),
-            ],
-            &[0, 1, 3, 2],
+    pub fn tex_coords_tup(self, (u, v): (f32, f32)) -> Self {
+        self.tex_coords(u, v)
+    }
+
+    /// Vertex colors multiply the color a model is drawn in. Untextured models are drawn in the
+    /// shade of `ModelRenderBuilder`, so `shade(1.0, 1.0, 1.0)` shows them as is; textured ones
+    /// mix the texture half and half with the shade first.
+    pub fn color(mut self, red: f32, green: f32, blue: f32) -> Self {
+        if let Some(vertex) = self.vertices.last_mut() {
+            vertex.color = (red, green, blue);
+        }
+
+        self
+    }
+
+    pub fn color_tup(self, (red, green, blue): (f32, f32, f32)) -> Self {
+        self.color(red, green, blue)
+    }
+
+    pub fn index(mut self, index: u32) -> Self {
+        self.indices.get_or_insert_with(Vec::new).push(index);
+        self
+    }
+
+    pub fn indices(mut self, indices: &[u32]) -> Self {
+        self.indices
+            .get_or_insert_with(Vec::new)
+            .extend_from_slice(indices);
+        self
+    }
+
+    pub fn texture(mut self, filename: &'a str) -> Self {
+        self.texture = Some(filename);
+        self
+    }
+
+    /// Use buffers optimized for frequent updates through `Model::update`.
+    pub fn dynamic(mut self) -> Self {
+        self.dynamic = true;
+        self
+    }
+
+    pub fn build(&self, display: &Display) -> Result<Model, Error> {
+        self.build_with_texture(display, load_texture(display, self.texture)?)
+    }
+
+    fn build_with_texture(
+        &self,
+        display: &Display,
+        texture: SrgbTexture2d,
+    ) -> Result<Model, Error> {
+        Ok(Model {
+            vertex_buffer: self.vertex_buffer(display)?,
+            index_buffer: self.index_buffer(display, self.primitive)?,
+            primitive: self.primitive,
             texture,
-        )
+        })
+    }
+
+    fn vertex_buffer(&self, display: &Display) -> Result<VertexBuffer, Error> {
+        let buffer = if self.dynamic {
+            VertexBuffer::dynamic(display, &self.vertices)
+        } else {
+            VertexBuffer::new(display, &self.vertices)
+        };
+
+        Ok(buffer?)
+    }
+
+    fn index_buffer(
+        &self,
+        display: &Display,
+        primitive: PrimitiveType,
+    ) -> Result<Option<IndexBuffer>, Error> {
+        let indices = match &self.indices {
+            Some(indices) => indices,
+            None => return Ok(None),
+        };
+
+        let buffer = if self.dynamic {
+            IndexBuffer::dynamic(display, primitive, indices)
+        } else {
+            IndexBuffer::new(display, primitive, indices)
+        };
+
+        Ok(Some(buffer?))
     }
 }
 
@@ -111,9 +459,7 @@ impl Viewport {
         }
     }
 
-    fn get_dimensions(&self, frame: &Frame) -> Rect {
-        let screen_size = frame.get_dimensions();
-
+    fn get_dimensions(&self, screen_size: (u32, u32)) -> Rect {
         let smallest_side = cmp::min(screen_size.0, screen_size.1) as f32;
 
         let screen_size = (screen_size.0 as f32, screen_size.1 as f32);
@@ -168,23 +514,35 @@ pub struct ModelRenderBuilder<'a> {
     position: Vec2,
     scale: Vec2,
     rotation: f32,
+    skew: Vec2,
+    pivot: Vec2,
+    flip: (bool, bool),
     color: Color,
+    tint: Color,
+    uv_rect: (f32, f32, f32, f32),
+    parent: Mat4,
 }
 
 impl<'a> ModelRenderBuilder<'a> {
     pub fn new(model: &'a Model) -> Self {
         Self {
             model,
-            position: (0.0, 0.0),
-            scale: (1.0, 1.0),
+            position: Vec2::ZERO,
+            scale: Vec2::ONE,
             rotation: 0.0,
+            skew: Vec2::ZERO,
+            pivot: Vec2::new(0.5, 0.5),
+            flip: (false, false),
             color: (0.0, 0.0, 0.0),
+            tint: (1.0, 1.0, 1.0),
+            uv_rect: (0.0, 0.0, 1.0, 1.0),
+            parent: Mat4::identity(),
         }
     }
 
     pub fn translate(mut self, dx: f32, dy: f32) -> Self {
-        self.position.0 += dx;
-        self.position.1 += dy;
+        self.position.x += dx;
+        self.position.y += dy;
         self
     }
 
@@ -193,8 +551,8 @@ impl<'a> ModelRenderBuilder<'a> {
     }
 
     pub fn scale(mut self, x_mult: f32, y_mult: f32) -> Self {
-        self.scale.0 *= x_mult;
-        self.scale.1 *= y_mult;
+        self.scale.x *= x_mult;
+        self.scale.y *= y_mult;
         self
     }
 
@@ -207,6 +565,40 @@ impl<'a> ModelRenderBuilder<'a> {
         self
     }
 
+    pub fn skew(mut self, x_rad: f32, y_rad: f32) -> Self {
+        self.skew.x += x_rad;
+        self.skew.y += y_rad;
+        self
+    }
+
+    pub fn skew_tup(self, (x_rad, y_rad): (f32, f32)) -> Self {
+        self.skew(x_rad, y_rad)
+    }
+
+    /// The point of the unit square that is placed at the position and that rotation, skewing and
+    /// scaling happen around. `(0.0, 0.0)` is the bottom left corner, `(0.5, 0.5)` (the default)
+    /// the center, and e.g. `(0.5, 0.0)` the bottom center, handy for characters.
+    pub fn pivot(mut self, x: f32, y: f32) -> Self {
+        self.pivot = Vec2::new(x, y);
+        self
+    }
+
+    pub fn pivot_tup(self, (x, y): (f32, f32)) -> Self {
+        self.pivot(x, y)
+    }
+
+    /// Mirror the texture horizontally. Doesn't affect the geometry or the pivot.
+    pub fn flip_x(mut self) -> Self {
+        self.flip.0 = !self.flip.0;
+        self
+    }
+
+    /// Mirror the texture vertically. Doesn't affect the geometry or the pivot.
+    pub fn flip_y(mut self) -> Self {
+        self.flip.1 = !self.flip.1;
+        self
+    }
+
     pub fn shade(mut self, red: f32, green: f32, blue: f32) -> Self {
         self.color = (red, green, blue);
         self
@@ -216,63 +608,407 @@ impl<'a> ModelRenderBuilder<'a> {
         self.shade(red, green, blue)
     }
 
+    /// Multiply the final color, after shading, by the given one.
+    pub fn tint(mut self, red: f32, green: f32, blue: f32) -> Self {
+        self.tint.0 *= red;
+        self.tint.1 *= green;
+        self.tint.2 *= blue;
+        self
+    }
+
+    pub fn tint_tup(self, (red, green, blue): (f32, f32, f32)) -> Self {
+        self.tint(red, green, blue)
+    }
+
+    /// Apply a parent transformation on top of everything else, e.g. one of a `Node`.
+    pub fn transform(mut self, matrix: Mat4) -> Self {
+        self.parent = matrix * self.parent;
+        self
+    }
+
+    /// Map the model's texture coordinates into the given part of the texture, e.g. a frame of a
+    /// sprite sheet. Coordinates are normalized, with the origin at the bottom left corner.
+    pub fn uv_rect(mut self, u: f32, v: f32, width: f32, height: f32) -> Self {
+        self.uv_rect = (u, v, width, height);
+        self
+    }
+
+    pub fn uv_rect_tup(self, (u, v, width, height): (f32, f32, f32, f32)) -> Self {
+        self.uv_rect(u, v, width, height)
+    }
+
     pub fn commit(self, canvas: &mut Canvas) {
         canvas.render_model_from_builder(self);
     }
 
     fn get_model_matrix(&self) -> Mat4 {
-        let translation = Mat4([
-            [1.0, 0.0, 0.0, self.position.0],
-            [0.0, 1.0, 0.0, self.position.1],
-            [0.0, 0.0, 1.0, 0.0],
-            [0.0, 0.0, 0.0, 1.0],
-        ]);
+        self.parent
+            * Mat4::translation(self.position.x, self.position.y)
+            * Mat4::rotation(self.rotation)
+            * Mat4::skew(self.skew.x, self.skew.y)
+            * Mat4::scale(self.scale.x, self.scale.y)
+            * Mat4::translation(0.5 - self.pivot.x, 0.5 - self.pivot.y)
+    }
+
+    fn get_uv_rect(&self) -> (f32, f32, f32, f32) {
+        flip_uv_rect(self.uv_rect, self.flip)
+    }
+}
+
+/// Mirror a UV rect by starting it from its other side, with a negative size.
+fn flip_uv_rect(uv_rect: Area, (flip_x, flip_y): (bool, bool)) -> Area {
+    let (mut u, mut v, mut width, mut height) = uv_rect;
+
+    if flip_x {
+        u += width;
+        width = -width;
+    }
+
+    if flip_y {
+        v += height;
+        height = -height;
+    }
+
+    (u, v, width, height)
+}
+
+/// A rectangle as (x, y, width, height), in world units or UVs.
+type Area = (f32, f32, f32, f32);
+
+pub enum SliceFill {
+    Stretch,
+    Tile,
+}
+
+/// Renders a texture as a nine-slice (9-patch): the corners keep their size, while the edges and
+/// the center stretch or tile to fill the rest of the panel.
+///
+/// Insets and regions are in texels, with the origin at the top left corner of the texture as in
+/// image editors. Each texel takes `texel_size` world units, 1.0 by default, which matches a canvas
+/// sized to the window in pixels.
+pub struct NineSliceBuilder<'a> {
+    model: &'a Model,
+    position: Vec2,
+    size: Vec2,
+    region: Option<(f32, f32, f32, f32)>,
+    insets: (f32, f32, f32, f32),
+    texel_size: f32,
+    fill: SliceFill,
+    color: Color,
+}
+
+impl<'a> NineSliceBuilder<'a> {
+    pub fn new(model: &'a Model) -> Self {
+        Self {
+            model,
+            position: Vec2::ZERO,
+            size: Vec2::ONE,
+            region: None,
+            insets: (0.0, 0.0, 0.0, 0.0),
+            texel_size: 1.0,
+            fill: SliceFill::Stretch,
+            color: (0.0, 0.0, 0.0),
+        }
+    }
+
+    pub fn translate(mut self, dx: f32, dy: f32) -> Self {
+        self.position.x += dx;
+        self.position.y += dy;
+        self
+    }
+
+    pub fn translate_tup(self, (dx, dy): (f32, f32)) -> Self {
+        self.translate(dx, dy)
+    }
+
+    /// Size of the whole panel in world units.
+    pub fn size(mut self, width: f32, height: f32) -> Self {
+        self.size = Vec2::new(width, height);
+        self
+    }
+
+    pub fn size_tup(self, (width, height): (f32, f32)) -> Self {
+        self.size(width, height)
+    }
+
+    /// Use only a part of the texture, e.g. a frame inside a texture atlas.
+    pub fn region(mut self, x: f32, y: f32, width: f32, height: f32) -> Self {
+        self.region = Some((x, y, width, height));
+        self
+    }
+
+    pub fn insets(mut self, left: f32, right: f32, top: f32, bottom: f32) -> Self {
+        self.insets = (left, right, top, bottom);
+        self
+    }
+
+    pub fn texel_size(mut self, texel_size: f32) -> Self {
+        self.texel_size = texel_size;
+        self
+    }
+
+    pub fn stretch(mut self) -> Self {
+        self.fill = SliceFill::Stretch;
+        self
+    }
 
-        let rotation = Mat4([
-            [self.rotation.cos(), -self.rotation.sin(), 0.0, 0.0],
-            [self.rotation.sin(), self.rotation.cos(), 0.0, 0.0],
-            [0.0, 0.0, 1.0, 0.0],
-            [0.0, 0.0, 0.0, 1.0],
-        ]);
+    pub fn tile(mut self) -> Self {
+        self.fill = SliceFill::Tile;
+        self
+    }
+
+    pub fn shade(mut self, red: f32, green: f32, blue: f32) -> Self {
+        self.color = (red, green, blue);
+        self
+    }
+
+    pub fn shade_tup(self, (red, green, blue): (f32, f32, f32)) -> Self {
+        self.shade(red, green, blue)
+    }
+
+    pub fn commit(self, canvas: &mut Canvas) {
+        let (texture_width, texture_height) = self.model.texture_size();
+        let (texture_width, texture_height) = (texture_width as f32, texture_height as f32);
+
+        let (region_x, region_y, region_width, region_height) =
+            self.region
+                .unwrap_or((0.0, 0.0, texture_width, texture_height));
+
+        let (left, right, top, bottom) = self.insets;
+
+        // Slices from left to right and from bottom to top, as (offset, size) in texels.
+        let columns = [
+            (0.0, left),
+            (left, region_width - left - right),
+            (region_width - right, right),
+        ];
+
+        let rows = [
+            (region_height - bottom, bottom),
+            (top, region_height - top - bottom),
+            (0.0, top),
+        ];
+
+        let column_sizes =
+            Self::fit_slices(self.size.x, left * self.texel_size, right * self.texel_size);
+
+        let row_sizes =
+            Self::fit_slices(self.size.y, bottom * self.texel_size, top * self.texel_size);
+
+        let mut y = self.position.y - self.size.y / 2.0;
+
+        for (row, &(texel_y, texel_height)) in rows.iter().enumerate() {
+            let mut x = self.position.x - self.size.x / 2.0;
+
+            for (column, &(texel_x, texel_width)) in columns.iter().enumerate() {
+                let source = (
+                    (region_x + texel_x) / texture_width,
+                    1.0 - (region_y + texel_y + texel_height) / texture_height,
+                    texel_width / texture_width,
+                    texel_height / texture_height,
+                );
+
+                let target = (x, y, column_sizes[column], row_sizes[row]);
+
+                let tile_size = match self.fill {
+                    SliceFill::Tile => (
+                        if column == 1 {
+                            texel_width * self.texel_size
+                        } else {
+                            target.2
+                        },
+                        if row == 1 {
+                            texel_height * self.texel_size
+                        } else {
+                            target.3
+                        },
+                    ),
+                    SliceFill::Stretch => (target.2, target.3),
+                };
+
+                self.render_tiled(canvas, source, target, tile_size);
+
+                x += column_sizes[column];
+            }
+
+            y += row_sizes[row];
+        }
+    }
+
+    /// Distribute `total` between two fixed borders and a flexible middle, shrinking the borders
+    /// proportionally if they don't fit.
+    fn fit_slices(total: f32, first: f32, last: f32) -> [f32; 3] {
+        let borders = first + last;
+
+        if borders > total && borders > 0.0 {
+            let ratio = total / borders;
+            [first * ratio, 0.0, last * ratio]
+        } else {
+            [first, total - borders, last]
+        }
+    }
+
+    fn render_tiled(&self, canvas: &mut Canvas, source: Area, target: Area, tile_size: (f32, f32)) {
+        for ((x, y, width, height), (u, v, uv_width, uv_height)) in
+            Self::tiles(source, target, tile_size)
+        {
+            ModelRenderBuilder::new(self.model)
+                .translate(x + width / 2.0, y + height / 2.0)
+                .scale(width, height)
+                .uv_rect(u, v, uv_width, uv_height)
+                .shade_tup(self.color)
+                .commit(canvas);
+        }
+    }
+
+    /// Cover `target` with tiles of `source` from its bottom left corner, as (target, UV rect)
+    /// pairs. The last tiles of a row or column are cropped instead of squished, keeping the part
+    /// of the slice next to the tile before them.
+    fn tiles(source: Area, target: Area, tile_size: (f32, f32)) -> Vec<(Area, Area)> {
+        let (x, y, width, height) = target;
+        let mut tiles = Vec::new();
+
+        if width <= 0.0 || height <= 0.0 || tile_size.0 <= 0.0 || tile_size.1 <= 0.0 {
+            return tiles;
+        }
+
+        let mut tile_y = 0.0;
+
+        while tile_y < height {
+            let tile_height = tile_size.1.min(height - tile_y);
+            let mut tile_x = 0.0;
+
+            while tile_x < width {
+                let tile_width = tile_size.0.min(width - tile_x);
+
+                // UVs grow up and to the right like the tiles, so both crop from the origin side.
+                let uv_rect = (
+                    source.0,
+                    source.1,
+                    source.2 * tile_width / tile_size.0,
+                    source.3 * tile_height / tile_size.1,
+                );
+
+                tiles.push(((x + tile_x, y + tile_y, tile_width, tile_height), uv_rect));
+                tile_x += tile_size.0;
+            }
+
+            tile_y += tile_size.1;
+        }
 
-        let scale = Mat4([
-            [self.scale.0, 0.0, 0.0, 0.0],
-            [0.0, self.scale.1, 0.0, 0.0],
-            [0.0, 0.0, 1.0, 0.0],
-            [0.0, 0.0, 0.0, 1.0],
-        ]);
+        tiles
+    }
+}
 
-        translation * rotation * scale
+/// Convert window pixels into world coordinates, given the transformation from the world into clip
+/// space and where the viewport sits in a window `screen_height` pixels high.
+fn screen_to_world(world_to_clip: Mat4, viewport: Rect, screen_height: u32, point: Vec2) -> Vec2 {
+    let clip = Vec2::new(
+        (point.x - viewport.left as f32) / viewport.width as f32 * 2.0 - 1.0,
+        (screen_height as f32 - point.y - viewport.bottom as f32) / viewport.height as f32 * 2.0
+            - 1.0,
+    );
+
+    match world_to_clip.inverse() {
+        Some(inverse) => inverse.transform_point(clip),
+        None => Vec2::ZERO,
     }
 }
 
+/// The opposite of `screen_to_world`.
+fn world_to_screen(world_to_clip: Mat4, viewport: Rect, screen_height: u32, point: Vec2) -> Vec2 {
+    let clip = world_to_clip.transform_point(point);
+
+    Vec2::new(
+        viewport.left as f32 + (clip.x + 1.0) / 2.0 * viewport.width as f32,
+        screen_height as f32
+            - viewport.bottom as f32
+            - (clip.y + 1.0) / 2.0 * viewport.height as f32,
+    )
+}
+
 enum CoordinatesOrigin {
     Center,
     BottomLeft,
 }
 
+/// Where a `Canvas` draws: the window, or a texture for effects like scene transitions.
+enum Target<'a> {
+    Frame(Frame),
+    Texture(SimpleFrameBuffer<'a>),
+}
+
 pub struct Canvas<'a> {
-    frame: Frame,
+    target: Target<'a>,
     program: &'a Program,
     viewport: Viewport,
     camera: Camera,
     origin: CoordinatesOrigin,
+    error: Option<Error>,
 }
 
 impl<'a> Canvas<'a> {
     pub fn new(frame: Frame, program: &'a Program) -> Self {
+        Self::with_target(Target::Frame(frame), program)
+    }
+
+    /// A canvas drawing into a texture instead of the window.
+    pub(crate) fn offscreen(framebuffer: SimpleFrameBuffer<'a>, program: &'a Program) -> Self {
+        Self::with_target(Target::Texture(framebuffer), program)
+    }
+
+    fn with_target(target: Target<'a>, program: &'a Program) -> Self {
         Self {
             viewport: Viewport::new(),
             camera: Camera::new(),
             origin: CoordinatesOrigin::Center,
-            frame,
+            target,
             program,
+            error: None,
         }
     }
 
-    pub fn finish(self) {
-        // TODO: handle errors.
-        self.frame.finish().unwrap();
+    /// Show the frame. Fails if it couldn't be shown, or if something couldn't be drawn.
+    pub fn finish(self) -> Result<(), Error> {
+        if let Target::Frame(frame) = self.target {
+            frame.finish()?;
+        }
+
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn program(&self) -> &'a Program {
+        self.program
+    }
+
+    /// Size of the window, or of the texture, in pixels.
+    pub(crate) fn dimensions(&self) -> (u32, u32) {
+        match &self.target {
+            Target::Frame(frame) => frame.get_dimensions(),
+            Target::Texture(framebuffer) => framebuffer.get_dimensions(),
+        }
+    }
+
+    /// Draw a model ignoring the viewport, camera and origin settings.
+    pub(crate) fn draw_model<U: Uniforms>(
+        &mut self,
+        model: &Model,
+        program: &Program,
+        uniforms: &U,
+        parameters: &DrawParameters,
+    ) {
+        let result = match &mut self.target {
+            Target::Frame(frame) => model.draw(frame, program, uniforms, parameters),
+            Target::Texture(framebuffer) => model.draw(framebuffer, program, uniforms, parameters),
+        };
+
+        // Keep drawing the rest of the frame, and report the first error from `finish`.
+        if let Err(error) = result {
+            self.error.get_or_insert(Error::Draw(error));
+        }
     }
 
     pub fn size(&mut self, width: f32, height: f32) {
@@ -293,6 +1029,11 @@ impl<'a> Canvas<'a> {
         self.camera.y = y;
     }
 
+    /// Where the camera looks at, in world coordinates.
+    pub fn camera_position(&self) -> Vec2 {
+        Vec2::new(self.camera.x, self.camera.y)
+    }
+
     pub fn center(&mut self) {
         self.origin = CoordinatesOrigin::Center;
     }
@@ -301,69 +1042,87 @@ impl<'a> Canvas<'a> {
         self.origin = CoordinatesOrigin::BottomLeft;
     }
 
-    pub fn render_model_from_builder(&mut self, renderer: ModelRenderBuilder) {
-        let mut parameters = DrawParameters::default();
-        parameters.viewport = Some(self.viewport.get_dimensions(&self.frame));
-
+    /// Transformation from world coordinates into clip space.
+    pub fn view_projection(&self) -> Mat4 {
         let projection = {
-            let scale = Mat4([
-                [1.0 / self.viewport.width, 0.0, 0.0, 0.0],
-                [0.0, 1.0 / self.viewport.height, 0.0, 0.0],
-                [0.0, 0.0, 1.0, 0.0],
-                [0.0, 0.0, 0.0, 1.0],
-            ]);
+            let scale = Mat4::scale(1.0 / self.viewport.width, 1.0 / self.viewport.height);
 
             let adjustment = match self.origin {
                 CoordinatesOrigin::Center => Mat4::identity(),
-                CoordinatesOrigin::BottomLeft => Mat4([
-                    [2.0, 0.0, 0.0, 0.0],
-                    [0.0, 2.0, 0.0, 0.0],
-                    [0.0, 0.0, 1.0, 0.0],
-                    [0.0, 0.0, 0.0, 1.0],
-                ]),
+                CoordinatesOrigin::BottomLeft => Mat4::scale(2.0, 2.0),
             };
 
             adjustment * scale
         };
 
-        let view = Mat4([
-            [1.0, 0.0, 0.0, -self.camera.x],
-            [0.0, 1.0, 0.0, -self.camera.y],
-            [0.0, 0.0, 1.0, 0.0],
-            [0.0, 0.0, 0.0, 1.0],
-        ]);
+        let view = Mat4::translation(-self.camera.x, -self.camera.y);
+
+        projection * view
+    }
+
+    /// Convert a point in window pixels, with the origin at the top left corner, into world
+    /// coordinates.
+    pub fn screen_to_world(&self, point: Vec2) -> Vec2 {
+        let viewport = self.viewport.get_dimensions(self.dimensions());
+        let (_, screen_height) = self.dimensions();
+
+        screen_to_world(self.world_to_clip(), viewport, screen_height, point)
+    }
+
+    /// The opposite of `screen_to_world`.
+    pub fn world_to_screen(&self, point: Vec2) -> Vec2 {
+        let viewport = self.viewport.get_dimensions(self.dimensions());
+        let (_, screen_height) = self.dimensions();
 
-        let adjustment = match self.origin {
+        world_to_screen(self.world_to_clip(), viewport, screen_height, point)
+    }
+
+    /// Shift applied to models on top of the view projection.
+    fn origin_adjustment(&self) -> Mat4 {
+        match self.origin {
             CoordinatesOrigin::Center => Mat4::identity(),
-            CoordinatesOrigin::BottomLeft => Mat4([
-                [1.0, 0.0, 0.0, 1.0],
-                [0.0, 1.0, 0.0, 1.0],
-                [0.0, 0.0, 1.0, 0.0],
-                [0.0, 0.0, 0.0, 1.0],
-            ]),
-        };
+            CoordinatesOrigin::BottomLeft => Mat4::translation(1.0, 1.0),
+        }
+    }
 
-        let model = renderer.get_model_matrix();
+    /// Where untransformed models end up in clip space, origin shift included.
+    fn world_to_clip(&self) -> Mat4 {
+        self.view_projection() * self.origin_adjustment()
+    }
+
+    pub fn render_model_from_builder(&mut self, renderer: ModelRenderBuilder<'_>) {
+        let parameters = DrawParameters {
+            viewport: Some(self.viewport.get_dimensions(self.dimensions())),
+            ..Default::default()
+        };
 
-        let mvp = projection * view * model * adjustment;
+        let adjustment = self.origin_adjustment();
 
-        self.frame
-            .draw(
-                &renderer.model.vertex_buffer,
-                &renderer.model.index_buffer,
-                &self.program,
-                &uniform! {
-                    mvp: mvp,
-                    color: renderer.color,
-                    tex: &renderer.model.texture,
-                },
-                &parameters,
-            )
-            .unwrap();
+        let model = renderer.get_model_matrix();
+        let uv_rect = renderer.get_uv_rect();
+
+        let mvp = self.view_projection() * model * adjustment;
+
+        self.draw_model(
+            renderer.model,
+            self.program,
+            &uniform! {
+                mvp: mvp,
+                shade: renderer.color,
+                tint: renderer.tint,
+                uv_offset: (uv_rect.0, uv_rect.1),
+                uv_size: (uv_rect.2, uv_rect.3),
+                tex: &renderer.model.texture,
+            },
+            &parameters,
+        );
     }
 
     pub fn clear(&mut self, r: f32, g: f32, b: f32) {
-        self.frame.clear_color(r, g, b, 1.0);
+        match &mut self.target {
+            Target::Frame(frame) => frame.clear_color(r, g, b, 1.0),
+            Target::Texture(framebuffer) => framebuffer.clear_color(r, g, b, 1.0),
+        }
     }
 }
 
@@ -371,18 +1130,24 @@ pub const VERTEX_SHADER: &str = "
 #version 140
 
 uniform mat4 mvp;
-uniform vec3 color;
+uniform vec3 shade;
+uniform vec3 tint;
+uniform vec2 uv_offset;
+uniform vec2 uv_size;
 
 in vec2 position;
 in vec2 tex_coords;
+in vec3 color;
 
+out vec3 v_shade;
 out vec3 v_color;
 out vec2 v_tex_coords;
 
 void main() {
     gl_Position = vec4(position, 0.0, 1.0) * mvp;
-    v_color = color;
-    v_tex_coords = tex_coords;
+    v_shade = shade;
+    v_color = color * tint;
+    v_tex_coords = uv_offset + tex_coords * uv_size;
 }
 ";
 
@@ -391,6 +1156,7 @@ pub const FRAGMENT_SHADER: &str = "
 
 uniform sampler2D tex;
 
+in vec3 v_shade;
 in vec3 v_color;
 in vec2 v_tex_coords;
 
@@ -399,10 +1165,187 @@ out vec4 f_color;
 void main() {
     // Use solid color instead of dummy 1x1 texture.
     if (textureSize(tex, 0) == vec2(1, 1)) {
-        f_color = vec4(v_color, 1.0);
+        f_color = vec4(v_shade, 1.0);
     } else {
         vec4 t_color = texture(tex, v_tex_coords);
-        f_color = mix(t_color, vec4(v_color, 1.0), 0.5);
+        f_color = mix(t_color, vec4(v_shade, 1.0), 0.5);
     }
+
+    f_color *= vec4(v_color, 1.0);
 }
 ";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mesh_attributes_go_to_the_last_vertex() {
+        let builder = MeshBuilder::new(PrimitiveType::TrianglesList)
+            .tex_coords(0.5, 0.5)
+            .color(0.5, 0.5, 0.5)
+            .vertex(1.0, 2.0)
+            .tex_coords(0.25, 0.75)
+            .vertex_tup((3.0, 4.0))
+            .color_tup((1.0, 0.0, 0.0));
+
+        let vertices: Vec<_> = builder
+            .vertices
+            .iter()
+            .map(|vertex| (vertex.position, vertex.tex_coords, vertex.color))
+            .collect();
+
+        assert_eq!(
+            vertices,
+            [
+                ((1.0, 2.0), (0.25, 0.75), (1.0, 1.0, 1.0)),
+                ((3.0, 4.0), (0.0, 0.0), (1.0, 0.0, 0.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn mesh_indices_add_up() {
+        assert!(MeshBuilder::new(PrimitiveType::Points).indices.is_none());
+
+        let builder = MeshBuilder::new(PrimitiveType::TrianglesList)
+            .indices(&[0, 1])
+            .index(2)
+            .indices(&[2, 3, 0]);
+
+        assert_eq!(builder.indices.unwrap(), [0, 1, 2, 2, 3, 0]);
+    }
+
+    #[test]
+    fn nine_slice_borders_shrink_to_fit() {
+        assert_eq!(
+            NineSliceBuilder::fit_slices(10.0, 2.0, 3.0),
+            [2.0, 5.0, 3.0]
+        );
+        assert_eq!(NineSliceBuilder::fit_slices(2.5, 2.0, 3.0), [1.0, 0.0, 1.5]);
+        assert_eq!(NineSliceBuilder::fit_slices(0.0, 0.0, 0.0), [0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn nine_slice_tiles_crop_from_the_origin() {
+        let source = (0.25, 0.5, 0.5, 0.25);
+        let tiles = NineSliceBuilder::tiles(source, (1.0, 2.0, 5.0, 3.0), (2.0, 2.0));
+
+        // Three columns and two rows, from the bottom left.
+        assert_eq!(tiles.len(), 6);
+        assert_eq!(tiles[0], ((1.0, 2.0, 2.0, 2.0), source));
+
+        // The right column is half a tile wide and keeps the left half of the slice.
+        assert_eq!(tiles[2], ((5.0, 2.0, 1.0, 2.0), (0.25, 0.5, 0.25, 0.25)));
+
+        // The top row is half a tile high and keeps the bottom half of the slice.
+        assert_eq!(tiles[3], ((1.0, 4.0, 2.0, 1.0), (0.25, 0.5, 0.5, 0.125)));
+        assert_eq!(tiles[5], ((5.0, 4.0, 1.0, 1.0), (0.25, 0.5, 0.25, 0.125)));
+
+        assert!(NineSliceBuilder::tiles(source, (0.0, 0.0, 0.0, 3.0), (2.0, 2.0)).is_empty());
+    }
+
+    #[test]
+    fn flipped_uv_rects_start_from_the_other_side() {
+        let uv_rect = (0.25, 0.5, 0.5, 0.25);
+
+        assert_eq!(flip_uv_rect(uv_rect, (false, false)), uv_rect);
+        assert_eq!(
+            flip_uv_rect(uv_rect, (true, false)),
+            (0.75, 0.5, -0.5, 0.25)
+        );
+        assert_eq!(
+            flip_uv_rect(uv_rect, (false, true)),
+            (0.25, 0.75, 0.5, -0.25)
+        );
+        assert_eq!(
+            flip_uv_rect(uv_rect, (true, true)),
+            (0.75, 0.75, -0.5, -0.25)
+        );
+    }
+
+    /// A 100 by 100 viewport 20 pixels from the left of a 140 by 100 window.
+    const VIEWPORT: Rect = Rect {
+        left: 20,
+        bottom: 0,
+        width: 100,
+        height: 100,
+    };
+
+    fn assert_close(a: Vec2, b: Vec2) {
+        assert!((a - b).length() < 1e-3, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn screen_and_world_round_trip() {
+        let view_projection = Mat4::scale(0.2, 0.2) * Mat4::translation(-3.0, 1.0);
+        let bottom_left = Mat4::scale(2.0, 2.0) * view_projection * Mat4::translation(1.0, 1.0);
+
+        for world_to_clip in [view_projection, bottom_left].iter() {
+            let point = Vec2::new(2.5, -1.0);
+            let screen = world_to_screen(*world_to_clip, VIEWPORT, 100, point);
+
+            assert_close(
+                screen_to_world(*world_to_clip, VIEWPORT, 100, screen),
+                point,
+            );
+        }
+    }
+
+    #[test]
+    fn world_to_screen_follows_the_viewport() {
+        let world_to_clip = Mat4::scale(0.2, 0.2);
+
+        assert_close(
+            world_to_screen(world_to_clip, VIEWPORT, 100, Vec2::ZERO),
+            Vec2::new(70.0, 50.0),
+        );
+        assert_close(
+            world_to_screen(world_to_clip, VIEWPORT, 100, Vec2::new(5.0, 5.0)),
+            Vec2::new(120.0, 0.0),
+        );
+    }
+
+    #[test]
+    fn bottom_left_origin_shifts_the_world() {
+        // A 10 by 10 bottom-left canvas, as `Canvas::world_to_clip` builds it.
+        let world_to_clip = Mat4::scale(0.2, 0.2) * Mat4::translation(1.0, 1.0);
+
+        assert_close(
+            world_to_screen(world_to_clip, VIEWPORT, 100, Vec2::new(-1.0, -1.0)),
+            Vec2::new(70.0, 50.0),
+        );
+        assert_close(
+            screen_to_world(world_to_clip, VIEWPORT, 100, Vec2::new(120.0, 0.0)),
+            Vec2::new(4.0, 4.0),
+        );
+    }
+
+    #[test]
+    fn pixel_buffers_must_match_the_size() {
+        assert!(check_pixels(2, 3, &[0; 24]).is_ok());
+        assert!(check_pixels(0, 0, &[]).is_ok());
+        assert!(matches!(
+            check_pixels(2, 3, &[0; 23]),
+            Err(Error::PixelBufferSize { length: 23, .. })
+        ));
+        assert!(check_pixels(u32::MAX, u32::MAX, &[0; 4]).is_err());
+    }
+
+    #[test]
+    fn regions_are_flipped_and_bounds_checked() {
+        let rect = texture_region((64, 32), 4, 2, 8, 10).unwrap();
+        assert_eq!(
+            (rect.left, rect.bottom, rect.width, rect.height),
+            (4, 20, 8, 10)
+        );
+
+        let whole = texture_region((64, 32), 0, 0, 64, 32).unwrap();
+        assert_eq!((whole.left, whole.bottom), (0, 0));
+
+        assert!(texture_region((64, 32), 60, 0, 8, 1).is_err());
+        assert!(texture_region((64, 32), 0, 30, 1, 8).is_err());
+        assert!(texture_region((64, 32), u32::MAX, 0, 2, 1).is_err());
+        assert!(texture_region((64, 32), 0, 1, 1, u32::MAX).is_err());
+    }
+}
@@ -0,0 +1,613 @@
+use crate::error::Error;
+use crate::game::{Context, Game, GameConfig};
+use crate::render::Canvas;
+use crate::transition::{Compositor, Effect};
+
+type Configure = Box<dyn Fn(&mut GameConfig)>;
+type SceneChange = Change<Box<dyn Scene>>;
+
+/// What the scene stack should do after a scene's update.
+pub enum Transition {
+    None,
+    /// Put a scene on top of the current one, e.g. a pause menu.
+    Push(Box<dyn Scene>),
+    /// Remove the current scene, going back to the one below.
+    Pop,
+    /// Swap the current scene for another one, e.g. from the title screen to the gameplay.
+    Replace(Box<dyn Scene>),
+    /// Remove every scene and start over with another one, e.g. from the pause menu back to the
+    /// title screen.
+    Reset(Box<dyn Scene>),
+    /// Any of the above, played with a screen transition; see `with_effect`.
+    Animated {
+        transition: Box<Transition>,
+        effect: Effect,
+        duration: f32,
+    },
+}
+
+impl Transition {
+    pub fn push<S: Scene + 'static>(scene: S) -> Self {
+        Transition::Push(Box::new(scene))
+    }
+
+    pub fn replace<S: Scene + 'static>(scene: S) -> Self {
+        Transition::Replace(Box::new(scene))
+    }
+
+    pub fn reset<S: Scene + 'static>(scene: S) -> Self {
+        Transition::Reset(Box::new(scene))
+    }
+
+    /// Go from the outgoing scenes to the incoming ones with an effect lasting `duration` seconds.
+    /// No scene is updated until it's over.
+    pub fn with_effect(self, effect: Effect, duration: f32) -> Self {
+        Transition::Animated {
+            transition: Box::new(self),
+            effect,
+            duration,
+        }
+    }
+}
+
+/// A screen of a game, like the title screen or the gameplay, managed by a `SceneStack`.
+pub trait Scene {
+    /// Called once, when the scene is added to the stack.
+    fn init(&mut self, _context: &mut Context) {}
+    /// Called whenever the scene becomes the top one: when added, and when the scene above it goes
+    /// away.
+    fn on_enter(&mut self, _context: &mut Context) {}
+    /// Called whenever the scene stops being the top one: when removed, and when another scene is
+    /// pushed above it.
+    fn on_exit(&mut self, _context: &mut Context) {}
+    /// Only the top scene is updated.
+    fn update(&mut self, _context: &mut Context) -> Transition {
+        Transition::None
+    }
+    fn render(&mut self, _canvas: &mut Canvas, _context: &mut Context) {}
+    /// The window's new size, in pixels. Every scene gets it, not only the top one, since the
+    /// ones below overlays and the ones leaving during an effect are still rendered.
+    fn resized(&mut self, _context: &mut Context, _width: u32, _height: u32) {}
+    /// Every scene gets it, like `resized`.
+    fn focus_changed(&mut self, _context: &mut Context, _focused: bool) {}
+    /// Every scene gets it, like `resized`; see `Game::context_restored`.
+    fn context_restored(&mut self, _context: &mut Context) {}
+    /// Only the top scene gets it; see `Game::error`.
+    fn error(&mut self, _context: &mut Context, error: Error) -> Result<(), Error> {
+        eprintln!("{}", error);
+        Ok(())
+    }
+    /// Overlays let the scenes below them be rendered first, paused since they don't get updated.
+    /// They shouldn't clear the canvas.
+    fn is_overlay(&self) -> bool {
+        false
+    }
+}
+
+impl Transition {
+    /// The change to the stack, and the effect to play it with. Nested effects are flattened, the
+    /// outermost one winning.
+    fn into_parts(self) -> (SceneChange, Option<(Effect, f32)>) {
+        let change = match self {
+            Transition::None => Change::None,
+            Transition::Push(scene) => Change::Push(scene),
+            Transition::Pop => Change::Pop,
+            Transition::Replace(scene) => Change::Replace(scene),
+            Transition::Reset(scene) => Change::Reset(scene),
+            Transition::Animated {
+                transition,
+                effect,
+                duration,
+            } => return (transition.into_parts().0, Some((effect, duration))),
+        };
+
+        (change, None)
+    }
+}
+
+/// A transition without its effect.
+enum Change<S> {
+    None,
+    Push(S),
+    Pop,
+    Replace(S),
+    Reset(S),
+}
+
+/// The scene hooks called by the stack.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Hook {
+    Init,
+    Enter,
+    Exit,
+}
+
+/// Calls the hooks of the scenes with `context`.
+fn with_context(context: &mut Context) -> impl FnMut(&mut Box<dyn Scene>, Hook) + '_ {
+    move |scene, hook| match hook {
+        Hook::Init => scene.init(context),
+        Hook::Enter => scene.on_enter(context),
+        Hook::Exit => scene.on_exit(context),
+    }
+}
+
+/// The bookkeeping of `SceneStack`, calling the hooks through `hook` so that it works without a
+/// `Context`.
+struct Stack<S> {
+    scenes: Vec<S>,
+}
+
+impl<S> Stack<S> {
+    fn push(&mut self, mut scene: S, hook: &mut impl FnMut(&mut S, Hook)) {
+        if let Some(top) = self.scenes.last_mut() {
+            hook(top, Hook::Exit);
+        }
+
+        hook(&mut scene, Hook::Init);
+        hook(&mut scene, Hook::Enter);
+        self.scenes.push(scene);
+    }
+
+    fn pop(&mut self, hook: &mut impl FnMut(&mut S, Hook)) -> Option<S> {
+        let mut scene = self.scenes.pop()?;
+        hook(&mut scene, Hook::Exit);
+
+        if let Some(top) = self.scenes.last_mut() {
+            hook(top, Hook::Enter);
+        }
+
+        Some(scene)
+    }
+
+    fn replace(&mut self, mut scene: S, hook: &mut impl FnMut(&mut S, Hook)) -> Option<S> {
+        let mut previous = self.scenes.pop();
+
+        if let Some(previous) = &mut previous {
+            hook(previous, Hook::Exit);
+        }
+
+        hook(&mut scene, Hook::Init);
+        hook(&mut scene, Hook::Enter);
+        self.scenes.push(scene);
+
+        previous
+    }
+
+    fn reset(&mut self, mut scene: S, hook: &mut impl FnMut(&mut S, Hook)) -> Vec<S> {
+        if let Some(top) = self.scenes.last_mut() {
+            hook(top, Hook::Exit);
+        }
+
+        let previous = std::mem::take(&mut self.scenes);
+
+        hook(&mut scene, Hook::Init);
+        hook(&mut scene, Hook::Enter);
+        self.scenes.push(scene);
+
+        previous
+    }
+
+    /// Returns how many scenes at the bottom of the stack were left alone, and the removed ones:
+    /// together they make up the stack as it was.
+    fn apply(&mut self, change: Change<S>, hook: &mut impl FnMut(&mut S, Hook)) -> (usize, Vec<S>) {
+        let len = self.scenes.len();
+
+        match change {
+            Change::None => (len, Vec::new()),
+            Change::Push(scene) => {
+                self.push(scene, hook);
+                (len, Vec::new())
+            }
+            Change::Pop => {
+                let removed = self.pop(hook);
+                (len.saturating_sub(1), removed.into_iter().collect())
+            }
+            Change::Replace(scene) => {
+                let removed = self.replace(scene, hook);
+                (len.saturating_sub(1), removed.into_iter().collect())
+            }
+            Change::Reset(scene) => (0, self.reset(scene, hook)),
+        }
+    }
+}
+
+struct Animation {
+    effect: Effect,
+    duration: f32,
+    elapsed: f32,
+    /// The bottom scenes of the stack that are part of both the outgoing and incoming ones.
+    common: usize,
+    /// Scenes removed from the top of the stack, still rendered until the animation is over.
+    leaving: Vec<Box<dyn Scene>>,
+}
+
+/// A `Game` made of a stack of scenes. Pass it to `run_game`.
+pub struct SceneStack {
+    stack: Stack<Box<dyn Scene>>,
+    pending: Option<Box<dyn Scene>>,
+    configure: Option<Configure>,
+    animation: Option<Animation>,
+    compositor: Option<Compositor>,
+}
+
+impl SceneStack {
+    /// The initial scene is added when the game starts.
+    pub fn new<S: Scene + 'static>(initial: S) -> Self {
+        Self {
+            stack: Stack { scenes: Vec::new() },
+            pending: Some(Box::new(initial)),
+            configure: None,
+            animation: None,
+            compositor: None,
+        }
+    }
+
+    /// Stands in for `Game::configure`.
+    pub fn with_config<F: Fn(&mut GameConfig) + 'static>(mut self, configure: F) -> Self {
+        self.configure = Some(Box::new(configure));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.stack.scenes.len()
+    }
+
+    /// Once the last scene is popped, nothing is updated nor rendered anymore.
+    pub fn is_empty(&self) -> bool {
+        self.stack.scenes.is_empty()
+    }
+
+    /// Whether a transition effect is playing.
+    pub fn is_animating(&self) -> bool {
+        self.animation.is_some()
+    }
+
+    pub fn push(&mut self, scene: Box<dyn Scene>, context: &mut Context) {
+        self.stack.push(scene, &mut with_context(context));
+    }
+
+    pub fn pop(&mut self, context: &mut Context) -> Option<Box<dyn Scene>> {
+        self.stack.pop(&mut with_context(context))
+    }
+
+    /// Unlike a pop followed by a push, the scene below isn't entered in between.
+    pub fn replace(
+        &mut self,
+        scene: Box<dyn Scene>,
+        context: &mut Context,
+    ) -> Option<Box<dyn Scene>> {
+        self.stack.replace(scene, &mut with_context(context))
+    }
+
+    /// Remove every scene, then push the given one. Only the top scene gets exited, since the
+    /// others already were when covered. Returns the removed scenes, bottom first.
+    pub fn reset(&mut self, scene: Box<dyn Scene>, context: &mut Context) -> Vec<Box<dyn Scene>> {
+        self.stack.reset(scene, &mut with_context(context))
+    }
+
+    fn apply(&mut self, transition: Transition, context: &mut Context) {
+        let (change, effect) = transition.into_parts();
+        let (common, leaving) = self.stack.apply(change, &mut with_context(context));
+
+        if let Some((effect, duration)) = effect {
+            self.animation = Some(Animation {
+                effect,
+                duration,
+                elapsed: 0.0,
+                common,
+                leaving,
+            });
+        }
+    }
+
+    /// Every scene still rendered, bottom first: the stack, then the ones leaving it during an
+    /// effect.
+    fn scenes_mut(&mut self) -> impl Iterator<Item = &mut Box<dyn Scene>> {
+        let leaving = self
+            .animation
+            .iter_mut()
+            .flat_map(|animation| animation.leaving.iter_mut());
+
+        self.stack.scenes.iter_mut().chain(leaving)
+    }
+
+    /// Render the outgoing and incoming scenes into textures, then blend them on the canvas.
+    fn render_transition(
+        &mut self,
+        canvas: &mut Canvas,
+        context: &mut Context,
+    ) -> Result<(), Error> {
+        let animation = self.animation.as_mut().unwrap();
+
+        let size = canvas.dimensions();
+        if self.compositor.is_none() {
+            self.compositor = Some(Compositor::new(context.display(), size)?);
+        }
+
+        let compositor = self.compositor.as_mut().unwrap();
+        compositor.resize(context.display(), size)?;
+
+        let outgoing = self.stack.scenes[..animation.common]
+            .iter_mut()
+            .chain(animation.leaving.iter_mut())
+            .collect();
+
+        let mut from = compositor.outgoing_canvas(context.display(), canvas.program())?;
+        from.clear(0.0, 0.0, 0.0);
+        render_scenes(outgoing, &mut from, context);
+        from.finish()?;
+
+        let mut to = compositor.incoming_canvas(context.display(), canvas.program())?;
+        to.clear(0.0, 0.0, 0.0);
+        render_scenes(self.stack.scenes.iter_mut().collect(), &mut to, context);
+        to.finish()?;
+
+        let progress = if animation.duration > 0.0 {
+            animation.elapsed / animation.duration
+        } else {
+            1.0
+        };
+
+        compositor.composite(canvas, animation.effect, progress);
+        Ok(())
+    }
+}
+
+/// Render the topmost scene that isn't an overlay, then the ones above it.
+fn render_scenes(scenes: Vec<&mut Box<dyn Scene>>, canvas: &mut Canvas, context: &mut Context) {
+    let first = scenes
+        .iter()
+        .rposition(|scene| !scene.is_overlay())
+        .unwrap_or(0);
+
+    for scene in scenes.into_iter().skip(first) {
+        scene.render(canvas, context);
+    }
+}
+
+impl Game for SceneStack {
+    fn configure(&self, config: &mut GameConfig) {
+        if let Some(configure) = &self.configure {
+            configure(config);
+        }
+    }
+
+    fn init(&mut self, context: &mut Context) {
+        if let Some(scene) = self.pending.take() {
+            self.push(scene, context);
+        }
+    }
+
+    fn update(&mut self, context: &mut Context) {
+        if let Some(animation) = &mut self.animation {
+            animation.elapsed += context.delta;
+
+            if animation.elapsed >= animation.duration {
+                self.animation = None;
+            }
+
+            return;
+        }
+
+        if let Some(top) = self.stack.scenes.last_mut() {
+            let transition = top.update(context);
+            self.apply(transition, context);
+        }
+    }
+
+    fn render(&mut self, canvas: &mut Canvas, context: &mut Context) {
+        if self.animation.is_none() {
+            return render_scenes(self.stack.scenes.iter_mut().collect(), canvas, context);
+        }
+
+        // Skip the effect if it can't be drawn, rather than not drawing the scenes at all.
+        if let Err(error) = self.render_transition(canvas, context) {
+            self.animation = None;
+            context.report(error);
+            render_scenes(self.stack.scenes.iter_mut().collect(), canvas, context);
+        }
+    }
+
+    fn resized(&mut self, context: &mut Context, width: u32, height: u32) {
+        for scene in self.scenes_mut() {
+            scene.resized(context, width, height);
+        }
+    }
+
+    fn focus_changed(&mut self, context: &mut Context, focused: bool) {
+        for scene in self.scenes_mut() {
+            scene.focus_changed(context, focused);
+        }
+    }
+
+    fn error(&mut self, context: &mut Context, error: Error) -> Result<(), Error> {
+        match self.stack.scenes.last_mut() {
+            Some(top) => top.error(context, error),
+            None => {
+                eprintln!("{}", error);
+                Ok(())
+            }
+        }
+    }
+
+    fn context_restored(&mut self, context: &mut Context) {
+        // Its textures were lost along with the context.
+        self.compositor = None;
+
+        for scene in self.scenes_mut() {
+            scene.context_restored(context);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transition::WipeDirection;
+
+    /// A stack of named scenes, recording the hooks called.
+    #[derive(Default)]
+    struct Recorder {
+        stack: Stack<&'static str>,
+        calls: Vec<(&'static str, Hook)>,
+    }
+
+    impl Recorder {
+        fn apply(&mut self, change: Change<&'static str>) -> (usize, Vec<&'static str>) {
+            let calls = &mut self.calls;
+            self.stack
+                .apply(change, &mut |scene, hook| calls.push((*scene, hook)))
+        }
+
+        fn calls(&mut self) -> Vec<(&'static str, Hook)> {
+            std::mem::take(&mut self.calls)
+        }
+    }
+
+    impl<S> Default for Stack<S> {
+        fn default() -> Self {
+            Self { scenes: Vec::new() }
+        }
+    }
+
+    /// A stack of `title`, `game` and `pause`, without the calls that built it.
+    fn three_scenes() -> Recorder {
+        let mut recorder = Recorder::default();
+        recorder.apply(Change::Push("title"));
+        recorder.apply(Change::Push("game"));
+        recorder.apply(Change::Push("pause"));
+        recorder.calls();
+        recorder
+    }
+
+    #[test]
+    fn push_exits_the_top_then_enters_the_new_scene() {
+        let mut recorder = Recorder::default();
+
+        assert_eq!(recorder.apply(Change::Push("title")), (0, vec![]));
+        assert_eq!(
+            recorder.calls(),
+            [("title", Hook::Init), ("title", Hook::Enter)]
+        );
+
+        assert_eq!(recorder.apply(Change::Push("pause")), (1, vec![]));
+        assert_eq!(
+            recorder.calls(),
+            [
+                ("title", Hook::Exit),
+                ("pause", Hook::Init),
+                ("pause", Hook::Enter)
+            ]
+        );
+    }
+
+    #[test]
+    fn pop_enters_the_scene_below() {
+        let mut recorder = three_scenes();
+
+        assert_eq!(recorder.apply(Change::Pop), (2, vec!["pause"]));
+        assert_eq!(
+            recorder.calls(),
+            [("pause", Hook::Exit), ("game", Hook::Enter)]
+        );
+        assert_eq!(recorder.stack.scenes, ["title", "game"]);
+
+        recorder.apply(Change::Pop);
+        recorder.apply(Change::Pop);
+        recorder.calls();
+
+        assert_eq!(recorder.apply(Change::Pop), (0, vec![]));
+        assert!(recorder.calls().is_empty());
+    }
+
+    #[test]
+    fn replace_skips_the_scene_below() {
+        let mut recorder = three_scenes();
+
+        assert_eq!(recorder.apply(Change::Replace("menu")), (2, vec!["pause"]));
+        assert_eq!(
+            recorder.calls(),
+            [
+                ("pause", Hook::Exit),
+                ("menu", Hook::Init),
+                ("menu", Hook::Enter)
+            ]
+        );
+        assert_eq!(recorder.stack.scenes, ["title", "game", "menu"]);
+    }
+
+    #[test]
+    fn reset_only_exits_the_top_scene() {
+        let mut recorder = three_scenes();
+
+        assert_eq!(
+            recorder.apply(Change::Reset("credits")),
+            (0, vec!["title", "game", "pause"])
+        );
+        assert_eq!(
+            recorder.calls(),
+            [
+                ("pause", Hook::Exit),
+                ("credits", Hook::Init),
+                ("credits", Hook::Enter)
+            ]
+        );
+        assert_eq!(recorder.stack.scenes, ["credits"]);
+    }
+
+    #[test]
+    fn nothing_keeps_every_scene() {
+        let mut recorder = three_scenes();
+
+        assert_eq!(recorder.apply(Change::None), (3, vec![]));
+        assert!(recorder.calls().is_empty());
+    }
+
+    #[test]
+    fn nested_effects_are_flattened() {
+        let (change, effect) = Transition::Pop
+            .with_effect(Effect::Crossfade, 1.0)
+            .with_effect(Effect::Wipe(WipeDirection::Left), 0.5)
+            .into_parts();
+
+        assert!(matches!(change, Change::Pop));
+        assert_eq!(effect, Some((Effect::Wipe(WipeDirection::Left), 0.5)));
+
+        let (change, effect) = Transition::None.into_parts();
+        assert!(matches!(change, Change::None));
+        assert_eq!(effect, None);
+    }
+
+    /// Tells scenes apart without a context, through the only hook that doesn't need one.
+    struct Layer(bool);
+
+    impl Scene for Layer {
+        fn is_overlay(&self) -> bool {
+            self.0
+        }
+    }
+
+    #[test]
+    fn scenes_leaving_during_an_effect_are_still_listed() {
+        let mut scenes = SceneStack::new(Layer(false));
+        scenes.stack.scenes.push(Box::new(Layer(false)));
+        scenes.stack.scenes.push(Box::new(Layer(true)));
+
+        scenes.animation = Some(Animation {
+            effect: Effect::Crossfade,
+            duration: 1.0,
+            elapsed: 0.0,
+            common: 2,
+            leaving: vec![Box::new(Layer(false))],
+        });
+
+        let overlays: Vec<bool> = scenes
+            .scenes_mut()
+            .map(|scene| scene.is_overlay())
+            .collect();
+        assert_eq!(overlays, [false, true, false]);
+
+        scenes.animation = None;
+        assert_eq!(scenes.scenes_mut().count(), 2);
+    }
+}
@@ -0,0 +1,349 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::task::{Context as TaskContext, Poll, Wake, Waker};
+
+use crate::game::Context;
+
+/// Identifies a timer or a coroutine started through `Context`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TaskId(u64);
+
+type Callback = Box<dyn FnMut(&mut Context)>;
+
+type Task = Pin<Box<dyn Future<Output = ()>>>;
+
+struct Timer {
+    id: TaskId,
+    remaining: f32,
+    /// `None` for one-shot timers.
+    interval: Option<f32>,
+    callback: Option<Callback>,
+}
+
+#[derive(Default)]
+struct Clock {
+    time: Cell<f64>,
+    updates: Cell<u64>,
+}
+
+/// Handed to coroutines so they can wait across updates; see `Context::spawn`.
+#[derive(Clone)]
+pub struct Coroutine {
+    clock: Rc<Clock>,
+}
+
+impl Coroutine {
+    /// Resume after `seconds` of game time.
+    pub fn wait(&self, seconds: f32) -> Wait {
+        Wait {
+            clock: self.clock.clone(),
+            until_time: self.clock.time.get() + seconds as f64,
+            until_update: self.clock.updates.get() + 1,
+        }
+    }
+
+    /// Resume on the next update.
+    pub fn next_update(&self) -> Wait {
+        self.wait(0.0)
+    }
+}
+
+/// Future returned by `Coroutine::wait`. It always waits for at least one update.
+pub struct Wait {
+    clock: Rc<Clock>,
+    until_time: f64,
+    until_update: u64,
+}
+
+impl Future for Wait {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, _context: &mut TaskContext) -> Poll<()> {
+        if self.clock.updates.get() >= self.until_update && self.clock.time.get() >= self.until_time
+        {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Timers and coroutines of a `Context`, advanced right before every `Game::update`.
+#[derive(Default)]
+pub(crate) struct Scheduler {
+    next_id: u64,
+    timers: Vec<Timer>,
+    fired: HashMap<TaskId, u32>,
+    coroutines: Vec<(TaskId, Task)>,
+    clock: Rc<Clock>,
+}
+
+impl Scheduler {
+    pub(crate) fn add_timer(
+        &mut self,
+        delay: f32,
+        interval: Option<f32>,
+        callback: Option<Callback>,
+    ) -> TaskId {
+        let id = self.next_id();
+
+        self.timers.push(Timer {
+            id,
+            remaining: delay,
+            interval,
+            callback,
+        });
+
+        id
+    }
+
+    pub(crate) fn spawn<F, Fut>(&mut self, coroutine: F) -> TaskId
+    where
+        F: FnOnce(Coroutine) -> Fut,
+        Fut: Future<Output = ()> + 'static,
+    {
+        let id = self.next_id();
+        let mut future: Task = Box::pin(coroutine(Coroutine {
+            clock: self.clock.clone(),
+        }));
+
+        // Run until the first wait right away.
+        let waker = noop_waker();
+        let mut task_context = TaskContext::from_waker(&waker);
+
+        if future.as_mut().poll(&mut task_context).is_pending() {
+            self.coroutines.push((id, future));
+        }
+
+        id
+    }
+
+    pub(crate) fn cancel(&mut self, id: TaskId) -> bool {
+        let count = self.timers.len() + self.coroutines.len();
+
+        self.timers.retain(|timer| timer.id != id);
+        self.coroutines.retain(|(other, _)| *other != id);
+
+        count != self.timers.len() + self.coroutines.len()
+    }
+
+    pub(crate) fn is_active(&self, id: TaskId) -> bool {
+        self.timers.iter().any(|timer| timer.id == id)
+            || self.coroutines.iter().any(|(other, _)| *other == id)
+    }
+
+    pub(crate) fn fired(&self, id: TaskId) -> u32 {
+        self.fired.get(&id).copied().unwrap_or(0)
+    }
+
+    /// Advance the scheduler of the context by `delta`: fire timers, run their callbacks and
+    /// resume coroutines.
+    pub(crate) fn update(context: &mut Context, delta: f32) {
+        let callbacks = context.scheduler.advance(delta);
+
+        // Callbacks are taken out while they run, since they get the whole context.
+        for (id, count, mut callback) in callbacks {
+            for _ in 0..count {
+                callback(context);
+            }
+
+            let timers = &mut context.scheduler.timers;
+
+            if let Some(timer) = timers.iter_mut().find(|timer| timer.id == id) {
+                timer.callback = Some(callback);
+            }
+        }
+
+        context.scheduler.resume();
+    }
+
+    /// Move the clock forward and fire the timers that are due, returning the callbacks to run
+    /// along with how many times each timer fired.
+    fn advance(&mut self, delta: f32) -> Vec<(TaskId, u32, Callback)> {
+        self.fired.clear();
+        self.clock.time.set(self.clock.time.get() + delta as f64);
+        self.clock.updates.set(self.clock.updates.get() + 1);
+
+        let mut callbacks = Vec::new();
+
+        for timer in &mut self.timers {
+            timer.remaining -= delta;
+
+            let mut count = 0;
+
+            while timer.remaining <= 0.0 {
+                count += 1;
+
+                match timer.interval {
+                    Some(interval) if interval > 0.0 => timer.remaining += interval,
+                    // One-shot timers, and repeating ones firing at most once per update.
+                    _ => break,
+                }
+            }
+
+            if count > 0 {
+                self.fired.insert(timer.id, count);
+
+                if let Some(callback) = timer.callback.take() {
+                    callbacks.push((timer.id, count, callback));
+                }
+            }
+        }
+
+        self.timers
+            .retain(|timer| timer.interval.is_some() || timer.remaining > 0.0);
+
+        callbacks
+    }
+
+    /// Poll the coroutines, dropping the finished ones.
+    fn resume(&mut self) {
+        let waker = noop_waker();
+        let mut task_context = TaskContext::from_waker(&waker);
+
+        self.coroutines
+            .retain_mut(|(_, future)| future.as_mut().poll(&mut task_context).is_pending());
+    }
+
+    fn next_id(&mut self) -> TaskId {
+        self.next_id += 1;
+        TaskId(self.next_id)
+    }
+}
+
+/// Coroutines are polled on every update anyway, so they don't need waking up.
+struct NoopWaker;
+
+impl Wake for NoopWaker {
+    fn wake(self: Arc<Self>) {}
+}
+
+fn noop_waker() -> Waker {
+    Waker::from(Arc::new(NoopWaker))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ids of the timers firing in an update of `delta` seconds.
+    fn fire(scheduler: &mut Scheduler, delta: f32) -> Vec<(TaskId, u32)> {
+        scheduler.advance(delta);
+
+        let mut fired: Vec<_> = scheduler.fired.iter().map(|(&id, &n)| (id, n)).collect();
+        fired.sort();
+        fired
+    }
+
+    #[test]
+    fn timers_fire_in_order() {
+        let mut scheduler = Scheduler::default();
+        let late = scheduler.add_timer(1.0, None, None);
+        let early = scheduler.add_timer(0.25, None, None);
+        let repeating = scheduler.add_timer(0.5, Some(0.5), None);
+
+        assert_eq!(fire(&mut scheduler, 0.3), vec![(early, 1)]);
+        assert!(!scheduler.is_active(early));
+
+        assert_eq!(fire(&mut scheduler, 0.3), vec![(repeating, 1)]);
+        assert_eq!(fire(&mut scheduler, 0.5), vec![(late, 1), (repeating, 1)]);
+        assert!(!scheduler.is_active(late));
+
+        // Catching up on a long update.
+        assert_eq!(fire(&mut scheduler, 1.0), vec![(repeating, 2)]);
+        assert_eq!(scheduler.fired(repeating), 2);
+        assert!(scheduler.is_active(repeating));
+    }
+
+    #[test]
+    fn callbacks_come_in_the_order_timers_were_added() {
+        let mut scheduler = Scheduler::default();
+        let first = scheduler.add_timer(0.5, None, Some(Box::new(|_| {})));
+        let second = scheduler.add_timer(0.1, None, Some(Box::new(|_| {})));
+
+        let callbacks = scheduler.advance(1.0);
+        let ids: Vec<_> = callbacks.iter().map(|(id, _, _)| *id).collect();
+
+        assert_eq!(ids, vec![first, second]);
+    }
+
+    #[test]
+    fn cancelled_timers_dont_fire() {
+        let mut scheduler = Scheduler::default();
+        let timer = scheduler.add_timer(0.5, Some(0.5), None);
+
+        assert!(scheduler.cancel(timer));
+        assert!(!scheduler.cancel(timer));
+        assert!(!scheduler.is_active(timer));
+        assert!(fire(&mut scheduler, 1.0).is_empty());
+    }
+
+    #[test]
+    fn coroutines_wait_and_resume() {
+        let mut scheduler = Scheduler::default();
+        let steps = Rc::new(Cell::new(0));
+        let counter = steps.clone();
+
+        let id = scheduler.spawn(|coroutine| async move {
+            counter.set(1);
+            coroutine.wait(1.0).await;
+            counter.set(2);
+            coroutine.next_update().await;
+            counter.set(3);
+        });
+
+        // Runs until the first wait right away.
+        assert_eq!(steps.get(), 1);
+
+        for &(delta, expected) in &[(0.5, 1), (0.6, 2), (0.0, 3)] {
+            scheduler.advance(delta);
+            scheduler.resume();
+            assert_eq!(steps.get(), expected);
+        }
+
+        assert!(!scheduler.is_active(id));
+    }
+
+    #[test]
+    fn waits_last_at_least_one_update() {
+        let mut scheduler = Scheduler::default();
+        let done = Rc::new(Cell::new(false));
+        let flag = done.clone();
+
+        scheduler.spawn(|coroutine| async move {
+            coroutine.wait(0.0).await;
+            flag.set(true);
+        });
+
+        assert!(!done.get());
+        scheduler.advance(0.0);
+        scheduler.resume();
+        assert!(done.get());
+    }
+
+    #[test]
+    fn cancelled_coroutines_stop() {
+        let mut scheduler = Scheduler::default();
+        let steps = Rc::new(Cell::new(0));
+        let counter = steps.clone();
+
+        let id = scheduler.spawn(|coroutine| async move {
+            loop {
+                coroutine.next_update().await;
+                counter.set(counter.get() + 1);
+            }
+        });
+
+        scheduler.advance(0.1);
+        scheduler.resume();
+        assert!(scheduler.cancel(id));
+
+        scheduler.advance(0.1);
+        scheduler.resume();
+        assert_eq!(steps.get(), 1);
+    }
+}
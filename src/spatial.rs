@@ -0,0 +1,244 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use crate::utils::{Rectangle, Vec2};
+
+type Cell = (i32, i32);
+
+/// Broad-phase index storing the bounds of many objects in a uniform grid, so that finding the
+/// objects in some area doesn't require checking every single one of them.
+///
+/// `K` identifies the objects, e.g. an entity ID or an index into a `Vec`. The cell size should be
+/// about the size of a typical object: too small cells make large objects span many of them, while
+/// too large ones put many objects into the same cell.
+pub struct SpatialHash<K> {
+    cell_size: f32,
+    cells: HashMap<Cell, Vec<K>>,
+    bounds: HashMap<K, Rectangle>,
+}
+
+impl<K: Copy + Eq + Hash> SpatialHash<K> {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            bounds: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.bounds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bounds.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.bounds.clear();
+    }
+
+    pub fn get(&self, key: K) -> Option<&Rectangle> {
+        self.bounds.get(&key)
+    }
+
+    /// Insert a new object, or move an existing one.
+    pub fn insert(&mut self, key: K, bounds: Rectangle) {
+        if let Some(old_bounds) = self.bounds.get(&key).copied() {
+            if self.cell_range(&old_bounds) == self.cell_range(&bounds) {
+                self.bounds.insert(key, bounds);
+                return;
+            }
+
+            self.remove(key);
+        }
+
+        for cell in self.cells_of(&bounds) {
+            self.cells.entry(cell).or_default().push(key);
+        }
+
+        self.bounds.insert(key, bounds);
+    }
+
+    /// Same as `insert`; reads better when moving objects every frame.
+    pub fn update(&mut self, key: K, bounds: Rectangle) {
+        self.insert(key, bounds);
+    }
+
+    /// Returns the bounds the object had, if it was present.
+    pub fn remove(&mut self, key: K) -> Option<Rectangle> {
+        let bounds = self.bounds.remove(&key)?;
+
+        for cell in self.cells_of(&bounds) {
+            if let Some(keys) = self.cells.get_mut(&cell) {
+                keys.retain(|&other| other != key);
+
+                if keys.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+
+        Some(bounds)
+    }
+
+    /// Objects whose bounds overlap `region`.
+    pub fn query_region(&self, region: &Rectangle) -> Vec<K> {
+        let mut seen = HashSet::new();
+        let mut result = Vec::new();
+
+        for cell in self.cells_of(region) {
+            for &key in self.cells.get(&cell).into_iter().flatten() {
+                if seen.insert(key) && self.bounds[&key].collides_with(region) {
+                    result.push(key);
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Objects whose bounds contain `point`.
+    pub fn query_point(&self, point: Vec2) -> Vec<K> {
+        let cell = self.cell_of(point);
+
+        self.cells
+            .get(&cell)
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|key| self.bounds[key].contains(point))
+            .collect()
+    }
+
+    /// All pairs of objects with overlapping bounds, each reported once.
+    pub fn pairs(&self) -> Vec<(K, K)> {
+        let mut result = Vec::new();
+
+        for (&cell, keys) in &self.cells {
+            for (i, &a) in keys.iter().enumerate() {
+                let bounds_a = &self.bounds[&a];
+
+                for &b in &keys[i + 1..] {
+                    let bounds_b = &self.bounds[&b];
+
+                    if !bounds_a.collides_with(bounds_b) {
+                        continue;
+                    }
+
+                    // Pairs sharing several cells are only reported by the cell containing the
+                    // bottom left corner of their intersection.
+                    let corner = Vec2::new(bounds_a.x.max(bounds_b.x), bounds_a.y.max(bounds_b.y));
+
+                    if self.cell_of(corner) == cell {
+                        result.push((a, b));
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
+    fn cell_of(&self, point: Vec2) -> Cell {
+        (
+            (point.x / self.cell_size).floor() as i32,
+            (point.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    fn cell_range(&self, bounds: &Rectangle) -> (Cell, Cell) {
+        let min = self.cell_of(Vec2::new(bounds.x, bounds.y));
+        let max = self.cell_of(Vec2::new(bounds.x + bounds.w, bounds.y + bounds.h));
+        (min, max)
+    }
+
+    fn cells_of(&self, bounds: &Rectangle) -> impl Iterator<Item = Cell> {
+        let (min, max) = self.cell_range(bounds);
+        (min.0..=max.0).flat_map(move |x| (min.1..=max.1).map(move |y| (x, y)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(mut keys: Vec<u32>) -> Vec<u32> {
+        keys.sort_unstable();
+        keys
+    }
+
+    fn hash() -> SpatialHash<u32> {
+        let mut hash = SpatialHash::new(10.0);
+        hash.insert(1, Rectangle::new(0.0, 0.0, 5.0, 5.0));
+        hash.insert(2, Rectangle::new(3.0, 3.0, 20.0, 20.0));
+        hash.insert(3, Rectangle::new(50.0, 50.0, 5.0, 5.0));
+        hash
+    }
+
+    #[test]
+    fn insert_and_query() {
+        let hash = hash();
+
+        assert_eq!(hash.len(), 3);
+        assert_eq!(hash.get(3), Some(&Rectangle::new(50.0, 50.0, 5.0, 5.0)));
+        assert_eq!(
+            sorted(hash.query_region(&Rectangle::new(-5.0, -5.0, 10.0, 10.0))),
+            vec![1, 2]
+        );
+        assert_eq!(
+            hash.query_region(&Rectangle::new(15.0, 15.0, 40.0, 1.0)),
+            vec![2]
+        );
+        assert!(hash
+            .query_region(&Rectangle::new(100.0, 0.0, 5.0, 5.0))
+            .is_empty());
+
+        assert_eq!(sorted(hash.query_point(Vec2::new(4.0, 4.0))), vec![1, 2]);
+        assert_eq!(hash.query_point(Vec2::new(52.0, 52.0)), vec![3]);
+        assert!(hash.query_point(Vec2::new(30.0, 30.0)).is_empty());
+    }
+
+    #[test]
+    fn move_objects() {
+        let mut hash = hash();
+
+        // Within the same cells, then across cells.
+        hash.update(3, Rectangle::new(51.0, 51.0, 5.0, 5.0));
+        assert_eq!(hash.query_point(Vec2::new(55.5, 55.5)), vec![3]);
+
+        hash.update(3, Rectangle::new(-20.0, -20.0, 5.0, 5.0));
+        assert!(hash.query_point(Vec2::new(52.0, 52.0)).is_empty());
+        assert_eq!(hash.query_point(Vec2::new(-18.0, -18.0)), vec![3]);
+        assert_eq!(hash.len(), 3);
+    }
+
+    #[test]
+    fn remove_objects() {
+        let mut hash = hash();
+
+        assert_eq!(hash.remove(2), Some(Rectangle::new(3.0, 3.0, 20.0, 20.0)));
+        assert_eq!(hash.remove(2), None);
+        assert_eq!(hash.len(), 2);
+        assert_eq!(hash.query_point(Vec2::new(4.0, 4.0)), vec![1]);
+        assert!(hash.query_point(Vec2::new(20.0, 20.0)).is_empty());
+        assert!(hash.pairs().is_empty());
+    }
+
+    #[test]
+    fn pairs_are_reported_once() {
+        let mut hash = hash();
+        hash.insert(4, Rectangle::new(15.0, 15.0, 20.0, 2.0));
+
+        let pairs: Vec<_> = hash
+            .pairs()
+            .into_iter()
+            .map(|(a, b)| (a.min(b), a.max(b)))
+            .collect();
+
+        assert_eq!(pairs.len(), 2);
+        assert!(pairs.contains(&(1, 2)));
+        assert!(pairs.contains(&(2, 4)));
+    }
+}
@@ -0,0 +1,436 @@
+use std::f32::consts::PI;
+
+use crate::audio::Sound;
+use crate::error::Error;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Waveform {
+    Square,
+    Sawtooth,
+    Sine,
+    Triangle,
+    Noise,
+}
+
+/// Generates retro sound effects from a few parameters, like sfxr. Start from one of the presets
+/// or from `new`, tweak it, then turn it into a `Sound` to play with `Audio::play_sound`, or save
+/// it as a WAV file.
+#[derive(Copy, Clone, Debug)]
+pub struct Synth {
+    pub waveform: Waveform,
+    /// Seconds to go from silence to full volume.
+    pub attack: f32,
+    /// Seconds spent at full volume.
+    pub sustain: f32,
+    /// Extra volume at the start of the sustain, fading out until its end; 1.0 doubles it.
+    pub punch: f32,
+    /// Seconds to go from full volume back to silence.
+    pub decay: f32,
+    /// Starting frequency, in hertz.
+    pub frequency: f32,
+    /// The sound stops once sliding below this frequency, if it's above zero.
+    pub min_frequency: f32,
+    /// Frequency change, in octaves per second.
+    pub slide: f32,
+    /// Change of the slide, in octaves per second squared.
+    pub delta_slide: f32,
+    /// Ratio of the frequency up and down, e.g. 0.1 goes 10% up and down.
+    pub vibrato_depth: f32,
+    /// Vibrations per second.
+    pub vibrato_speed: f32,
+    /// For square waves, the part of every period spent up; 0.5 is a regular square wave.
+    pub duty: f32,
+    /// Change of the duty per second.
+    pub duty_sweep: f32,
+    /// The frequency gets multiplied by it once, after `arpeggio_delay` seconds; 1.0 does
+    /// nothing.
+    pub arpeggio: f32,
+    pub arpeggio_delay: f32,
+    pub volume: f32,
+    /// For noise, which is the same for the same seed.
+    pub seed: u64,
+}
+
+impl Synth {
+    pub fn new(waveform: Waveform) -> Self {
+        Self {
+            waveform,
+            attack: 0.0,
+            sustain: 0.1,
+            punch: 0.0,
+            decay: 0.2,
+            frequency: 440.0,
+            min_frequency: 0.0,
+            slide: 0.0,
+            delta_slide: 0.0,
+            vibrato_depth: 0.0,
+            vibrato_speed: 0.0,
+            duty: 0.5,
+            duty_sweep: 0.0,
+            arpeggio: 1.0,
+            arpeggio_delay: 0.0,
+            volume: 0.5,
+            seed: 1,
+        }
+    }
+
+    /// A coin or item pickup: a short square beep jumping up once.
+    pub fn pickup() -> Self {
+        Self::new(Waveform::Square)
+            .with_envelope(0.0, 0.05, 0.25)
+            .with_punch(0.5)
+            .with_frequency(1000.0)
+            .with_arpeggio(1.5, 0.06)
+    }
+
+    /// A laser shot: a sawtooth sliding down fast.
+    pub fn laser() -> Self {
+        Self::new(Waveform::Sawtooth)
+            .with_envelope(0.0, 0.08, 0.15)
+            .with_frequency(1600.0)
+            .with_slide(-8.0, 0.0)
+            .with_min_frequency(100.0)
+    }
+
+    pub fn explosion() -> Self {
+        Self::new(Waveform::Noise)
+            .with_envelope(0.0, 0.15, 0.5)
+            .with_punch(0.8)
+            .with_frequency(300.0)
+            .with_slide(-1.5, 0.0)
+    }
+
+    /// A power-up: a square wave sliding up with some vibrato.
+    pub fn powerup() -> Self {
+        Self::new(Waveform::Square)
+            .with_envelope(0.0, 0.2, 0.3)
+            .with_frequency(300.0)
+            .with_slide(2.0, 0.0)
+            .with_vibrato(0.1, 12.0)
+            .with_duty(0.3, 0.5)
+    }
+
+    /// Getting hit: a short burst of noise sliding down.
+    pub fn hit() -> Self {
+        Self::new(Waveform::Noise)
+            .with_envelope(0.0, 0.03, 0.15)
+            .with_frequency(1200.0)
+            .with_slide(-4.0, 0.0)
+    }
+
+    pub fn jump() -> Self {
+        Self::new(Waveform::Square)
+            .with_envelope(0.0, 0.1, 0.15)
+            .with_frequency(350.0)
+            .with_slide(3.0, 0.0)
+            .with_duty(0.25, 0.0)
+    }
+
+    /// A menu blip.
+    pub fn blip() -> Self {
+        Self::new(Waveform::Square)
+            .with_envelope(0.0, 0.04, 0.04)
+            .with_frequency(880.0)
+    }
+
+    pub fn with_envelope(mut self, attack: f32, sustain: f32, decay: f32) -> Self {
+        self.attack = attack;
+        self.sustain = sustain;
+        self.decay = decay;
+        self
+    }
+
+    pub fn with_punch(mut self, punch: f32) -> Self {
+        self.punch = punch;
+        self
+    }
+
+    pub fn with_frequency(mut self, frequency: f32) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    pub fn with_min_frequency(mut self, min_frequency: f32) -> Self {
+        self.min_frequency = min_frequency;
+        self
+    }
+
+    pub fn with_slide(mut self, slide: f32, delta_slide: f32) -> Self {
+        self.slide = slide;
+        self.delta_slide = delta_slide;
+        self
+    }
+
+    pub fn with_vibrato(mut self, depth: f32, speed: f32) -> Self {
+        self.vibrato_depth = depth;
+        self.vibrato_speed = speed;
+        self
+    }
+
+    pub fn with_duty(mut self, duty: f32, sweep: f32) -> Self {
+        self.duty = duty;
+        self.duty_sweep = sweep;
+        self
+    }
+
+    pub fn with_arpeggio(mut self, multiplier: f32, delay: f32) -> Self {
+        self.arpeggio = multiplier;
+        self.arpeggio_delay = delay;
+        self
+    }
+
+    pub fn with_volume(mut self, volume: f32) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// A variation of the sound, with the frequencies and timings randomly changed by up to
+    /// `amount`, e.g. 0.1 for 10%. The same seed always gives the same variation.
+    pub fn mutate(mut self, amount: f32, seed: u64) -> Self {
+        let mut random = Random::new(seed);
+        let mut vary = |value: &mut f32| *value *= 1.0 + random.next_signed() * amount;
+
+        vary(&mut self.attack);
+        vary(&mut self.sustain);
+        vary(&mut self.decay);
+        vary(&mut self.frequency);
+        vary(&mut self.slide);
+        vary(&mut self.vibrato_speed);
+        vary(&mut self.arpeggio_delay);
+
+        self.seed = seed;
+        self
+    }
+
+    /// In seconds, unless the frequency slides below `min_frequency` before the end.
+    pub fn duration(&self) -> f32 {
+        self.attack.max(0.0) + self.sustain.max(0.0) + self.decay.max(0.0)
+    }
+
+    /// Mono samples, between -1.0 and 1.0.
+    pub fn render(&self, sample_rate: u32) -> Vec<f32> {
+        let rate = sample_rate as f32;
+        let length = (self.duration() * rate) as usize;
+        let mut samples = Vec::with_capacity(length);
+
+        let mut random = Random::new(self.seed);
+        let mut noise = random.next_signed();
+        let mut noise_step = 0;
+
+        let mut frequency = self.frequency;
+        let mut slide = self.slide;
+        let mut arpeggio_done = self.arpeggio == 1.0;
+        let mut phase = 0.0f32;
+
+        for i in 0..length {
+            let time = i as f32 / rate;
+
+            if !arpeggio_done && time >= self.arpeggio_delay {
+                frequency *= self.arpeggio;
+                arpeggio_done = true;
+            }
+
+            frequency *= 2f32.powf(slide / rate);
+            slide += self.delta_slide / rate;
+
+            if self.min_frequency > 0.0 && frequency < self.min_frequency {
+                break;
+            }
+
+            let vibrato = 1.0 + self.vibrato_depth * (2.0 * PI * self.vibrato_speed * time).sin();
+            let current = (frequency * vibrato).clamp(1.0, rate / 2.0);
+
+            phase = (phase + current / rate).fract();
+
+            let value = match self.waveform {
+                Waveform::Square => {
+                    let duty = (self.duty + self.duty_sweep * time).clamp(0.0, 1.0);
+
+                    if phase < duty {
+                        1.0
+                    } else {
+                        -1.0
+                    }
+                }
+                Waveform::Sawtooth => phase * 2.0 - 1.0,
+                Waveform::Sine => (phase * 2.0 * PI).sin(),
+                Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+                Waveform::Noise => {
+                    // A new random value 32 times per period, so that the noise has a pitch.
+                    let step = (phase * 32.0) as usize;
+
+                    if step != noise_step {
+                        noise_step = step;
+                        noise = random.next_signed();
+                    }
+
+                    noise
+                }
+            };
+
+            // Punch can take loud sounds past full volume.
+            samples.push((value * self.envelope(time) * self.volume).clamp(-1.0, 1.0));
+        }
+
+        samples
+    }
+
+    fn envelope(&self, time: f32) -> f32 {
+        if time < self.attack {
+            time / self.attack
+        } else if time < self.attack + self.sustain {
+            let progress = (time - self.attack) / self.sustain;
+            1.0 + self.punch * (1.0 - progress)
+        } else {
+            let progress = (time - self.attack - self.sustain) / self.decay;
+            (1.0 - progress).max(0.0)
+        }
+    }
+
+    pub fn to_sound(&self, sample_rate: u32) -> Sound {
+        Sound::from_mono(&self.render(sample_rate), sample_rate)
+    }
+
+    /// Write the sound as a 16-bit mono WAV file.
+    pub fn save_wav(&self, filename: &str, sample_rate: u32) -> Result<(), Error> {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+
+        let mut writer = hound::WavWriter::create(filename, spec)?;
+
+        for sample in self.render(sample_rate) {
+            let sample = (sample * i16::MAX as f32) as i16;
+            writer.write_sample(sample)?;
+        }
+
+        writer.finalize()?;
+        Ok(())
+    }
+}
+
+/// Xorshift, good enough for noise.
+struct Random(u64);
+
+impl Random {
+    fn new(seed: u64) -> Self {
+        // Zero would only ever give zeros.
+        Self(seed.max(1))
+    }
+
+    /// Between -1.0 and 1.0.
+    fn next_signed(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+
+        (self.0 >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 8000;
+
+    const WAVEFORMS: [Waveform; 5] = [
+        Waveform::Square,
+        Waveform::Sawtooth,
+        Waveform::Sine,
+        Waveform::Triangle,
+        Waveform::Noise,
+    ];
+
+    #[test]
+    fn same_seed_same_sound() {
+        let synth = Synth::explosion().mutate(0.2, 7);
+
+        assert_eq!(synth.render(RATE), synth.render(RATE));
+        assert_eq!(
+            Synth::explosion().mutate(0.2, 7).render(RATE),
+            synth.render(RATE)
+        );
+        assert_ne!(synth.with_seed(8).render(RATE), synth.render(RATE));
+    }
+
+    #[test]
+    fn min_frequency_cuts_the_sound_short() {
+        // An octave down in 0.1 seconds, out of 0.3.
+        let synth = Synth::new(Waveform::Sine)
+            .with_frequency(800.0)
+            .with_slide(-10.0, 0.0);
+
+        assert_eq!(synth.render(RATE).len(), 2400);
+
+        let length = synth.with_min_frequency(400.0).render(RATE).len();
+        assert!((799..=801).contains(&length), "{}", length);
+    }
+
+    #[test]
+    fn envelope_fades_out_at_the_end() {
+        let synth = Synth::new(Waveform::Square)
+            .with_envelope(0.05, 0.1, 0.2)
+            .with_punch(0.5)
+            .with_volume(1.0);
+
+        assert_eq!(synth.envelope(0.0), 0.0);
+        assert_eq!(synth.envelope(0.05), 1.5);
+        assert_eq!(synth.envelope(synth.duration()), 0.0);
+
+        let samples = synth.render(RATE);
+        assert_eq!(samples.len(), (synth.duration() * RATE as f32) as usize);
+
+        // Within a sample of the end.
+        let last = samples.last().unwrap().abs();
+        assert!(last <= 1.0 / (0.2 * RATE as f32) + 1e-4, "{}", last);
+    }
+
+    #[test]
+    fn samples_stay_in_range() {
+        for waveform in WAVEFORMS {
+            let synth = Synth::new(waveform)
+                .with_punch(2.0)
+                .with_volume(1.0)
+                .with_vibrato(0.5, 10.0);
+            let samples = synth.render(RATE);
+
+            assert!(samples.iter().any(|&sample| sample.abs() > 0.9));
+            assert!(
+                samples.iter().all(|sample| (-1.0..=1.0).contains(sample)),
+                "{:?}",
+                waveform
+            );
+        }
+    }
+
+    #[test]
+    fn save_wav() {
+        let synth = Synth::explosion();
+        let path = std::env::temp_dir().join(format!("gamelib-synth-{}.wav", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        synth.save_wav(path, 8000).unwrap();
+
+        let reader = hound::WavReader::open(path).unwrap();
+        assert_eq!(reader.spec().sample_rate, 8000);
+        assert_eq!(reader.len() as usize, synth.render(8000).len());
+        std::fs::remove_file(path).unwrap();
+
+        let missing = std::env::temp_dir()
+            .join("gamelib-missing")
+            .join("synth.wav");
+        assert!(matches!(
+            synth.save_wav(missing.to_str().unwrap(), 8000),
+            Err(Error::Io(_))
+        ));
+    }
+}
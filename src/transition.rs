@@ -0,0 +1,172 @@
+use glium::framebuffer::SimpleFrameBuffer;
+use glium::texture::SrgbTexture2d;
+use glium::{Display, DrawParameters, Program};
+
+use crate::error::Error;
+use crate::render::{Canvas, Model};
+use crate::utils::{Color, Mat4};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum WipeDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// How a `SceneStack` goes from one scene to another; see `Transition::with_effect`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Effect {
+    /// Fade the outgoing scene out to the color, then the incoming one in from it.
+    Fade(Color),
+    /// Blend the outgoing scene into the incoming one.
+    Crossfade,
+    /// Uncover the incoming scene with an edge moving in the given direction.
+    Wipe(WipeDirection),
+    /// Uncover the incoming scene with a circle growing from the center of the screen.
+    Iris,
+}
+
+/// Renders scenes into textures and blends them together.
+pub(crate) struct Compositor {
+    program: Program,
+    quad: Model,
+    from: SrgbTexture2d,
+    to: SrgbTexture2d,
+}
+
+impl Compositor {
+    pub(crate) fn new(display: &Display, (width, height): (u32, u32)) -> Result<Self, Error> {
+        Ok(Self {
+            program: Program::from_source(display, VERTEX_SHADER, FRAGMENT_SHADER, None)?,
+            quad: Model::square(display, None)?,
+            from: SrgbTexture2d::empty(display, width, height)?,
+            to: SrgbTexture2d::empty(display, width, height)?,
+        })
+    }
+
+    /// Recreate the textures if the window was resized.
+    pub(crate) fn resize(
+        &mut self,
+        display: &Display,
+        (width, height): (u32, u32),
+    ) -> Result<(), Error> {
+        if self.from.dimensions() != (width, height) {
+            *self = Self::new(display, (width, height))?;
+        }
+
+        Ok(())
+    }
+
+    /// A canvas drawing into the texture of the outgoing scene.
+    pub(crate) fn outgoing_canvas<'a>(
+        &'a self,
+        display: &Display,
+        program: &'a Program,
+    ) -> Result<Canvas<'a>, Error> {
+        let framebuffer = SimpleFrameBuffer::new(display, &self.from)?;
+        Ok(Canvas::offscreen(framebuffer, program))
+    }
+
+    /// A canvas drawing into the texture of the incoming scene.
+    pub(crate) fn incoming_canvas<'a>(
+        &'a self,
+        display: &Display,
+        program: &'a Program,
+    ) -> Result<Canvas<'a>, Error> {
+        let framebuffer = SimpleFrameBuffer::new(display, &self.to)?;
+        Ok(Canvas::offscreen(framebuffer, program))
+    }
+
+    /// Cover the whole canvas with both scenes blended together; `progress` goes from 0.0, only
+    /// the outgoing scene, to 1.0, only the incoming one.
+    pub(crate) fn composite(&self, canvas: &mut Canvas, effect: Effect, progress: f32) {
+        let (width, height) = canvas.dimensions();
+
+        let (kind, color, direction) = match effect {
+            Effect::Fade(color) => (0, color, (0.0, 0.0)),
+            Effect::Crossfade => (1, (0.0, 0.0, 0.0), (0.0, 0.0)),
+            Effect::Wipe(direction) => {
+                let direction = match direction {
+                    WipeDirection::Left => (-1.0, 0.0),
+                    WipeDirection::Right => (1.0, 0.0),
+                    WipeDirection::Up => (0.0, 1.0),
+                    WipeDirection::Down => (0.0, -1.0),
+                };
+
+                (2, (0.0, 0.0, 0.0), direction)
+            }
+            Effect::Iris => (3, (0.0, 0.0, 0.0), (0.0, 0.0)),
+        };
+
+        canvas.draw_model(
+            &self.quad,
+            &self.program,
+            &uniform! {
+                mvp: Mat4::scale(2.0, 2.0),
+                from: &self.from,
+                to: &self.to,
+                effect: kind,
+                progress: progress.clamp(0.0, 1.0),
+                color: color,
+                direction: direction,
+                aspect: width as f32 / height as f32,
+            },
+            &DrawParameters::default(),
+        );
+    }
+}
+
+const VERTEX_SHADER: &str = "
+#version 140
+
+uniform mat4 mvp;
+
+in vec2 position;
+in vec2 tex_coords;
+
+out vec2 v_tex_coords;
+
+void main() {
+    gl_Position = vec4(position, 0.0, 1.0) * mvp;
+    v_tex_coords = tex_coords;
+}
+";
+
+const FRAGMENT_SHADER: &str = "
+#version 140
+
+uniform sampler2D from;
+uniform sampler2D to;
+uniform int effect;
+uniform float progress;
+uniform vec3 color;
+uniform vec2 direction;
+uniform float aspect;
+
+in vec2 v_tex_coords;
+
+out vec4 f_color;
+
+void main() {
+    vec4 a = texture(from, v_tex_coords);
+    vec4 b = texture(to, v_tex_coords);
+    vec4 solid = vec4(color, 1.0);
+
+    if (effect == 0) {
+        f_color = progress < 0.5
+            ? mix(a, solid, progress * 2.0)
+            : mix(solid, b, progress * 2.0 - 1.0);
+    } else if (effect == 1) {
+        f_color = mix(a, b, progress);
+    } else if (effect == 2) {
+        // 0.0 at the edge the wipe starts from, 1.0 at the opposite one.
+        float distance = dot(v_tex_coords - 0.5, direction) + 0.5;
+        f_color = distance < progress ? b : a;
+    } else {
+        vec2 offset = (v_tex_coords - 0.5) * vec2(aspect, 1.0);
+        float radius = length(vec2(aspect, 1.0) * 0.5) * progress;
+        f_color = length(offset) < radius ? b : a;
+    }
+}
+";
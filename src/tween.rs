@@ -0,0 +1,729 @@
+use std::f32::consts::PI;
+
+use crate::utils::{Color, Vec2};
+
+/// Values that can be interpolated by tweens.
+pub trait Lerp: Copy {
+    /// `self` when `t` is 0.0, `to` when it's 1.0. Easings like `BackOut` go slightly outside of
+    /// that range.
+    fn lerp(self, to: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Lerp for Vec2 {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        Vec2::lerp(self, to, t)
+    }
+}
+
+impl Lerp for Color {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        (
+            self.0.lerp(to.0, t),
+            self.1.lerp(to.1, t),
+            self.2.lerp(to.2, t),
+        )
+    }
+}
+
+/// Easing curves; see https://easings.net for how they look.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    ElasticIn,
+    ElasticOut,
+    ElasticInOut,
+    BounceIn,
+    BounceOut,
+    BounceInOut,
+    BackIn,
+    BackOut,
+    BackInOut,
+}
+
+impl Easing {
+    /// Map the linear progress `t`, from 0.0 to 1.0, through the curve.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::QuadInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::CubicInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Easing::ElasticIn => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else {
+                    -(2.0f32.powf(10.0 * t - 10.0)) * ((t * 10.0 - 10.75) * ELASTIC).sin()
+                }
+            }
+            Easing::ElasticOut => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else {
+                    2.0f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * ELASTIC).sin() + 1.0
+                }
+            }
+            Easing::ElasticInOut => {
+                let period = 2.0 * PI / 4.5;
+
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else if t < 0.5 {
+                    -(2.0f32.powf(20.0 * t - 10.0) * ((20.0 * t - 11.125) * period).sin()) / 2.0
+                } else {
+                    2.0f32.powf(-20.0 * t + 10.0) * ((20.0 * t - 11.125) * period).sin() / 2.0 + 1.0
+                }
+            }
+            Easing::BounceIn => 1.0 - bounce_out(1.0 - t),
+            Easing::BounceOut => bounce_out(t),
+            Easing::BounceInOut => {
+                if t < 0.5 {
+                    (1.0 - bounce_out(1.0 - 2.0 * t)) / 2.0
+                } else {
+                    (1.0 + bounce_out(2.0 * t - 1.0)) / 2.0
+                }
+            }
+            Easing::BackIn => (BACK + 1.0) * t * t * t - BACK * t * t,
+            Easing::BackOut => 1.0 + (BACK + 1.0) * (t - 1.0).powi(3) + BACK * (t - 1.0).powi(2),
+            Easing::BackInOut => {
+                let overshoot = BACK * 1.525;
+
+                if t < 0.5 {
+                    (2.0 * t).powi(2) * ((overshoot + 1.0) * 2.0 * t - overshoot) / 2.0
+                } else {
+                    ((2.0 * t - 2.0).powi(2) * ((overshoot + 1.0) * (t * 2.0 - 2.0) + overshoot)
+                        + 2.0)
+                        / 2.0
+                }
+            }
+        }
+    }
+}
+
+/// How far `Back` easings overshoot.
+const BACK: f32 = 1.70158;
+
+/// Angular frequency of `Elastic` easings.
+const ELASTIC: f32 = 2.0 * PI / 3.0;
+
+fn bounce_out(t: f32) -> f32 {
+    let (strength, width) = (7.5625, 2.75);
+
+    if t < 1.0 / width {
+        strength * t * t
+    } else if t < 2.0 / width {
+        let t = t - 1.5 / width;
+        strength * t * t + 0.75
+    } else if t < 2.5 / width {
+        let t = t - 2.25 / width;
+        strength * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / width;
+        strength * t * t + 0.984375
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Repeat {
+    /// Play this many more times after the first.
+    Times(u32),
+    Forever,
+}
+
+/// Animates a value from one end to another. Call `update` with `Context::delta` every update,
+/// or put it in a `Sequence` or `Parallel` group with `Tween::on`.
+#[derive(Copy, Clone, Debug)]
+pub struct Tween<T> {
+    pub from: T,
+    pub to: T,
+    /// Of a single play, in seconds.
+    pub duration: f32,
+    pub easing: Easing,
+    /// Waited once, before the first play.
+    pub delay: f32,
+    pub repeat: Repeat,
+    /// Play every other repetition backwards, from `to` to `from`.
+    pub yoyo: bool,
+    elapsed: f32,
+}
+
+impl<T: Lerp> Tween<T> {
+    pub fn new(from: T, to: T, duration: f32) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            easing: Easing::Linear,
+            delay: 0.0,
+            repeat: Repeat::Times(0),
+            yoyo: false,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    pub fn with_delay(mut self, delay: f32) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    pub fn with_repeat(mut self, times: u32) -> Self {
+        self.repeat = Repeat::Times(times);
+        self
+    }
+
+    pub fn repeat_forever(mut self) -> Self {
+        self.repeat = Repeat::Forever;
+        self
+    }
+
+    pub fn with_yoyo(mut self) -> Self {
+        self.yoyo = true;
+        self
+    }
+
+    /// Advance the time and return the new value.
+    pub fn update(&mut self, delta: f32) -> T {
+        self.advance(delta);
+        self.value()
+    }
+
+    pub fn value(&self) -> T {
+        let time = (self.elapsed - self.delay).max(0.0);
+
+        let (play, progress) = if self.duration <= 0.0 {
+            (self.last_play().unwrap_or(0), 1.0)
+        } else {
+            let play = (time / self.duration).floor() as u32;
+
+            match self.last_play() {
+                Some(last) if play > last => (last, 1.0),
+                _ => (play, time / self.duration - play as f32),
+            }
+        };
+
+        let progress = if self.yoyo && play % 2 == 1 {
+            1.0 - progress
+        } else {
+            progress
+        };
+
+        self.from.lerp(self.to, self.easing.apply(progress))
+    }
+
+    pub fn is_finished(&self) -> bool {
+        match self.total_duration() {
+            Some(total) => self.elapsed >= total,
+            None => false,
+        }
+    }
+
+    /// Including the delay and repetitions; `None` when repeating forever.
+    pub fn total_duration(&self) -> Option<f32> {
+        let plays = self.last_play()? + 1;
+        Some(self.delay + self.duration.max(0.0) * plays as f32)
+    }
+
+    /// Start over, including the delay.
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+    }
+
+    /// Write the value into a property of `S` instead, to animate it as part of a group.
+    pub fn on<S, F>(self, setter: F) -> Property<S, T>
+    where
+        F: FnMut(&mut S, T) + 'static,
+    {
+        Property {
+            tween: self,
+            setter: Box::new(setter),
+        }
+    }
+
+    /// Returns the part of `delta` left over after finishing.
+    fn advance(&mut self, delta: f32) -> f32 {
+        self.elapsed += delta;
+
+        match self.total_duration() {
+            Some(total) if self.elapsed > total => {
+                let leftover = self.elapsed - total;
+                self.elapsed = total;
+                leftover
+            }
+            _ => 0.0,
+        }
+    }
+
+    fn last_play(&self) -> Option<u32> {
+        match self.repeat {
+            Repeat::Times(times) => Some(times),
+            Repeat::Forever => None,
+        }
+    }
+}
+
+/// Whether a group should play again after `plays` repetitions.
+fn repeats(repeat: Repeat, plays: u32) -> bool {
+    match repeat {
+        Repeat::Times(times) => plays < times,
+        Repeat::Forever => true,
+    }
+}
+
+/// Something animating the properties of an `S` over time: a `Property`, a `Sequence` or a
+/// `Parallel` group.
+pub trait Animation<S> {
+    /// Advance the time, updating `target`. Returns the part of `delta` left over after finishing.
+    fn advance(&mut self, delta: f32, target: &mut S) -> f32;
+    fn is_finished(&self) -> bool;
+    fn reset(&mut self);
+}
+
+type Setter<S, T> = Box<dyn FnMut(&mut S, T)>;
+
+/// A tween bound to a property; see `Tween::on`.
+pub struct Property<S, T> {
+    pub tween: Tween<T>,
+    setter: Setter<S, T>,
+}
+
+impl<S, T: Lerp> Animation<S> for Property<S, T> {
+    fn advance(&mut self, delta: f32, target: &mut S) -> f32 {
+        let leftover = self.tween.advance(delta);
+        (self.setter)(target, self.tween.value());
+        leftover
+    }
+
+    fn is_finished(&self) -> bool {
+        self.tween.is_finished()
+    }
+
+    fn reset(&mut self) {
+        self.tween.reset();
+    }
+}
+
+struct Wait {
+    duration: f32,
+    elapsed: f32,
+}
+
+impl<S> Animation<S> for Wait {
+    fn advance(&mut self, delta: f32, _target: &mut S) -> f32 {
+        self.elapsed += delta;
+        let leftover = (self.elapsed - self.duration).max(0.0);
+        self.elapsed = self.elapsed.min(self.duration);
+        leftover
+    }
+
+    fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    fn reset(&mut self) {
+        self.elapsed = 0.0;
+    }
+}
+
+/// Plays animations one after the other.
+pub struct Sequence<S> {
+    pub repeat: Repeat,
+    steps: Vec<Box<dyn Animation<S>>>,
+    current: usize,
+    plays: u32,
+}
+
+impl<S> Sequence<S> {
+    pub fn new() -> Self {
+        Self {
+            repeat: Repeat::Times(0),
+            steps: Vec::new(),
+            current: 0,
+            plays: 0,
+        }
+    }
+
+    pub fn then<A: Animation<S> + 'static>(mut self, animation: A) -> Self {
+        self.steps.push(Box::new(animation));
+        self
+    }
+
+    /// Do nothing for a while before the next step.
+    pub fn then_wait(self, seconds: f32) -> Self {
+        self.then(Wait {
+            duration: seconds,
+            elapsed: 0.0,
+        })
+    }
+
+    pub fn with_repeat(mut self, times: u32) -> Self {
+        self.repeat = Repeat::Times(times);
+        self
+    }
+
+    pub fn repeat_forever(mut self) -> Self {
+        self.repeat = Repeat::Forever;
+        self
+    }
+
+    pub fn update(&mut self, delta: f32, target: &mut S) {
+        self.advance(delta, target);
+    }
+}
+
+impl<S> Default for Sequence<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Animation<S> for Sequence<S> {
+    fn advance(&mut self, mut delta: f32, target: &mut S) -> f32 {
+        let mut delta_at_start = delta;
+
+        loop {
+            if let Some(step) = self.steps.get_mut(self.current) {
+                delta = step.advance(delta, target);
+
+                if !step.is_finished() {
+                    return 0.0;
+                }
+
+                self.current += 1;
+                continue;
+            }
+
+            // Stop instead of looping forever if a whole play takes no time.
+            if !repeats(self.repeat, self.plays) || delta == delta_at_start {
+                self.current = self.steps.len();
+                return delta;
+            }
+
+            self.plays += 1;
+            self.current = 0;
+            delta_at_start = delta;
+
+            for step in &mut self.steps {
+                step.reset();
+            }
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.current >= self.steps.len() && !repeats(self.repeat, self.plays)
+    }
+
+    fn reset(&mut self) {
+        self.current = 0;
+        self.plays = 0;
+
+        for step in &mut self.steps {
+            step.reset();
+        }
+    }
+}
+
+/// Plays animations at the same time; it's finished once all of them are.
+pub struct Parallel<S> {
+    pub repeat: Repeat,
+    animations: Vec<Box<dyn Animation<S>>>,
+    plays: u32,
+}
+
+impl<S> Parallel<S> {
+    pub fn new() -> Self {
+        Self {
+            repeat: Repeat::Times(0),
+            animations: Vec::new(),
+            plays: 0,
+        }
+    }
+
+    pub fn with<A: Animation<S> + 'static>(mut self, animation: A) -> Self {
+        self.animations.push(Box::new(animation));
+        self
+    }
+
+    pub fn with_repeat(mut self, times: u32) -> Self {
+        self.repeat = Repeat::Times(times);
+        self
+    }
+
+    pub fn repeat_forever(mut self) -> Self {
+        self.repeat = Repeat::Forever;
+        self
+    }
+
+    pub fn update(&mut self, delta: f32, target: &mut S) {
+        self.advance(delta, target);
+    }
+}
+
+impl<S> Default for Parallel<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Animation<S> for Parallel<S> {
+    fn advance(&mut self, mut delta: f32, target: &mut S) -> f32 {
+        loop {
+            // What's left over after the longest animation finished.
+            let leftover = self
+                .animations
+                .iter_mut()
+                .map(|animation| animation.advance(delta, target))
+                .fold(delta, f32::min);
+
+            let finished = self
+                .animations
+                .iter()
+                .all(|animation| animation.is_finished());
+
+            if !finished || !repeats(self.repeat, self.plays) || leftover == delta {
+                return if finished { leftover } else { 0.0 };
+            }
+
+            self.plays += 1;
+            delta = leftover;
+
+            for animation in &mut self.animations {
+                animation.reset();
+            }
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.animations
+            .iter()
+            .all(|animation| animation.is_finished())
+            && !repeats(self.repeat, self.plays)
+    }
+
+    fn reset(&mut self) {
+        self.plays = 0;
+
+        for animation in &mut self.animations {
+            animation.reset();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EASINGS: [Easing; 16] = [
+        Easing::Linear,
+        Easing::QuadIn,
+        Easing::QuadOut,
+        Easing::QuadInOut,
+        Easing::CubicIn,
+        Easing::CubicOut,
+        Easing::CubicInOut,
+        Easing::ElasticIn,
+        Easing::ElasticOut,
+        Easing::ElasticInOut,
+        Easing::BounceIn,
+        Easing::BounceOut,
+        Easing::BounceInOut,
+        Easing::BackIn,
+        Easing::BackOut,
+        Easing::BackInOut,
+    ];
+
+    #[test]
+    fn easings_start_at_zero_and_end_at_one() {
+        for &easing in EASINGS.iter() {
+            assert!(easing.apply(0.0).abs() < 1e-5, "{:?} at 0", easing);
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-5, "{:?} at 1", easing);
+
+            // Progress outside of the range is clamped.
+            assert!(easing.apply(-1.0).abs() < 1e-5, "{:?} before 0", easing);
+            assert!(
+                (easing.apply(2.0) - 1.0).abs() < 1e-5,
+                "{:?} after 1",
+                easing
+            );
+        }
+    }
+
+    #[test]
+    fn tweens_end_on_the_target() {
+        for &easing in EASINGS.iter() {
+            let mut tween = Tween::new(2.0, 5.0, 1.0).with_easing(easing);
+
+            assert_eq!(tween.value(), 2.0);
+            assert!((tween.update(1.5) - 5.0).abs() < 1e-5, "{:?}", easing);
+            assert!(tween.is_finished());
+        }
+    }
+
+    /// Values every quarter of a second, which floats represent exactly.
+    fn steps(tween: &mut Tween<f32>, count: usize) -> Vec<f32> {
+        (0..count).map(|_| tween.update(0.25)).collect()
+    }
+
+    #[derive(Default, Debug, PartialEq)]
+    struct Target {
+        x: f32,
+        y: f32,
+    }
+
+    fn x(from: f32, to: f32, duration: f32) -> Property<Target, f32> {
+        Tween::new(from, to, duration).on(|target: &mut Target, x| target.x = x)
+    }
+
+    fn y(from: f32, to: f32, duration: f32) -> Property<Target, f32> {
+        Tween::new(from, to, duration).on(|target: &mut Target, y| target.y = y)
+    }
+
+    #[test]
+    fn delay_holds_the_start() {
+        let mut tween = Tween::new(0.0, 4.0, 1.0).with_delay(0.5);
+
+        assert_eq!(tween.total_duration(), Some(1.5));
+        assert_eq!(steps(&mut tween, 6), [0.0, 0.0, 1.0, 2.0, 3.0, 4.0]);
+        assert!(tween.is_finished());
+
+        tween.reset();
+        assert_eq!(tween.value(), 0.0);
+        assert!(!tween.is_finished());
+    }
+
+    #[test]
+    fn repeat_starts_over() {
+        let mut tween = Tween::new(0.0, 4.0, 1.0).with_repeat(1);
+
+        assert_eq!(tween.total_duration(), Some(2.0));
+        assert_eq!(
+            steps(&mut tween, 8),
+            [1.0, 2.0, 3.0, 0.0, 1.0, 2.0, 3.0, 4.0]
+        );
+        assert!(tween.is_finished());
+
+        // Stays on the end.
+        assert_eq!(tween.update(1.0), 4.0);
+    }
+
+    #[test]
+    fn yoyo_plays_every_other_repetition_backwards() {
+        let mut tween = Tween::new(0.0, 4.0, 1.0).with_repeat(2).with_yoyo();
+
+        assert_eq!(
+            steps(&mut tween, 12),
+            [1.0, 2.0, 3.0, 4.0, 3.0, 2.0, 1.0, 0.0, 1.0, 2.0, 3.0, 4.0]
+        );
+        assert!(tween.is_finished());
+    }
+
+    #[test]
+    fn repeating_forever_never_finishes() {
+        let mut tween = Tween::new(0.0, 4.0, 1.0).repeat_forever().with_yoyo();
+
+        assert_eq!(tween.total_duration(), None);
+        assert_eq!(tween.update(100.25), 1.0);
+        assert_eq!(tween.update(1.0), 3.0);
+        assert!(!tween.is_finished());
+    }
+
+    #[test]
+    fn sequence_plays_steps_one_after_the_other() {
+        let mut sequence = Sequence::new()
+            .then(x(0.0, 1.0, 0.5))
+            .then_wait(0.25)
+            .then(y(0.0, 2.0, 0.5));
+        let mut target = Target::default();
+
+        let mut values = Vec::new();
+
+        for _ in 0..5 {
+            sequence.update(0.25, &mut target);
+            values.push((target.x, target.y));
+        }
+
+        assert_eq!(
+            values,
+            [(0.5, 0.0), (1.0, 0.0), (1.0, 0.0), (1.0, 1.0), (1.0, 2.0)]
+        );
+        assert!(sequence.is_finished());
+    }
+
+    #[test]
+    fn sequence_carries_time_over_to_the_next_steps() {
+        let mut sequence = Sequence::new()
+            .then(x(0.0, 1.0, 0.5))
+            .then_wait(0.25)
+            .then(y(0.0, 2.0, 0.5))
+            .with_repeat(1);
+        let mut target = Target::default();
+
+        sequence.update(1.0, &mut target);
+        assert_eq!(target, Target { x: 1.0, y: 1.0 });
+
+        // Into the second play.
+        sequence.update(0.5, &mut target);
+        assert_eq!(target, Target { x: 0.5, y: 2.0 });
+        assert!(!sequence.is_finished());
+
+        sequence.update(1.0, &mut target);
+        assert_eq!(target, Target { x: 1.0, y: 2.0 });
+        assert!(sequence.is_finished());
+    }
+
+    #[test]
+    fn parallel_finishes_with_the_longest_animation() {
+        let mut parallel = Parallel::new()
+            .with(x(0.0, 1.0, 0.5))
+            .with(y(0.0, 2.0, 1.0))
+            .with_repeat(1);
+        let mut target = Target::default();
+
+        parallel.update(0.5, &mut target);
+        assert_eq!(target, Target { x: 1.0, y: 1.0 });
+
+        parallel.update(0.25, &mut target);
+        assert_eq!(target, Target { x: 1.0, y: 1.5 });
+
+        // Both start over together.
+        parallel.update(0.5, &mut target);
+        assert_eq!(target, Target { x: 0.5, y: 0.5 });
+        assert!(!parallel.is_finished());
+
+        parallel.update(1.0, &mut target);
+        assert_eq!(target, Target { x: 1.0, y: 2.0 });
+        assert!(parallel.is_finished());
+    }
+}
@@ -1,66 +1,219 @@
-use glium::uniforms::{AsUniformValue, UniformValue};
+use crate::collision::{Contact, RayHit, Shape};
 
-use std::ops::Mul;
+pub use crate::math::{Mat3, Mat4, Vec2};
 
-pub type Vec2 = (f32, f32);
 pub type Color = (f32, f32, f32);
 
-#[derive(Copy, Clone)]
-pub struct Mat4(pub [[f32; 4]; 4]);
-
-impl Mat4 {
-    pub fn identity() -> Self {
-        Self([
-            [1.0, 0.0, 0.0, 0.0],
-            [0.0, 1.0, 0.0, 0.0],
-            [0.0, 0.0, 1.0, 0.0],
-            [0.0, 0.0, 0.0, 1.0],
-        ])
-    }
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Rectangle {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
 }
 
-impl Mul for Mat4 {
-    type Output = Self;
+impl Rectangle {
+    pub fn new(x: f32, y: f32, w: f32, h: f32) -> Self {
+        Self { x, y, w, h }
+    }
+
+    /// Rectangles merely touching each other don't collide.
+    pub fn collides_with(&self, other: &Rectangle) -> bool {
+        self.x + self.w > other.x
+            && self.x < other.x + other.w
+            && self.y + self.h > other.y
+            && self.y < other.y + other.h
+    }
+
+    /// Points on the edges don't count as contained.
+    pub fn contains(&self, point: Vec2) -> bool {
+        point.x > self.x
+            && point.x < self.x + self.w
+            && point.y > self.y
+            && point.y < self.y + self.h
+    }
+
+    /// See `Shape::contact`.
+    pub fn contact(&self, other: &Rectangle) -> Option<Contact> {
+        Shape::Rectangle(*self).contact(&Shape::Rectangle(*other))
+    }
+
+    /// Swept AABB test: move this rectangle by `motion` and report when it first hits `other`.
+    ///
+    /// Unlike `collides_with`, rectangles touching each other and moving towards each other count
+    /// as a hit at fraction 0, while sliding along each other doesn't. Rectangles already
+    /// overlapping count as a hit at fraction 0 only if `motion` pushes them deeper into each
+    /// other, with the normal of the face they're closest to getting out through.
+    pub fn sweep(&self, motion: Vec2, other: &Rectangle) -> Option<RayHit> {
+        let (fraction, normal) = if self.collides_with(other) {
+            // Already overlapping: only a hit if moving deeper along the shortest way out.
+            let normal = self.separation_normal(other);
+
+            if motion.dot(normal) >= 0.0 {
+                return None;
+            }
+
+            (0.0, normal)
+        } else {
+            self.sweep_entry(motion, other)?
+        };
 
-    fn mul(self, other: Self) -> Self {
-        let mut result = self;
+        let moved = Rectangle::new(
+            self.x + motion.x * fraction,
+            self.y + motion.y * fraction,
+            self.w,
+            self.h,
+        );
 
-        for i in 0..4 {
-            for j in 0..4 {
-                result.0[i][j] = 0.0;
+        // Middle of the touching faces.
+        let point = if normal.x != 0.0 {
+            let top = (moved.y + moved.h).min(other.y + other.h);
+            let bottom = moved.y.max(other.y);
+            let x = if normal.x < 0.0 {
+                other.x
+            } else {
+                other.x + other.w
+            };
+            Vec2::new(x, (top + bottom) / 2.0)
+        } else {
+            let right = (moved.x + moved.w).min(other.x + other.w);
+            let left = moved.x.max(other.x);
+            let y = if normal.y < 0.0 {
+                other.y
+            } else {
+                other.y + other.h
+            };
+            Vec2::new((left + right) / 2.0, y)
+        };
+
+        Some(RayHit {
+            point,
+            normal,
+            fraction,
+        })
+    }
 
-                for n in 0..4 {
-                    result.0[i][j] += self.0[i][n] * other.0[n][j];
+    /// When moving by `motion` first brings this rectangle into contact with `other`, and the
+    /// normal of the face it meets. The rectangles mustn't overlap already.
+    fn sweep_entry(&self, motion: Vec2, other: &Rectangle) -> Option<(f32, Vec2)> {
+        // Shrink this rectangle to a point and grow the other one by its size instead.
+        let min = Vec2::new(other.x - self.w, other.y - self.h);
+        let max = Vec2::new(other.x + other.w, other.y + other.h);
+        let origin = Vec2::new(self.x, self.y);
+
+        let mut entry = f32::NEG_INFINITY;
+        let mut exit = f32::INFINITY;
+        let mut normal = Vec2::ZERO;
+
+        let axes = [
+            (origin.x, motion.x, min.x, max.x, Vec2::new(1.0, 0.0)),
+            (origin.y, motion.y, min.y, max.y, Vec2::new(0.0, 1.0)),
+        ];
+
+        for &(start, speed, min, max, axis) in &axes {
+            if speed == 0.0 {
+                if start <= min || start >= max {
+                    return None;
                 }
+
+                continue;
+            }
+
+            let (near, far, axis_normal) = if speed > 0.0 {
+                ((min - start) / speed, (max - start) / speed, -axis)
+            } else {
+                ((max - start) / speed, (min - start) / speed, axis)
+            };
+
+            if near > entry {
+                entry = near;
+                normal = axis_normal;
             }
+
+            exit = exit.min(far);
+        }
+
+        if entry >= exit || exit <= 0.0 || entry > 1.0 {
+            return None;
         }
 
-        result
+        Some((entry.max(0.0), normal))
     }
-}
 
-impl AsUniformValue for Mat4 {
-    fn as_uniform_value(&self) -> UniformValue {
-        AsUniformValue::as_uniform_value(&self.0)
+    /// Normal of the face of `other` closest to pushing this overlapping rectangle out of it.
+    fn separation_normal(&self, other: &Rectangle) -> Vec2 {
+        let depths = [
+            (self.x + self.w - other.x, Vec2::new(-1.0, 0.0)),
+            (other.x + other.w - self.x, Vec2::new(1.0, 0.0)),
+            (self.y + self.h - other.y, Vec2::new(0.0, -1.0)),
+            (other.y + other.h - self.y, Vec2::new(0.0, 1.0)),
+        ];
+
+        depths
+            .iter()
+            .min_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|&(_, normal)| normal)
+            .unwrap()
     }
-}
 
-pub struct Rectangle {
-    pub x: f32,
-    pub y: f32,
-    pub w: f32,
-    pub h: f32,
+    pub fn center(&self) -> Vec2 {
+        Vec2::new(self.x + self.w / 2.0, self.y + self.h / 2.0)
+    }
 }
 
-impl Rectangle {
-    pub fn new(x: f32, y: f32, w: f32, h: f32) -> Self {
-        Self { x, y, w, h }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wall() -> Rectangle {
+        Rectangle::new(10.0, 0.0, 2.0, 10.0)
     }
 
-    pub fn collides_with(&self, other: &Rectangle) -> bool {
-        self.x + self.w >= other.x
-            && self.x <= other.x + other.w
-            && self.y + self.h >= other.y
-            && self.y <= other.y + other.h
+    #[test]
+    fn sweep_hits_the_near_face() {
+        let hit = Rectangle::new(0.0, 4.0, 2.0, 2.0)
+            .sweep(Vec2::new(16.0, 0.0), &wall())
+            .unwrap();
+
+        assert_eq!(hit.fraction, 0.5);
+        assert_eq!(hit.normal, Vec2::new(-1.0, 0.0));
+        assert_eq!(hit.point, Vec2::new(10.0, 5.0));
+    }
+
+    #[test]
+    fn sweep_misses() {
+        let rectangle = Rectangle::new(0.0, 4.0, 2.0, 2.0);
+
+        // Too short, away from it, and past it.
+        assert!(rectangle.sweep(Vec2::new(4.0, 0.0), &wall()).is_none());
+        assert!(rectangle.sweep(Vec2::new(-16.0, 0.0), &wall()).is_none());
+        assert!(rectangle.sweep(Vec2::new(16.0, 20.0), &wall()).is_none());
+        assert!(rectangle.sweep(Vec2::ZERO, &wall()).is_none());
+    }
+
+    #[test]
+    fn sweep_touching() {
+        let touching = Rectangle::new(8.0, 4.0, 2.0, 2.0);
+
+        let hit = touching.sweep(Vec2::new(1.0, 0.0), &wall()).unwrap();
+        assert_eq!(hit.fraction, 0.0);
+        assert_eq!(hit.normal, Vec2::new(-1.0, 0.0));
+
+        // Sliding along the wall.
+        assert!(touching.sweep(Vec2::new(0.0, 1.0), &wall()).is_none());
+    }
+
+    #[test]
+    fn sweep_overlapping() {
+        // One unit into the left face of the wall.
+        let overlapping = Rectangle::new(9.0, 4.0, 2.0, 2.0);
+
+        let hit = overlapping.sweep(Vec2::new(1.0, 0.0), &wall()).unwrap();
+        assert_eq!(hit.fraction, 0.0);
+        assert_eq!(hit.normal, Vec2::new(-1.0, 0.0));
+
+        assert!(overlapping.sweep(Vec2::ZERO, &wall()).is_none());
+        assert!(overlapping.sweep(Vec2::new(-1.0, 0.0), &wall()).is_none());
+        assert!(overlapping.sweep(Vec2::new(0.0, 1.0), &wall()).is_none());
     }
 }
@@ -0,0 +1,479 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// Every file gamelib reads, textures, sounds and assets, goes through the mounted sources,
+/// last mounted first. Paths not found in any of them are read from the working directory,
+/// then from the directory of the executable.
+///
+/// Mounts are shared by the whole program, since files are also read from the loading and audio
+/// threads.
+static MOUNTS: RwLock<Vec<Box<dyn Mount>>> = RwLock::new(Vec::new());
+
+pub trait ReadSeek: Read + Seek + Send {}
+
+impl<R: Read + Seek + Send> ReadSeek for R {}
+
+/// Somewhere files can be read from.
+pub trait Mount: Send + Sync {
+    /// `None` if the file isn't there.
+    fn read(&self, path: &str) -> Option<io::Result<Vec<u8>>>;
+
+    /// For reading files bit by bit, e.g. streamed music.
+    fn open(&self, path: &str) -> Option<io::Result<Box<dyn ReadSeek>>> {
+        let bytes = self.read(path)?;
+        Some(bytes.map(|bytes| Box::new(Cursor::new(bytes)) as Box<dyn ReadSeek>))
+    }
+
+    /// Where the file is on disk, if it's a file of its own, for hot reloading.
+    fn local_path(&self, _path: &str) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// Files in a directory, e.g. `Directory::next_to_executable("assets")`.
+pub struct Directory {
+    root: PathBuf,
+}
+
+impl Directory {
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+
+    /// A directory relative to the executable instead of the working directory.
+    pub fn next_to_executable<P: AsRef<Path>>(path: P) -> Self {
+        Self::new(executable_directory().join(path))
+    }
+}
+
+impl Mount for Directory {
+    fn read(&self, path: &str) -> Option<io::Result<Vec<u8>>> {
+        let path = self.local_path(path)?;
+        Some(fs::read(path))
+    }
+
+    fn open(&self, path: &str) -> Option<io::Result<Box<dyn ReadSeek>>> {
+        let path = self.local_path(path)?;
+        Some(File::open(path).map(|file| Box::new(file) as Box<dyn ReadSeek>))
+    }
+
+    fn local_path(&self, path: &str) -> Option<PathBuf> {
+        let path = self.root.join(path);
+
+        if path.is_file() {
+            Some(path)
+        } else {
+            None
+        }
+    }
+}
+
+const ARCHIVE_MAGIC: &[u8; 4] = b"GLPK";
+const ARCHIVE_VERSION: u32 = 1;
+
+/// Files packed into a single one with `Archive::pack`, or the `gamelib-pack` tool.
+///
+/// The format is little-endian: the magic bytes `GLPK`, a version and the number of files as
+/// `u32`s, then for every file the length of its path as a `u32`, the path in UTF-8, its offset
+/// from the start of the archive and its length as `u64`s. The files themselves come last.
+pub struct Archive {
+    filename: PathBuf,
+    /// Offset and length of every file.
+    files: HashMap<String, (u64, u64)>,
+}
+
+impl Archive {
+    /// Only reads the list of files; their contents are read when needed.
+    pub fn open<P: AsRef<Path>>(filename: P) -> io::Result<Self> {
+        let filename = filename.as_ref().to_path_buf();
+        let mut file = io::BufReader::new(File::open(&filename)?);
+        let size = file.get_ref().metadata()?.len();
+
+        let mut magic = [0; 4];
+        file.read_exact(&mut magic)?;
+
+        if &magic != ARCHIVE_MAGIC {
+            return Err(invalid_data("not an archive"));
+        }
+
+        if read_u32(&mut file)? != ARCHIVE_VERSION {
+            return Err(invalid_data("unsupported archive version"));
+        }
+
+        let count = read_u32(&mut file)?;
+        let mut files = HashMap::new();
+
+        for _ in 0..count {
+            // Check lengths before allocating anything, in case the archive is corrupted.
+            let path_length = read_u32(&mut file)?;
+
+            if path_length as u64 > size {
+                return Err(invalid_data("path longer than the archive"));
+            }
+
+            let mut path = vec![0; path_length as usize];
+            file.read_exact(&mut path)?;
+
+            let path = String::from_utf8(path).map_err(|_| invalid_data("invalid path"))?;
+            let offset = read_u64(&mut file)?;
+            let length = read_u64(&mut file)?;
+
+            check_bounds(offset, length, size)?;
+            files.insert(path, (offset, length));
+        }
+
+        Ok(Self { filename, files })
+    }
+
+    /// Pack files into an archive, keeping their paths as given. Directories are packed with
+    /// everything in them, except for the archive itself if it's there. Returns how many files
+    /// were packed.
+    pub fn pack<P: AsRef<Path>, Q: AsRef<Path>>(filename: P, inputs: &[Q]) -> io::Result<usize> {
+        let filename = filename.as_ref();
+        let mut files = Vec::new();
+
+        for input in inputs {
+            collect_files(input.as_ref(), &mut files)?;
+        }
+
+        // An archive from a previous run would otherwise be packed into the new one.
+        if let Ok(output) = fs::canonicalize(filename) {
+            files.retain(|file| fs::canonicalize(file).map_or(true, |file| file != output));
+        }
+
+        files.sort();
+        files.dedup();
+
+        let paths: Vec<String> = files
+            .iter()
+            .map(|file| normalize(&file.to_string_lossy()))
+            .collect();
+
+        let header_size = 12 + paths.iter().map(|path| 20 + path.len()).sum::<usize>();
+        let mut output = io::BufWriter::new(File::create(filename)?);
+
+        output.write_all(ARCHIVE_MAGIC)?;
+        output.write_all(&ARCHIVE_VERSION.to_le_bytes())?;
+        output.write_all(&(files.len() as u32).to_le_bytes())?;
+
+        let mut offset = header_size as u64;
+        let mut lengths = Vec::with_capacity(files.len());
+
+        for (file, path) in files.iter().zip(&paths) {
+            let length = fs::metadata(file)?.len();
+            lengths.push(length);
+
+            output.write_all(&(path.len() as u32).to_le_bytes())?;
+            output.write_all(path.as_bytes())?;
+            output.write_all(&offset.to_le_bytes())?;
+            output.write_all(&length.to_le_bytes())?;
+
+            offset += length;
+        }
+
+        for (file, &length) in files.iter().zip(&lengths) {
+            let copied = io::copy(&mut File::open(file)?, &mut output)?;
+
+            if copied != length {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("{} changed while packing it", file.display()),
+                ));
+            }
+        }
+
+        output.flush()?;
+        Ok(files.len())
+    }
+
+    /// Paths of the packed files.
+    pub fn files(&self) -> impl Iterator<Item = &str> {
+        self.files.keys().map(String::as_str)
+    }
+}
+
+impl Mount for Archive {
+    fn read(&self, path: &str) -> Option<io::Result<Vec<u8>>> {
+        let &(offset, length) = self.files.get(&normalize(path))?;
+
+        let read = || {
+            let mut file = File::open(&self.filename)?;
+
+            // The archive may have been replaced since it was opened.
+            check_bounds(offset, length, file.metadata()?.len())?;
+            file.seek(SeekFrom::Start(offset))?;
+
+            let mut bytes = vec![0; length as usize];
+            file.read_exact(&mut bytes)?;
+            Ok(bytes)
+        };
+
+        Some(read())
+    }
+}
+
+/// Files built into the executable; see `embed_assets!`.
+#[derive(Default)]
+pub struct Embedded {
+    files: HashMap<String, &'static [u8]>,
+}
+
+impl Embedded {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, path: &str, bytes: &'static [u8]) -> Self {
+        self.files.insert(normalize(path), bytes);
+        self
+    }
+}
+
+impl Mount for Embedded {
+    fn read(&self, path: &str) -> Option<io::Result<Vec<u8>>> {
+        let bytes = self.files.get(&normalize(path))?;
+        Some(Ok(bytes.to_vec()))
+    }
+}
+
+/// Build files into the executable, by their paths relative to the crate root, and return an
+/// `Embedded` to mount.
+#[macro_export]
+macro_rules! embed_assets {
+    ($($path:expr),* $(,)?) => {
+        $crate::vfs::Embedded::new()
+            $(.with($path, include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/", $path))))*
+    };
+}
+
+/// Files are looked for in it before the ones mounted earlier.
+pub fn mount<M: Mount + 'static>(mount: M) {
+    MOUNTS.write().unwrap().push(Box::new(mount));
+}
+
+/// Go back to reading from the working directory only.
+pub fn unmount_all() {
+    MOUNTS.write().unwrap().clear();
+}
+
+pub fn read(path: &str) -> io::Result<Vec<u8>> {
+    find(path, |mount| mount.read(path))
+}
+
+pub fn open(path: &str) -> io::Result<Box<dyn ReadSeek>> {
+    find(path, |mount| mount.open(path))
+}
+
+/// Where the file is on disk, unless it's packed or embedded.
+pub fn local_path(path: &str) -> Option<PathBuf> {
+    find(path, |mount| mount.local_path(path).map(Ok)).ok()
+}
+
+fn find<T>(path: &str, mut get: impl FnMut(&dyn Mount) -> Option<io::Result<T>>) -> io::Result<T> {
+    for mount in MOUNTS.read().unwrap().iter().rev() {
+        if let Some(result) = get(mount.as_ref()) {
+            return result;
+        }
+    }
+
+    let fallbacks = [Directory::new("."), Directory::new(executable_directory())];
+
+    for mount in &fallbacks {
+        if let Some(result) = get(mount) {
+            return result;
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} not found", path),
+    ))
+}
+
+fn executable_directory() -> PathBuf {
+    env::current_exe()
+        .ok()
+        .and_then(|executable| executable.parent().map(Path::to_path_buf))
+        .unwrap_or_default()
+}
+
+/// Forward slashes, without a leading `./`, so that packed and embedded files are found however
+/// their paths are written.
+fn normalize(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let mut path = path.as_str();
+
+    while let Some(rest) = path.strip_prefix("./") {
+        path = rest;
+    }
+
+    path.to_string()
+}
+
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    if path.is_dir() {
+        for entry in fs::read_dir(path)? {
+            collect_files(&entry?.path(), files)?;
+        }
+    } else {
+        files.push(path.to_path_buf());
+    }
+
+    Ok(())
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Fails unless a packed file lies within an archive of `size` bytes.
+fn check_bounds(offset: u64, length: u64, size: u64) -> io::Result<()> {
+    match offset.checked_add(length) {
+        Some(end) if end <= size => Ok(()),
+        _ => Err(invalid_data("packed file past the end of the archive")),
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty directory of its own for every test.
+    fn temp_dir(name: &str) -> PathBuf {
+        let directory =
+            env::temp_dir().join(format!("gamelib-vfs-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    /// An archive header listing a single file.
+    fn header(path_length: u32, offset: u64, length: u64) -> Vec<u8> {
+        let mut bytes = ARCHIVE_MAGIC.to_vec();
+        bytes.extend(&ARCHIVE_VERSION.to_le_bytes());
+        bytes.extend(&1u32.to_le_bytes());
+        bytes.extend(&path_length.to_le_bytes());
+        bytes.extend(b"a.txt");
+        bytes.extend(&offset.to_le_bytes());
+        bytes.extend(&length.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn pack_and_read() {
+        let directory = temp_dir("pack");
+        fs::create_dir(directory.join("data")).unwrap();
+        fs::write(directory.join("data").join("a.txt"), "first").unwrap();
+        fs::write(directory.join("data").join("b.txt"), "second").unwrap();
+
+        let archive = directory.join("data.glpk");
+        assert_eq!(
+            Archive::pack(&archive, &[directory.join("data")]).unwrap(),
+            2
+        );
+
+        let archive = Archive::open(&archive).unwrap();
+        let path = normalize(&directory.join("data").join("b.txt").to_string_lossy());
+
+        assert_eq!(archive.files().count(), 2);
+        assert_eq!(archive.read(&path).unwrap().unwrap(), b"second");
+        assert!(archive.read("missing.txt").is_none());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn pack_skips_the_archive_itself() {
+        let directory = temp_dir("repack");
+        fs::write(directory.join("a.txt"), "first").unwrap();
+
+        let archive = directory.join("data.glpk");
+        assert_eq!(Archive::pack(&archive, &[&directory]).unwrap(), 1);
+        assert_eq!(Archive::pack(&archive, &[&directory]).unwrap(), 1);
+        assert_eq!(Archive::open(&archive).unwrap().files().count(), 1);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn corrupted_archives_are_rejected() {
+        let directory = temp_dir("corrupted");
+        let archive = directory.join("data.glpk");
+
+        let valid = header(5, 37, 2);
+        let mut bytes = valid.clone();
+        bytes.extend(b"hi");
+        fs::write(&archive, &bytes).unwrap();
+        assert_eq!(
+            Archive::open(&archive)
+                .unwrap()
+                .read("a.txt")
+                .unwrap()
+                .unwrap(),
+            b"hi"
+        );
+
+        // A path too long to be real, then files past the end of the archive.
+        for bytes in [
+            header(u32::MAX, 37, 2),
+            header(5, 37, 3),
+            header(5, u64::MAX, 2),
+        ]
+        .iter()
+        {
+            fs::write(&archive, bytes).unwrap();
+            let error = Archive::open(&archive).err().unwrap();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+
+        // Truncated after being opened.
+        fs::write(&archive, &bytes).unwrap();
+        let opened = Archive::open(&archive).unwrap();
+        fs::write(&archive, &valid).unwrap();
+        assert!(opened.read("a.txt").unwrap().is_err());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn directories_only_have_their_files() {
+        let directory = temp_dir("directory");
+        fs::write(directory.join("a.txt"), "first").unwrap();
+
+        let mount = Directory::new(&directory);
+        assert_eq!(mount.read("a.txt").unwrap().unwrap(), b"first");
+        assert_eq!(mount.local_path("a.txt"), Some(directory.join("a.txt")));
+        assert!(mount.read("missing.txt").is_none());
+        assert!(mount.local_path(".").is_none());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn embedded_paths_are_normalized() {
+        let mount = Embedded::new().with("./data\\a.txt", b"first");
+
+        assert_eq!(mount.read("data/a.txt").unwrap().unwrap(), b"first");
+        assert_eq!(mount.read("./data/a.txt").unwrap().unwrap(), b"first");
+        assert!(mount.read("data/b.txt").is_none());
+        assert!(mount.local_path("data/a.txt").is_none());
+    }
+}
@@ -0,0 +1,80 @@
+use glium::glutin::window::{Fullscreen, Icon, Window};
+
+use crate::assets::{AssetError, AssetErrorKind};
+use crate::vfs;
+
+pub use glium::glutin::window::CursorIcon;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum WindowMode {
+    Windowed,
+    /// A window covering the whole monitor, quick to switch to and from.
+    Borderless,
+    /// Takes over the monitor, changing its resolution to the window size if it supports it.
+    Exclusive,
+}
+
+/// What to pass winit for the mode, on the monitor the window is on.
+pub(crate) fn fullscreen(window: &Window, mode: WindowMode) -> Option<Fullscreen> {
+    let monitor = window.current_monitor();
+
+    match mode {
+        WindowMode::Windowed => None,
+        WindowMode::Borderless => Some(Fullscreen::Borderless(monitor)),
+        WindowMode::Exclusive => {
+            let size = window.inner_size();
+
+            // The window size if the monitor has it, otherwise its biggest mode.
+            monitor
+                .video_modes()
+                .max_by_key(|video_mode| {
+                    let matches = video_mode.size() == size;
+                    let area = video_mode.size().width * video_mode.size().height;
+                    (
+                        matches,
+                        area,
+                        video_mode.bit_depth(),
+                        video_mode.refresh_rate(),
+                    )
+                })
+                .map(Fullscreen::Exclusive)
+        }
+    }
+}
+
+pub(crate) fn load_icon(filename: &str) -> Result<Icon, AssetError> {
+    let error = |kind| AssetError {
+        path: filename.to_string(),
+        kind,
+    };
+
+    let bytes = vfs::read(filename).map_err(|io| error(AssetErrorKind::Io(io)))?;
+    let image = image::load_from_memory(&bytes)
+        .map_err(|decode| error(AssetErrorKind::Decode(decode.to_string())))?
+        .to_rgba8();
+
+    let (width, height) = image.dimensions();
+
+    Icon::from_rgba(image.into_raw(), width, height)
+        .map_err(|icon| error(AssetErrorKind::Create(icon.to_string())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn icons_load_from_images() {
+        let image = image::open("examples/textures/ch.png").unwrap().to_rgba8();
+        let (width, height) = image.dimensions();
+        let icon = load_icon("examples/textures/ch.png").unwrap();
+
+        assert_eq!(
+            format!("{:?}", icon),
+            format!(
+                "{:?}",
+                Icon::from_rgba(image.into_raw(), width, height).unwrap()
+            )
+        );
+    }
+}